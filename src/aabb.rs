@@ -0,0 +1,72 @@
+use std::mem::swap;
+
+use crate::ray::Ray;
+use crate::vec3::*;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    minimum: Point3,
+    maximum: Point3,
+}
+
+impl Aabb {
+    pub fn new(minimum: Point3, maximum: Point3) -> Self {
+        Self { minimum, maximum }
+    }
+
+    pub fn min(&self) -> Point3 {
+        self.minimum
+    }
+
+    pub fn max(&self) -> Point3 {
+        self.maximum
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (self.minimum + self.maximum)
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let d = self.maximum - self.minimum;
+        2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+
+    pub fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> bool {
+        self.clip(r, t_min, t_max).is_some()
+    }
+
+    /// The part of `[t_min, t_max]` for which the ray is inside the box.
+    pub fn clip(&self, r: Ray, mut t_min: f32, mut t_max: f32) -> Option<(f32, f32)> {
+        let origin = r.origin();
+        let direction = r.direction();
+        for a in 0..3 {
+            let inv_d = 1.0 / direction[a];
+            let mut t0 = (self.minimum[a] - origin[a]) * inv_d;
+            let mut t1 = (self.maximum[a] - origin[a]) * inv_d;
+            if inv_d < 0.0 {
+                swap(&mut t0, &mut t1);
+            }
+            // Written so that a NaN slab (origin on the plane, direction parallel) is ignored.
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+}
+
+pub fn surrounding_box(box0: Aabb, box1: Aabb) -> Aabb {
+    let small = Point3::new(
+        box0.min().x().min(box1.min().x()),
+        box0.min().y().min(box1.min().y()),
+        box0.min().z().min(box1.min().z()),
+    );
+    let big = Point3::new(
+        box0.max().x().max(box1.max().x()),
+        box0.max().y().max(box1.max().y()),
+        box0.max().z().max(box1.max().z()),
+    );
+    Aabb::new(small, big)
+}
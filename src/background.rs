@@ -0,0 +1,294 @@
+//! What a ray sees once it leaves the scene.
+
+use std::f32::consts::PI;
+
+use crate::image::Image;
+use crate::rtweekend::random;
+use crate::sampling::Distribution2D;
+use crate::vec3::*;
+
+/// Radiance arriving from infinitely far away, by direction.
+pub trait Background: Send + Sync {
+    /// `direction` need not be normalized.
+    fn color(&self, direction: Vec3) -> Color;
+
+    /// Pick a unit direction towards the background, favouring its bright
+    /// parts, together with its solid angle density. `None` for backgrounds
+    /// that are not worth sampling explicitly.
+    fn sample(&self) -> Option<(Vec3, f32)> {
+        None
+    }
+
+    /// Solid angle density of `sample` picking `direction`.
+    fn pdf(&self, _direction: Vec3) -> f32 {
+        0.0
+    }
+
+    /// Whether `sample` picks directions at all, making the background one
+    /// of the lights aimed at by next-event estimation.
+    fn is_light(&self) -> bool {
+        false
+    }
+}
+
+/// The same colour in every direction; black for scenes lit only by their
+/// own lights.
+pub struct SolidBackground {
+    color: Color,
+}
+
+impl SolidBackground {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Background for SolidBackground {
+    fn color(&self, _direction: Vec3) -> Color {
+        self.color
+    }
+}
+
+/// A vertical blend from `bottom`, straight down, to `top`, straight up.
+///
+/// The default is the white to light blue sky of the book.
+pub struct SkyGradient {
+    bottom: Color,
+    top: Color,
+}
+
+impl SkyGradient {
+    pub fn new(bottom: Color, top: Color) -> Self {
+        Self { bottom, top }
+    }
+}
+
+impl Default for SkyGradient {
+    fn default() -> Self {
+        Self::new(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Background for SkyGradient {
+    fn color(&self, direction: Vec3) -> Color {
+        let unit_direction = unit(direction);
+        let t = 0.5 * (unit_direction.y() + 1.0);
+        (1.0 - t) * self.bottom + t * self.top
+    }
+}
+
+/// An equirectangular (latitude-longitude) environment image. Its centre
+/// lies along -z, its top row straight up.
+///
+/// Directions are sampled from a piecewise-constant distribution over the
+/// pixels' luminance, so small bright features such as the sun are found by
+/// `sample` rather than by chance.
+pub struct LatLongMap {
+    image: Image,
+    rotation: f32,
+    intensity: f32,
+    distribution: Distribution2D,
+}
+
+impl LatLongMap {
+    pub fn new(image: Image) -> Self {
+        assert!(
+            image.width() > 0 && image.height() > 0,
+            "environment map without pixels"
+        );
+        // Bilinear lookups inside a pixel reach into its neighbours, so each
+        // step takes the brightest pixel around it. Rows near the poles
+        // cover less solid angle than the equator.
+        let (width, height) = (image.width(), image.height());
+        let mut func = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let sin_theta = (PI * (y as f32 + 0.5) / height as f32).sin();
+            for x in 0..width {
+                let mut brightest = 0.0f32;
+                for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                    for nx in [x + width - 1, x, x + 1] {
+                        brightest = brightest.max(luminance(image.get(nx % width, ny)));
+                    }
+                }
+                func.push(brightest * sin_theta);
+            }
+        }
+        let distribution = Distribution2D::new(&func, image.width());
+        Self {
+            image,
+            rotation: 0.0,
+            intensity: 1.0,
+            distribution,
+        }
+    }
+
+    /// Turn the map about the vertical axis, counter-clockwise seen from
+    /// above.
+    pub fn rotation(mut self, degrees: f32) -> Self {
+        self.rotation = degrees.to_radians();
+        self
+    }
+
+    /// Scale the radiance of every pixel.
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Bilinearly filtered lookup, wrapping around horizontally.
+    pub fn lookup(&self, u: f32, v: f32) -> Color {
+        let (width, height) = (self.image.width(), self.image.height());
+        let x = u * width as f32 - 0.5;
+        let y = (v * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        let x0 = (x0 as i64).rem_euclid(width as i64) as usize;
+        let x1 = (x0 + 1) % width;
+        let y0 = y0 as usize;
+        let y1 = (y0 + 1).min(height - 1);
+        let row = |y| (1.0 - fx) * self.image.get(x0, y) + fx * self.image.get(x1, y);
+        (1.0 - fy) * row(y0) + fy * row(y1)
+    }
+}
+
+impl Background for LatLongMap {
+    fn color(&self, direction: Vec3) -> Color {
+        let (u, v) = lat_long_coordinates(rotate_y(direction, -self.rotation));
+        self.intensity * self.lookup(u, v)
+    }
+
+    fn sample(&self) -> Option<(Vec3, f32)> {
+        let ((u, v), pdf) = self.distribution.sample(random(), random());
+        let sin_theta = (PI * v).sin();
+        if pdf == 0.0 || sin_theta == 0.0 {
+            return None;
+        }
+        // From density over the map to density over solid angle.
+        let pdf = pdf / (2.0 * PI * PI * sin_theta);
+        Some((rotate_y(lat_long_direction(u, v), self.rotation), pdf))
+    }
+
+    fn pdf(&self, direction: Vec3) -> f32 {
+        let (u, v) = lat_long_coordinates(rotate_y(direction, -self.rotation));
+        let sin_theta = (PI * v).sin();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn is_light(&self) -> bool {
+        true
+    }
+}
+
+/// The unit direction at map coordinates `(u, v)`, undoing
+/// `lat_long_coordinates`.
+pub fn lat_long_direction(u: f32, v: f32) -> Vec3 {
+    let (sin_theta, cos_theta) = (PI * v).sin_cos();
+    let (sin_phi, cos_phi) = (2.0 * PI * (u - 0.5)).sin_cos();
+    Vec3::new(sin_theta * sin_phi, cos_theta, -sin_theta * cos_phi)
+}
+
+fn luminance(c: Color) -> f32 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+/// Rotate `v` by `angle` radians about +y.
+fn rotate_y(v: Vec3, angle: f32) -> Vec3 {
+    let (sin, cos) = angle.sin_cos();
+    Vec3::new(cos * v.x() + sin * v.z(), v.y(), -sin * v.x() + cos * v.z())
+}
+
+/// Map coordinates in [0, 1]² of a direction: `u` goes round the horizon
+/// from +z through -x, -z and +x, `v` from straight up to straight down.
+pub fn lat_long_coordinates(direction: Vec3) -> (f32, f32) {
+    let d = unit(direction);
+    let u = 0.5 + d.x().atan2(-d.z()) / (2.0 * PI);
+    let v = d.y().clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gradient_matches_the_old_sky() {
+        let sky = SkyGradient::default();
+        assert_eq!(sky.color(Vec3::new(0., 5., 0.)), Color::new(0.5, 0.7, 1.0));
+        assert_eq!(sky.color(Vec3::new(0., -1., 0.)), Color::new(1., 1., 1.));
+        let side = sky.color(Vec3::new(1., 0., 1.));
+        assert!((side - Color::new(0.75, 0.85, 1.0)).length() < 1e-6);
+    }
+
+    #[test]
+    fn lat_long_directions() {
+        // Four columns round the horizon, two rows: sky and ground.
+        let mut image = Image::new(4, 2);
+        for x in 0..4 {
+            image.set(x, 0, Color::new(x as f32, 1., 0.));
+            image.set(x, 1, Color::new(x as f32, 0., 0.));
+        }
+        let map = LatLongMap::new(image);
+
+        let (u, v) = lat_long_coordinates(Vec3::new(0., 0., -2.));
+        assert!((u - 0.5).abs() < 1e-6 && (v - 0.5).abs() < 1e-6);
+        let (u, _) = lat_long_coordinates(Vec3::new(1., 0., 0.));
+        assert!((u - 0.75).abs() < 1e-6);
+
+        let up = map.color(Vec3::new(0.001, 1., -1.));
+        assert!((up.y() - 1.).abs() < 1e-6);
+        // Straight behind the viewer sits on the seam, halfway between the
+        // last column and the first.
+        let behind = map.color(Vec3::new(0., -1., 1.));
+        assert!((behind - Color::new(1.5, 0., 0.)).length() < 1e-3);
+    }
+
+    #[test]
+    fn sampling_finds_the_sun() {
+        crate::rtweekend::seed_rng(7);
+        // A dim sky with one bright pixel a little above the horizon.
+        let mut image = Image::new(32, 16);
+        for y in 0..16 {
+            for x in 0..32 {
+                image.set(x, y, Color::new(0.1, 0.1, 0.1));
+            }
+        }
+        image.set(20, 6, Color::new(1000., 900., 800.));
+        let map = LatLongMap::new(image).rotation(90.).intensity(2.);
+
+        let sun = rotate_y(lat_long_direction(20.5 / 32., 6.5 / 16.), PI / 2.);
+        assert!((map.color(sun) - Color::new(2000., 1800., 1600.)).length() < 1.);
+        let mut hits = 0;
+        let n = 4000;
+        let mut estimate = Color::default();
+        for _ in 0..n {
+            let (direction, pdf) = map.sample().unwrap();
+            assert!((direction.length() - 1.).abs() < 1e-4);
+            assert!((map.pdf(direction) - pdf).abs() <= 1e-3 * pdf);
+            if dot(direction, sun) > 0.9 {
+                hits += 1;
+            }
+            estimate += map.color(direction) / pdf / n as f32;
+        }
+        assert!(hits > n * 9 / 10);
+
+        // The integral over the sphere, against the sum of each pixel times
+        // the solid angle it covers.
+        let mut expected = Color::default();
+        for y in 0..16 {
+            let sin_theta = (PI * (y as f32 + 0.5) / 16.).sin();
+            for x in 0..32 {
+                let solid_angle = 2. * PI * PI * sin_theta / (32. * 16.);
+                expected += 2. * map.image().get(x, y) * solid_angle;
+            }
+        }
+        assert!((estimate - expected).length() < 0.05 * expected.length());
+    }
+}
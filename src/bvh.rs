@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use crate::aabb::{surrounding_box, Aabb};
+use crate::hittable::{HitRecord, Hittable};
+use crate::hittable_list::HittableList;
+use crate::ray::Ray;
+
+/// Bounding volume hierarchy over a set of bounded objects.
+///
+/// Each node splits its objects in two along the axis and position that
+/// minimise the surface-area heuristic, so `hit` only descends into the
+/// children whose boxes the ray actually crosses. A lone object is a leaf
+/// with no right child, so it is only ever tested once.
+pub struct BvhNode {
+    left: Arc<dyn Hittable>,
+    right: Option<Arc<dyn Hittable>>,
+    bbox: Aabb,
+}
+
+impl BvhNode {
+    pub fn new(list: &HittableList) -> Self {
+        let mut objects: Vec<_> = list
+            .objects()
+            .iter()
+            .map(|object| {
+                let bbox = object
+                    .bounding_box()
+                    .expect("No bounding box in BvhNode constructor.");
+                (object.clone(), bbox)
+            })
+            .collect();
+        assert!(!objects.is_empty(), "BvhNode needs at least one object.");
+        Self::build(&mut objects)
+    }
+
+    fn build(objects: &mut [(Arc<dyn Hittable>, Aabb)]) -> Self {
+        let (left, right): (Arc<dyn Hittable>, Arc<dyn Hittable>) = match objects.len() {
+            1 => {
+                return Self {
+                    left: objects[0].0.clone(),
+                    right: None,
+                    bbox: objects[0].1,
+                }
+            }
+            2 => (objects[0].0.clone(), objects[1].0.clone()),
+            _ => {
+                let (axis, mid) = Self::sah_split(objects);
+                sort_by_centroid(objects, axis);
+                let (l, r) = objects.split_at_mut(mid);
+                (Arc::new(Self::build(l)), Arc::new(Self::build(r)))
+            }
+        };
+        let bbox = surrounding_box(left.bounding_box().unwrap(), right.bounding_box().unwrap());
+        Self {
+            left,
+            right: Some(right),
+            bbox,
+        }
+    }
+
+    /// Find the axis and split index with the lowest surface-area cost.
+    ///
+    /// The cost of splitting after the `i`th object (in centroid order) is
+    /// `area(left) * i + area(right) * (n - i)`; traversal cost is constant
+    /// and therefore left out.
+    fn sah_split(objects: &mut [(Arc<dyn Hittable>, Aabb)]) -> (usize, usize) {
+        let n = objects.len();
+        let mut best = (0, n / 2);
+        let mut best_cost = f32::INFINITY;
+        let mut right_area = vec![0.0; n];
+        for axis in 0..3 {
+            sort_by_centroid(objects, axis);
+
+            let mut acc = objects[n - 1].1;
+            for i in (1..n).rev() {
+                acc = surrounding_box(acc, objects[i].1);
+                right_area[i] = acc.surface_area();
+            }
+
+            let mut acc = objects[0].1;
+            for i in 1..n {
+                let cost = acc.surface_area() * i as f32 + right_area[i] * (n - i) as f32;
+                if cost < best_cost {
+                    best_cost = cost;
+                    best = (axis, i);
+                }
+                acc = surrounding_box(acc, objects[i].1);
+            }
+        }
+        best
+    }
+}
+
+/// A `BvhNode` over the bounded objects in `list`, listed alongside the
+/// unbounded ones (such as planes) that no box can hold.
+pub fn accelerate(list: &HittableList) -> HittableList {
+    let mut bounded = HittableList::new();
+    let mut world = HittableList::new();
+    for object in list.objects() {
+        if object.bounding_box().is_some() {
+            bounded.add(object.clone());
+        } else {
+            world.add(object.clone());
+        }
+    }
+    if !bounded.is_empty() {
+        world.add(Arc::new(BvhNode::new(&bounded)));
+    }
+    world
+}
+
+fn sort_by_centroid(objects: &mut [(Arc<dyn Hittable>, Aabb)], axis: usize) {
+    objects.sort_by(|a, b| a.1.centroid()[axis].total_cmp(&b.1.centroid()[axis]));
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        if !self.bbox.hit(r, t_min, t_max) {
+            return None;
+        }
+
+        let hit_left = self.left.hit(r, t_min, t_max);
+        let Some(right) = &self.right else {
+            return hit_left;
+        };
+        let t_max = hit_left.as_ref().map_or(t_max, |rec| rec.t);
+        let hit_right = right.hit(r, t_min, t_max);
+
+        hit_right.or(hit_left)
+    }
+
+    fn occluded(&self, r: Ray, t_min: f32, t_max: f32) -> bool {
+        self.bbox.hit(r, t_min, t_max)
+            && (self.left.occluded(r, t_min, t_max)
+                || self
+                    .right
+                    .as_ref()
+                    .is_some_and(|right| right.occluded(r, t_min, t_max)))
+    }
+
+    fn transmittance(&self, r: Ray, t_min: f32, t_max: f32) -> f32 {
+        if !self.bbox.hit(r, t_min, t_max) {
+            return 1.0;
+        }
+        let left = self.left.transmittance(r, t_min, t_max);
+        if left == 0.0 {
+            return 0.0;
+        }
+        match &self.right {
+            Some(right) => left * right.transmittance(r, t_min, t_max),
+            None => left,
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::constant_medium::ConstantMedium;
+    use crate::density_grid::DensityGrid;
+    use crate::heterogeneous_medium::HeterogeneousMedium;
+    use crate::material::Isotropic;
+    use crate::rtweekend::{random, seed_rng};
+    use crate::scene::random_scene;
+    use crate::sphere::Sphere;
+    use crate::vec3::*;
+
+    #[test]
+    fn same_closest_hit_as_list() {
+        let world = random_scene();
+        let bvh = BvhNode::new(&world);
+        for _ in 0..10000 {
+            let origin = Point3::new(13., 2., 3.) + Vec3::random_in_range(-1.0, 1.0);
+            let target = Point3::new(0., 0., 0.) + Vec3::random_in_range(-12.0, 12.0);
+            let r = Ray::new(
+                origin,
+                target - origin + 0.1 * random_unit_vector() * random::<f32>(),
+            );
+            let expected = world.hit(r, 0.001, f32::INFINITY);
+            let actual = bvh.hit(r, 0.001, f32::INFINITY);
+            match (expected, actual) {
+                (None, None) => {}
+                (Some(e), Some(a)) => {
+                    assert_eq!(e.t, a.t);
+                    assert_eq!(e.p, a.p);
+                    assert_eq!(e.normal, a.normal);
+                    assert!(Arc::ptr_eq(&e.material, &a.material));
+                }
+                (e, a) => panic!(
+                    "list hit {:?} but bvh hit {:?}",
+                    e.map(|rec| rec.t),
+                    a.map(|rec| rec.t)
+                ),
+            }
+            for t_max in [1.0, 5.0, f32::INFINITY] {
+                assert_eq!(
+                    bvh.occluded(r, 0.001, t_max),
+                    world.hit(r, 0.001, t_max).is_some()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn same_transmittance_as_list() {
+        seed_rng(23);
+        let mut world = random_scene();
+        let smoke = Arc::new(Isotropic::new(Color::new(0.8, 0.8, 0.8)));
+        for (x, z) in [(4., 1.), (-2., 2.), (1., -3.)] {
+            let boundary = Arc::new(Sphere::new(Point3::new(x, 1., z), 1.5, smoke.clone()));
+            world.add(Arc::new(ConstantMedium::new(boundary, 0.3, smoke.clone())));
+        }
+        let bvh = BvhNode::new(&world);
+        for _ in 0..1000 {
+            let origin = Point3::new(13., 2., 3.) + Vec3::random_in_range(-1.0, 1.0);
+            let target = Point3::new(0., 1., 0.) + Vec3::random_in_range(-6.0, 6.0);
+            let r = Ray::new(origin, target - origin);
+            for t_max in [0.5, 1.0] {
+                let expected = world.transmittance(r, 0.001, t_max);
+                assert!((bvh.transmittance(r, 0.001, t_max) - expected).abs() < 1e-5);
+            }
+        }
+
+        // Ratio tracking is only right on average.
+        let grid = Arc::new(DensityGrid::from_fn([4, 4, 4], |_| 1.));
+        let mut cloud = HittableList::new();
+        cloud.add(Arc::new(HeterogeneousMedium::new(
+            grid,
+            Point3::new(-1., -1., -1.),
+            Point3::new(1., 1., 1.),
+            0.5,
+            smoke,
+        )));
+        let bvh = BvhNode::new(&cloud);
+        let r = Ray::new(Point3::new(0., 0., 5.), Vec3::new(0., 0., -1.));
+        let n = 20000;
+        let mean = (0..n)
+            .map(|_| bvh.transmittance(r, 0.001, f32::INFINITY))
+            .sum::<f32>()
+            / n as f32;
+        assert!((mean - (-1f32).exp()).abs() < 0.01);
+    }
+}
@@ -1,5 +1,6 @@
-use crate::{ray::Ray, vec3::*};
+use crate::{ray::Ray, rtweekend::random, vec3::*};
 
+/// Thin-lens camera producing primary rays for normalised image coordinates.
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct Camera {
     origin: Point3,
@@ -10,6 +11,8 @@ pub struct Camera {
     v: Vec3,
     w: Vec3,
     lens_radius: f32,
+    shutter_open: f32,
+    shutter_close: f32,
 }
 
 impl Camera {
@@ -43,13 +46,58 @@ impl Camera {
         cam
     }
 
+    /// Open the shutter from `open` to `close`, so each ray samples a time
+    /// uniformly in between and moving objects blur. A still camera sees
+    /// everything at `open`.
+    pub fn shutter(mut self, open: f32, close: f32) -> Self {
+        self.shutter_open = open;
+        self.shutter_close = close.max(open);
+        self
+    }
+
     pub fn get_ray(&self, s: f32, t: f32) -> Ray {
         let rd = self.lens_radius * random_in_unit_disk();
         let offset = self.u * rd.x() + self.v * rd.y();
 
-        Ray::new(
+        let time = if self.shutter_close > self.shutter_open {
+            self.shutter_open + random::<f32>() * (self.shutter_close - self.shutter_open)
+        } else {
+            self.shutter_open
+        };
+
+        Ray::with_time(
             self.origin + offset,
             self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
+            time,
+        )
+    }
+}
+
+/// Camera placement, kept separate from `Camera` so the aspect ratio can
+/// still change after a scene is loaded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraSettings {
+    pub look_from: Point3,
+    pub look_at: Point3,
+    pub vup: Vec3,
+    pub vfov: f32,
+    pub aperture: f32,
+    pub focus_dist: f32,
+    pub shutter_open: f32,
+    pub shutter_close: f32,
+}
+
+impl CameraSettings {
+    pub fn build(&self, aspect_ratio: f32) -> Camera {
+        Camera::new(
+            self.look_from,
+            self.look_at,
+            self.vup,
+            self.vfov,
+            aspect_ratio,
+            self.aperture,
+            self.focus_dist,
         )
+        .shutter(self.shutter_open, self.shutter_close)
     }
 }
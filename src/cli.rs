@@ -0,0 +1,324 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use ray_tracing_one_weekend::output::OutputFormat;
+use ray_tracing_one_weekend::render::Backend;
+
+pub const USAGE: &str = "\
+Render a scene with the ray tracer.
+
+Usage: ray_tracing_one_weekend [OPTIONS] [SCENE_FILE]
+
+Arguments:
+  [SCENE_FILE]             JSON scene description to render instead of a built-in scene
+
+Options:
+  -s, --scene <NAME>       built-in scene: test, metal, random or cornell
+                           [default: metal]
+  -w, --width <PIXELS>     image width [default: from the scene]
+      --height <PIXELS>    image height [default: width / aspect ratio]
+  -n, --samples <N>        samples per pixel [default: from the scene]
+  -d, --depth <N>          maximum ray bounce depth [default: from the scene]
+  -o, --output <PATH>      output file, '-' for stdout [default: -]
+  -f, --format <FORMAT>    output format: ppm, ppm-ascii, png, pfm, hdr, exr
+                           or exr-uncompressed
+                           [default: from the output extension, ppm-ascii for stdout]
+  -j, --threads <N>        number of worker threads [default: one per core]
+      --seed <N>           seed the random generator for reproducible renders
+  -b, --backend <BACKEND>  cpu or gpu [default: cpu]
+  -h, --help               print this help
+";
+
+/// Every option that takes a value.
+const OPTIONS: &[&str] = &[
+    "-s",
+    "--scene",
+    "-w",
+    "--width",
+    "--height",
+    "-n",
+    "--samples",
+    "-d",
+    "--depth",
+    "-o",
+    "--output",
+    "-f",
+    "--format",
+    "-j",
+    "--threads",
+    "--seed",
+    "-b",
+    "--backend",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinScene {
+    Test,
+    Metal,
+    Random,
+    Cornell,
+}
+
+impl FromStr for BuiltinScene {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "test" => Ok(Self::Test),
+            "metal" => Ok(Self::Metal),
+            "random" => Ok(Self::Random),
+            "cornell" => Ok(Self::Cornell),
+            _ => Err(format!(
+                "unknown scene '{}' (expected test, metal, random or cornell)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SceneSource {
+    Builtin(BuiltinScene),
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub scene: SceneSource,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub samples_per_pixel: Option<i32>,
+    pub max_depth: Option<i32>,
+    /// `None` writes to stdout.
+    pub output: Option<PathBuf>,
+    pub format: OutputFormat,
+    pub threads: Option<usize>,
+    pub seed: Option<u64>,
+    pub backend: Backend,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Render(Options),
+    Help,
+}
+
+/// Parse the command line, not including the program name.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut builtin = None;
+    let mut file = None;
+    let mut width = None;
+    let mut height = None;
+    let mut samples_per_pixel = None;
+    let mut max_depth = None;
+    let mut output = None;
+    let mut format = None;
+    let mut threads = None;
+    let mut seed = None;
+    let mut backend = Backend::Cpu;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "-" {
+            return Err("cannot read the scene from stdin, give a SCENE_FILE path".to_string());
+        }
+        if !arg.starts_with('-') {
+            if file.replace(PathBuf::from(&arg)).is_some() {
+                return Err(format!("unexpected argument '{}'", arg));
+            }
+            continue;
+        }
+
+        // Accept both `--name value` and `--name=value`.
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => {
+                (name.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        if name == "-h" || name == "--help" {
+            return Ok(Command::Help);
+        }
+        if !OPTIONS.contains(&name.as_str()) {
+            return Err(format!("unknown option '{}'", name));
+        }
+        let value = match inline_value.or_else(|| args.next()) {
+            Some(value) => value,
+            None => return Err(format!("missing value for '{}'", name)),
+        };
+
+        match name.as_str() {
+            "-s" | "--scene" => builtin = Some(value.parse()?),
+            "-w" | "--width" => width = Some(parse_positive(&name, &value)?),
+            "--height" => height = Some(parse_positive(&name, &value)?),
+            "-n" | "--samples" => samples_per_pixel = Some(parse_positive(&name, &value)?),
+            "-d" | "--depth" => max_depth = Some(parse_positive(&name, &value)?),
+            "-o" | "--output" => output = Some(value),
+            "-f" | "--format" => format = Some(value.parse()?),
+            "-j" | "--threads" => threads = Some(parse_positive(&name, &value)? as usize),
+            "--seed" => {
+                seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid value '{}' for '{}'", value, name))?,
+                )
+            }
+            "-b" | "--backend" => backend = value.parse()?,
+            _ => unreachable!(),
+        }
+    }
+
+    let scene = match (builtin, file) {
+        (Some(_), Some(_)) => {
+            return Err("--scene and a scene file are mutually exclusive".to_string())
+        }
+        (Some(builtin), None) => SceneSource::Builtin(builtin),
+        (None, Some(file)) => SceneSource::File(file),
+        (None, None) => SceneSource::Builtin(BuiltinScene::Metal),
+    };
+    let output = output.filter(|path| path != "-").map(PathBuf::from);
+    let format = match (format, &output) {
+        (Some(format), _) => format,
+        (None, None) => OutputFormat::PpmAscii,
+        (None, Some(path)) => OutputFormat::from_extension(path).ok_or_else(|| {
+            format!(
+                "cannot tell the output format from '{}', use --format",
+                path.display()
+            )
+        })?,
+    };
+
+    Ok(Command::Render(Options {
+        scene,
+        width,
+        height,
+        samples_per_pixel,
+        max_depth,
+        output,
+        format,
+        threads,
+        seed,
+        backend,
+    }))
+}
+
+fn parse_positive(name: &str, value: &str) -> Result<i32, String> {
+    match value.parse::<i32>() {
+        Ok(v) if v > 0 => Ok(v),
+        _ => Err(format!(
+            "invalid value '{}' for '{}': expected a positive integer",
+            value, name
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ray_tracing_one_weekend::output::ExrCompression;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn options(args: &[&str]) -> Options {
+        match parse(args) {
+            Ok(Command::Render(options)) => options,
+            other => panic!("expected render options, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        let options = options(&[]);
+        assert_eq!(options.scene, SceneSource::Builtin(BuiltinScene::Metal));
+        assert_eq!(options.output, None);
+        assert_eq!(options.format, OutputFormat::PpmAscii);
+        assert_eq!(options.backend, Backend::Cpu);
+    }
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(
+            options(&["-o", "out.exr"]).format,
+            OutputFormat::Exr(ExrCompression::Zip)
+        );
+        assert_eq!(options(&["-o", "out.HDR"]).format, OutputFormat::Hdr);
+        assert_eq!(
+            options(&["-o", "out.exr", "-f", "exr-uncompressed"]).format,
+            OutputFormat::Exr(ExrCompression::None)
+        );
+    }
+
+    #[test]
+    fn all_options() {
+        let options = options(&[
+            "scene.json",
+            "-w",
+            "320",
+            "--height=200",
+            "--samples",
+            "16",
+            "-d",
+            "8",
+            "-o",
+            "out.ppm",
+            "-j",
+            "4",
+            "--seed",
+            "42",
+            "--backend",
+            "cpu",
+        ]);
+        assert_eq!(
+            options.scene,
+            SceneSource::File(PathBuf::from("scene.json"))
+        );
+        assert_eq!(options.width, Some(320));
+        assert_eq!(options.height, Some(200));
+        assert_eq!(options.samples_per_pixel, Some(16));
+        assert_eq!(options.max_depth, Some(8));
+        assert_eq!(options.output, Some(PathBuf::from("out.ppm")));
+        assert_eq!(options.format, OutputFormat::Ppm);
+        assert_eq!(options.threads, Some(4));
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.backend, Backend::Cpu);
+        assert_eq!(parse(&["--scene", "random", "--help"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        assert_eq!(
+            parse(&["--width", "0"]),
+            Err("invalid value '0' for '--width': expected a positive integer".to_string())
+        );
+        assert_eq!(
+            parse(&["--scene", "sponza"]),
+            Err("unknown scene 'sponza' (expected test, metal, random or cornell)".to_string())
+        );
+        assert_eq!(
+            parse(&["--depth"]),
+            Err("missing value for '--depth'".to_string())
+        );
+        assert_eq!(
+            parse(&["-o", "out.xyz"]),
+            Err("cannot tell the output format from 'out.xyz', use --format".to_string())
+        );
+        assert_eq!(
+            parse(&["--scene", "test", "scene.json"]),
+            Err("--scene and a scene file are mutually exclusive".to_string())
+        );
+        assert_eq!(
+            parse(&["--backend", "vulkan"]),
+            Err("unknown backend 'vulkan' (expected cpu or gpu)".to_string())
+        );
+        assert_eq!(
+            parse(&["-"]),
+            Err("cannot read the scene from stdin, give a SCENE_FILE path".to_string())
+        );
+        assert_eq!(
+            parse(&["--fast"]),
+            Err("unknown option '--fast'".to_string())
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::aabb::{surrounding_box, Aabb};
+use crate::disk::rim_extent;
+use crate::hittable::*;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::roots::quadratic;
+use crate::vec3::*;
+
+/// A circular cone narrowing from a base of `radius` around `base` to a
+/// point at `apex`, with its base closed off unless built `capped(false)`.
+///
+/// On the side `u` goes round the axis and `v` from base to apex; on the
+/// cap `v` runs out from the centre.
+#[derive(Clone)]
+pub struct Cone {
+    base: Point3,
+    height: f32,
+    radius: f32,
+    /// `w` along the axis, from base to apex.
+    frame: Onb,
+    capped: bool,
+    material: Arc<dyn Material>,
+}
+
+impl Cone {
+    pub fn new(base: Point3, apex: Point3, radius: f32, material: Arc<dyn Material>) -> Self {
+        Self {
+            base,
+            height: (apex - base).length(),
+            radius,
+            frame: Onb::new(unit(apex - base)),
+            capped: true,
+            material,
+        }
+    }
+
+    pub fn capped(mut self, capped: bool) -> Self {
+        self.capped = capped;
+        self
+    }
+
+    pub fn is_capped(&self) -> bool {
+        self.capped
+    }
+}
+
+impl Cone {
+    /// Every point where the whole line through `r` crosses the surface,
+    /// as `t`, the outward normal in the axis frame and `v`.
+    fn crossings(&self, r: Ray) -> [Option<(f32, Vec3, f32)>; 3] {
+        // Along the axis frame, the side is x² + y² = k² (height - z)².
+        let o = self.frame.coords(r.origin() - self.base);
+        let d = self.frame.coords(r.direction());
+        let k = self.radius / self.height;
+        let k2 = k * k;
+        let to_apex = self.height - o.z();
+        let mut crossings = [None; 3];
+
+        let a = d.x() * d.x() + d.y() * d.y() - k2 * d.z() * d.z();
+        let b = 2.0 * (o.x() * d.x() + o.y() * d.y() + k2 * to_apex * d.z());
+        let c = o.x() * o.x() + o.y() * o.y() - k2 * to_apex * to_apex;
+        if let Some((t0, t1)) = quadratic(a, b, c) {
+            for (slot, t) in crossings.iter_mut().zip([t0, t1]) {
+                let p = o + t * d;
+                // The quadratic also describes the mirrored cone beyond the
+                // apex, which the height check rules out.
+                if (0.0..=self.height).contains(&p.z()) {
+                    let normal = unit(Vec3::new(p.x(), p.y(), k2 * (self.height - p.z())));
+                    *slot = Some((t, normal, p.z() / self.height));
+                }
+            }
+        }
+
+        if self.capped && d.z() != 0.0 {
+            let t = -o.z() / d.z();
+            let p = o + t * d;
+            let rho2 = p.x() * p.x() + p.y() * p.y();
+            if rho2 <= self.radius * self.radius {
+                crossings[2] = Some((t, Vec3::new(0., 0., -1.), rho2.sqrt() / self.radius));
+            }
+        }
+        crossings
+    }
+
+    fn record(&self, r: Ray, (t, normal, v): (f32, Vec3, f32)) -> HitRecord {
+        let p = self.frame.coords(r.at(t) - self.base);
+        let mut rec = HitRecord {
+            p: r.at(t),
+            t,
+            material: self.material.clone(),
+            normal: Vec3::default(),
+            u: (p.y().atan2(p.x()) / (2.0 * PI)).rem_euclid(1.0),
+            v,
+            front_face: false,
+        };
+        rec.set_face_normal(r, self.frame.local(normal));
+        rec
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let nearest = self
+            .crossings(r)
+            .into_iter()
+            .flatten()
+            .filter(|(t, _, _)| (t_min..=t_max).contains(t))
+            .min_by(|a, b| a.0.total_cmp(&b.0))?;
+        Some(self.record(r, nearest))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let rim = rim_extent(self.frame.w(), self.radius) + Vec3::new(1e-4, 1e-4, 1e-4);
+        let apex = self.base + self.height * self.frame.w();
+        Some(surrounding_box(
+            Aabb::new(self.base - rim, self.base + rim),
+            Aabb::new(apex, apex),
+        ))
+    }
+
+    fn is_closed(&self) -> bool {
+        self.capped
+    }
+
+    fn intervals(&self, r: Ray) -> Vec<Interval> {
+        if !self.capped {
+            return Vec::new();
+        }
+        convex_interval(
+            self.crossings(r)
+                .into_iter()
+                .flatten()
+                .map(|crossing| self.record(r, crossing)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn side_and_base() {
+        // Upright, 2 high with a base of radius 1: the side slopes at 45°
+        // in the sense that the radius shrinks by half a unit per unit up.
+        let cone = Cone::new(
+            Point3::new(0., 0., 0.),
+            Point3::new(0., 2., 0.),
+            1.,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        let level = |y| Ray::new(Point3::new(-5., y, 0.), Vec3::new(1., 0., 0.));
+        let rec = cone.hit(level(1.), 0., f32::INFINITY).unwrap();
+        assert!((rec.p - Point3::new(-0.5, 1., 0.)).length() < 1e-5);
+        assert!((rec.normal - unit(Vec3::new(-1., 0.5, 0.))).length() < 1e-5);
+        assert!(rec.front_face);
+        assert!((rec.v - 0.5).abs() < 1e-5);
+        // Not the mirrored cone above the apex.
+        assert!(cone.hit(level(3.), 0., f32::INFINITY).is_none());
+
+        let up = Ray::new(Point3::new(0.3, -1., 0.), Vec3::new(0., 1., 0.));
+        let rec = cone.hit(up, 0., f32::INFINITY).unwrap();
+        assert!((rec.t - 1.).abs() < 1e-5);
+        assert!((rec.normal - Vec3::new(0., -1., 0.)).length() < 1e-5);
+        // Through the open base, the side is seen from inside.
+        let rec = cone
+            .clone()
+            .capped(false)
+            .hit(up, 0., f32::INFINITY)
+            .unwrap();
+        assert!(!rec.front_face);
+        assert!((rec.p.y() - 1.4).abs() < 1e-4);
+
+        let bbox = cone.bounding_box().unwrap();
+        assert!((bbox.min() - Point3::new(-1., 0., -1.)).length() < 1e-3);
+        assert!((bbox.max() - Point3::new(1., 2., 1.)).length() < 1e-3);
+    }
+}
@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::*;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::rtweekend::random;
+use crate::vec3::*;
+
+/// Fog or smoke of constant `density` filling a closed `boundary`. A ray
+/// crossing it travels an exponentially distributed distance before it
+/// scatters off the `phase_function`, usually an `Isotropic` material, and
+/// passes straight through otherwise.
+#[derive(Clone)]
+pub struct ConstantMedium {
+    boundary: Arc<dyn Hittable>,
+    density: f32,
+    phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    /// `boundary` must be closed; its own material is ignored.
+    pub fn new(
+        boundary: Arc<dyn Hittable>,
+        density: f32,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        assert!(boundary.is_closed(), "a medium needs a closed boundary");
+        assert!(density > 0.0, "density must be positive");
+        Self {
+            boundary,
+            density,
+            phase_function,
+        }
+    }
+
+    pub fn density(&self) -> f32 {
+        self.density
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let speed = r.direction().length();
+        // Free flight is memoryless, so one distance covers every stretch of
+        // the boundary the ray passes through.
+        let mut remaining = -(1.0 - random::<f32>()).ln() / self.density;
+        for Interval { enter, exit } in self.boundary.intervals(r) {
+            let (enter, exit) = (enter.t.max(t_min), exit.t.min(t_max));
+            if enter >= exit {
+                continue;
+            }
+            let inside = (exit - enter) * speed;
+            if remaining < inside {
+                let t = enter + remaining / speed;
+                return Some(HitRecord {
+                    p: r.at(t),
+                    t,
+                    material: self.phase_function.clone(),
+                    // Arbitrary: phase functions ignore it.
+                    normal: Vec3::new(1., 0., 0.),
+                    u: 0.,
+                    v: 0.,
+                    front_face: true,
+                });
+            }
+            remaining -= inside;
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+
+    /// Beer–Lambert over the stretches of `[t_min, t_max]` inside.
+    fn transmittance(&self, r: Ray, t_min: f32, t_max: f32) -> f32 {
+        let inside: f32 = self
+            .boundary
+            .intervals(r)
+            .into_iter()
+            .map(|Interval { enter, exit }| (exit.t.min(t_max) - enter.t.max(t_min)).max(0.0))
+            .sum();
+        (-self.density * inside * r.direction().length()).exp()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bvh::accelerate;
+    use crate::cuboid::Cuboid;
+    use crate::hittable_list::HittableList;
+    use crate::material::{Isotropic, Lambertian};
+    use crate::rtweekend::seed_rng;
+    use crate::sphere::Sphere;
+
+    fn fog_cube() -> ConstantMedium {
+        let boundary: Arc<dyn Hittable> = Arc::new(Cuboid::new(
+            Point3::new(-1., -1., -1.),
+            Point3::new(1., 1., 1.),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        ));
+        ConstantMedium::new(
+            boundary,
+            0.5,
+            Arc::new(Isotropic::new(Color::new(0.9, 0.9, 0.9))),
+        )
+    }
+
+    #[test]
+    fn scatters_at_exponential_depths() {
+        seed_rng(19);
+        let fog = fog_cube();
+        // Unnormalised directions still measure density per world unit.
+        let r = Ray::new(Point3::new(0., 0., 5.), Vec3::new(0., 0., -2.));
+        let n = 20000;
+        let mut hits = 0;
+        let mut depth = 0.0;
+        for _ in 0..n {
+            if let Some(rec) = fog.hit(r, 0.001, f32::INFINITY) {
+                assert!(rec.p.z() <= 1. && rec.p.z() >= -1.);
+                assert!((r.at(rec.t) - rec.p).length() < 1e-5);
+                hits += 1;
+                depth += 1. - rec.p.z();
+            }
+        }
+        // Passing 2 units of density 0.5 leaves e^-1 of the rays untouched.
+        let scattered = hits as f32 / n as f32;
+        assert!((scattered - (1. - (-1f32).exp())).abs() < 0.01);
+        // The mean depth of those that do scatter, 2 - 2 / (e - 1).
+        let expected = 2. - 2. / (1f32.exp() - 1.);
+        assert!((depth / hits as f32 - expected).abs() < 0.02);
+
+        // Starting inside, only the rest of the way counts.
+        let inside = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., -1.));
+        for _ in 0..100 {
+            if let Some(rec) = fog.hit(inside, 0.001, f32::INFINITY) {
+                assert!(rec.p.z() < 0. && rec.p.z() >= -1.);
+            }
+        }
+        assert!(fog.hit(r, 0.001, 3.).is_none());
+        assert!((fog.transmittance(r, 0.001, f32::INFINITY) - (-1f32).exp()).abs() < 1e-5);
+        assert!((fog.transmittance(inside, 0., 0.5) - (-0.25f32).exp()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn scatters_as_often_through_a_bvh() {
+        seed_rng(20);
+        let r = Ray::new(Point3::new(0., 0., 5.), Vec3::new(0., 0., -1.));
+        let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let mut alone = HittableList::new();
+        alone.add(Arc::new(fog_cube()));
+        let mut crowded = alone.clone();
+        for x in [-4., 4.] {
+            crowded.add(Arc::new(Sphere::new(
+                Point3::new(x, 0., 0.),
+                1.,
+                material.clone(),
+            )));
+        }
+        for list in [alone, crowded] {
+            let world = accelerate(&list);
+            let n = 20000;
+            let hits = (0..n)
+                .filter(|_| world.hit(r, 0.001, f32::INFINITY).is_some())
+                .count();
+            assert!((hits as f32 / n as f32 - (1. - (-1f32).exp())).abs() < 0.01);
+            assert!((world.transmittance(r, 0.001, f32::INFINITY) - (-1f32).exp()).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Constructive solid geometry.
+
+use std::sync::Arc;
+
+use crate::aabb::{surrounding_box, Aabb};
+use crate::hittable::*;
+use crate::ray::Ray;
+use crate::vec3::*;
+
+/// How a `Csg` combines its two solids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOp {
+    /// Inside either.
+    Union,
+    /// Inside both.
+    Intersection,
+    /// Inside the first but not the second.
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(self, a: bool, b: bool) -> bool {
+        match self {
+            Self::Union => a || b,
+            Self::Intersection => a && b,
+            Self::Difference => a && !b,
+        }
+    }
+}
+
+/// Two closed objects combined into one solid, such as a sphere with a
+/// cylinder bored out of it. The surfaces keep their own materials, and
+/// the result is itself closed, so `Csg`s nest.
+///
+/// Intervals of both operands are computed for every ray, so a `Csg` costs
+/// about as much as its operands together.
+#[derive(Clone)]
+pub struct Csg {
+    op: CsgOp,
+    a: Arc<dyn Hittable>,
+    b: Arc<dyn Hittable>,
+    bbox: Option<Aabb>,
+}
+
+impl Csg {
+    /// Both `a` and `b` must be closed.
+    pub fn new(op: CsgOp, a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        assert!(
+            a.is_closed() && b.is_closed(),
+            "CSG operands must be closed"
+        );
+        let bbox = match (op, a.bounding_box(), b.bounding_box()) {
+            (CsgOp::Union, Some(a), Some(b)) => Some(surrounding_box(a, b)),
+            (CsgOp::Union, _, _) => None,
+            (CsgOp::Intersection, Some(a), Some(b)) => Some(Aabb::new(
+                Point3::new(
+                    a.min().x().max(b.min().x()),
+                    a.min().y().max(b.min().y()),
+                    a.min().z().max(b.min().z()),
+                ),
+                Point3::new(
+                    a.max().x().min(b.max().x()),
+                    a.max().y().min(b.max().y()),
+                    a.max().z().min(b.max().z()),
+                ),
+            )),
+            (CsgOp::Intersection, a, b) => a.or(b),
+            (CsgOp::Difference, a, _) => a,
+        };
+        Self { op, a, b, bbox }
+    }
+
+    pub fn union(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        Self::new(CsgOp::Union, a, b)
+    }
+
+    pub fn intersection(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        Self::new(CsgOp::Intersection, a, b)
+    }
+
+    pub fn difference(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        Self::new(CsgOp::Difference, a, b)
+    }
+
+    pub fn op(&self) -> CsgOp {
+        self.op
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        self.intervals(r)
+            .into_iter()
+            .flat_map(|Interval { enter, exit }| [enter, exit])
+            .find(|rec| (t_min..=t_max).contains(&rec.t))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bbox
+    }
+
+    fn is_closed(&self) -> bool {
+        true
+    }
+
+    /// Sweep along the ray through the operands' boundaries, keeping those
+    /// where being inside the combination changes.
+    fn intervals(&self, r: Ray) -> Vec<Interval> {
+        let mut events: Vec<(HitRecord, bool, bool)> = Vec::new();
+        for (is_a, object) in [(true, &self.a), (false, &self.b)] {
+            for Interval { enter, exit } in object.intervals(r) {
+                events.push((enter, is_a, true));
+                events.push((exit, is_a, false));
+            }
+        }
+        events.sort_by(|x, y| x.0.t.total_cmp(&y.0.t));
+
+        let (mut in_a, mut in_b, mut inside) = (false, false, false);
+        let mut enter = None;
+        let mut intervals = Vec::new();
+        for (mut rec, is_a, entering) in events {
+            if is_a {
+                in_a = entering;
+            } else {
+                in_b = entering;
+            }
+            let now = self.op.inside(in_a, in_b);
+            if now == inside {
+                continue;
+            }
+            inside = now;
+            // The normal already faces the ray; only which side it hit
+            // changes, as leaving a subtracted solid enters the result.
+            rec.front_face = now;
+            match enter.take() {
+                None => enter = Some(rec),
+                Some(enter) => intervals.push(Interval { enter, exit: rec }),
+            }
+        }
+        intervals
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cylinder::Cylinder;
+    use crate::material::Lambertian;
+    use crate::sphere::Sphere;
+
+    fn sphere(x: f32, radius: f32) -> Arc<dyn Hittable> {
+        Arc::new(Sphere::new(
+            Point3::new(x, 0., 0.),
+            radius,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        ))
+    }
+
+    fn ts(intervals: &[Interval]) -> Vec<(f32, f32)> {
+        intervals.iter().map(|i| (i.enter.t, i.exit.t)).collect()
+    }
+
+    #[test]
+    fn combines_intervals() {
+        // Along the x axis: a covers [-1, 1], b covers [0, 2].
+        let (a, b) = (sphere(0., 1.), sphere(1., 1.));
+        let r = Ray::new(Point3::new(-5., 0., 0.), Vec3::new(1., 0., 0.));
+        assert_eq!(
+            ts(&Csg::union(a.clone(), b.clone()).intervals(r)),
+            [(4., 7.)]
+        );
+        assert_eq!(
+            ts(&Csg::intersection(a.clone(), b.clone()).intervals(r)),
+            [(5., 6.)]
+        );
+        assert_eq!(
+            ts(&Csg::difference(a.clone(), b.clone()).intervals(r)),
+            [(4., 5.)]
+        );
+        assert_eq!(
+            ts(&Csg::difference(b.clone(), a.clone()).intervals(r)),
+            [(6., 7.)]
+        );
+
+        // Leaving the result through the inside of b's surface.
+        let lens = Csg::difference(a.clone(), b.clone());
+        let rec = lens.hit(r, 4.5, f32::INFINITY).unwrap();
+        assert_eq!(rec.t, 5.);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(-1., 0., 0.));
+        assert!(lens.hit(r, 5.5, f32::INFINITY).is_none());
+
+        let bbox = Csg::intersection(a, b).bounding_box().unwrap();
+        assert_eq!(bbox.min(), Point3::new(0., -1., -1.));
+        assert_eq!(bbox.max(), Point3::new(1., 1., 1.));
+    }
+
+    #[test]
+    fn bored_sphere() {
+        let bore: Arc<dyn Hittable> = Arc::new(Cylinder::new(
+            Point3::new(0., -2., 0.),
+            Point3::new(0., 2., 0.),
+            0.25,
+            Arc::new(Lambertian::new(Color::new(0.8, 0.1, 0.1))),
+        ));
+        let part = Csg::difference(sphere(0., 1.), bore);
+        // Straight down the bore there is nothing to hit.
+        let down = |x| Ray::new(Point3::new(x, 5., 0.), Vec3::new(0., -1., 0.));
+        assert!(part.hit(down(0.), 0., f32::INFINITY).is_none());
+        assert!(part.hit(down(0.5), 0., f32::INFINITY).is_some());
+
+        // Across the middle: into the sphere, out into the bore and back.
+        let across = Ray::new(Point3::new(-5., 0., 0.), Vec3::new(1., 0., 0.));
+        let intervals = part.intervals(across);
+        assert_eq!(ts(&intervals), [(4., 4.75), (5.25, 6.)]);
+        let wall = &intervals[1].enter;
+        assert!(wall.front_face);
+        assert_eq!(wall.normal, Vec3::new(-1., 0., 0.));
+        assert!(part.is_closed());
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::*;
+use crate::hittable_list::HittableList;
+use crate::material::Material;
+use crate::quad::Quad;
+use crate::ray::Ray;
+use crate::vec3::*;
+
+/// An axis-aligned box between two opposite corners, made of six outward
+/// facing quads. Turn it with an `Instance` for any other orientation.
+#[derive(Clone)]
+pub struct Cuboid {
+    min: Point3,
+    max: Point3,
+    sides: HittableList,
+}
+
+impl Cuboid {
+    pub fn new(a: Point3, b: Point3, material: Arc<dyn Material>) -> Self {
+        let min = Point3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+        let max = Point3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+        let dx = Vec3::new(max.x() - min.x(), 0., 0.);
+        let dy = Vec3::new(0., max.y() - min.y(), 0.);
+        let dz = Vec3::new(0., 0., max.z() - min.z());
+
+        let mut sides = HittableList::new();
+        let mut side = |q, u, v| sides.add(Arc::new(Quad::new(q, u, v, material.clone())));
+        side(Point3::new(min.x(), min.y(), max.z()), dx, dy); // front
+        side(Point3::new(max.x(), min.y(), max.z()), -dz, dy); // right
+        side(Point3::new(max.x(), min.y(), min.z()), -dx, dy); // back
+        side(Point3::new(min.x(), min.y(), min.z()), dz, dy); // left
+        side(Point3::new(min.x(), max.y(), max.z()), dx, -dz); // top
+        side(Point3::new(min.x(), min.y(), min.z()), dx, dz); // bottom
+        Self { min, max, sides }
+    }
+
+    pub fn min(&self) -> Point3 {
+        self.min
+    }
+
+    pub fn max(&self) -> Point3 {
+        self.max
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        self.sides.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.sides.bounding_box()
+    }
+
+    fn occluded(&self, r: Ray, t_min: f32, t_max: f32) -> bool {
+        self.sides.occluded(r, t_min, t_max)
+    }
+
+    fn is_closed(&self) -> bool {
+        true
+    }
+
+    fn intervals(&self, r: Ray) -> Vec<Interval> {
+        convex_interval(
+            self.sides
+                .objects()
+                .iter()
+                .filter_map(|side| side.hit(r, f32::NEG_INFINITY, f32::INFINITY)),
+        )
+    }
+
+    fn sample_towards(&self, origin: Point3) -> Option<(HitRecord, f32)> {
+        self.sides.sample_towards(origin)
+    }
+
+    fn pdf_towards(&self, origin: Point3, direction: Vec3) -> f32 {
+        self.sides.pdf_towards(origin, direction)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn sides_face_outwards() {
+        let cuboid = Cuboid::new(
+            Point3::new(1., 2., 3.),
+            Point3::new(-1., 0., 0.),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        assert_eq!(cuboid.min(), Point3::new(-1., 0., 0.));
+        let center = Point3::new(0., 1., 1.5);
+        for axis in [
+            Vec3::new(1., 0., 0.),
+            Vec3::new(0., 1., 0.),
+            Vec3::new(0., 0., 1.),
+        ] {
+            for sign in [1., -1.] {
+                let outward = sign * axis;
+                let r = Ray::new(center + 10. * outward, -outward);
+                let rec = cuboid.hit(r, 0., f32::INFINITY).unwrap();
+                assert!(rec.front_face, "{:?}", outward);
+                assert_eq!(rec.normal, outward);
+                // From inside, the same side is seen from the back.
+                let rec = cuboid
+                    .hit(Ray::new(center, outward), 0., f32::INFINITY)
+                    .unwrap();
+                assert!(!rec.front_face);
+                assert_eq!(rec.normal, -outward);
+            }
+        }
+        assert!(cuboid
+            .hit(
+                Ray::new(Point3::new(2., 1., 5.), Vec3::new(0., 0., -1.)),
+                0.,
+                f32::INFINITY
+            )
+            .is_none());
+    }
+}
@@ -0,0 +1,184 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::aabb::{surrounding_box, Aabb};
+use crate::disk::rim_extent;
+use crate::hittable::*;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::roots::quadratic;
+use crate::vec3::*;
+
+/// A circular cylinder of `radius` from `base` to `top`, closed off with
+/// flat caps unless built `capped(false)`.
+///
+/// On the side `u` goes round the axis and `v` from base to top; on the
+/// caps `v` runs out from the centre.
+#[derive(Clone)]
+pub struct Cylinder {
+    base: Point3,
+    height: f32,
+    radius: f32,
+    /// `w` along the axis, from base to top.
+    frame: Onb,
+    capped: bool,
+    material: Arc<dyn Material>,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, top: Point3, radius: f32, material: Arc<dyn Material>) -> Self {
+        Self {
+            base,
+            height: (top - base).length(),
+            radius,
+            frame: Onb::new(unit(top - base)),
+            capped: true,
+            material,
+        }
+    }
+
+    pub fn capped(mut self, capped: bool) -> Self {
+        self.capped = capped;
+        self
+    }
+
+    pub fn is_capped(&self) -> bool {
+        self.capped
+    }
+}
+
+impl Cylinder {
+    /// Every point where the whole line through `r` crosses the surface,
+    /// as `t`, the outward normal in the axis frame and `v`.
+    fn crossings(&self, r: Ray) -> [Option<(f32, Vec3, f32)>; 4] {
+        // Along the axis frame, the side is x² + y² = radius².
+        let o = self.frame.coords(r.origin() - self.base);
+        let d = self.frame.coords(r.direction());
+        let mut crossings = [None; 4];
+
+        let a = d.x() * d.x() + d.y() * d.y();
+        let b = 2.0 * (o.x() * d.x() + o.y() * d.y());
+        let c = o.x() * o.x() + o.y() * o.y() - self.radius * self.radius;
+        if let Some((t0, t1)) = quadratic(a, b, c) {
+            for (slot, t) in crossings.iter_mut().zip([t0, t1]) {
+                let p = o + t * d;
+                if (0.0..=self.height).contains(&p.z()) {
+                    let normal = Vec3::new(p.x(), p.y(), 0.0) / self.radius;
+                    *slot = Some((t, normal, p.z() / self.height));
+                }
+            }
+        }
+
+        if self.capped && d.z() != 0.0 {
+            for (slot, (z, normal)) in crossings[2..]
+                .iter_mut()
+                .zip([(0.0, -1.0), (self.height, 1.0)])
+            {
+                let t = (z - o.z()) / d.z();
+                let p = o + t * d;
+                let rho2 = p.x() * p.x() + p.y() * p.y();
+                if rho2 <= self.radius * self.radius {
+                    *slot = Some((t, Vec3::new(0., 0., normal), rho2.sqrt() / self.radius));
+                }
+            }
+        }
+        crossings
+    }
+
+    fn record(&self, r: Ray, (t, normal, v): (f32, Vec3, f32)) -> HitRecord {
+        let p = self.frame.coords(r.at(t) - self.base);
+        let mut rec = HitRecord {
+            p: r.at(t),
+            t,
+            material: self.material.clone(),
+            normal: Vec3::default(),
+            u: (p.y().atan2(p.x()) / (2.0 * PI)).rem_euclid(1.0),
+            v,
+            front_face: false,
+        };
+        rec.set_face_normal(r, self.frame.local(normal));
+        rec
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let nearest = self
+            .crossings(r)
+            .into_iter()
+            .flatten()
+            .filter(|(t, _, _)| (t_min..=t_max).contains(t))
+            .min_by(|a, b| a.0.total_cmp(&b.0))?;
+        Some(self.record(r, nearest))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let rim = rim_extent(self.frame.w(), self.radius) + Vec3::new(1e-4, 1e-4, 1e-4);
+        let top = self.base + self.height * self.frame.w();
+        Some(surrounding_box(
+            Aabb::new(self.base - rim, self.base + rim),
+            Aabb::new(top - rim, top + rim),
+        ))
+    }
+
+    fn is_closed(&self) -> bool {
+        self.capped
+    }
+
+    fn intervals(&self, r: Ray) -> Vec<Interval> {
+        if !self.capped {
+            return Vec::new();
+        }
+        convex_interval(
+            self.crossings(r)
+                .into_iter()
+                .flatten()
+                .map(|crossing| self.record(r, crossing)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn sides_and_caps() {
+        let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        // Lying along x, from x = 1 to x = 3.
+        let cylinder = Cylinder::new(
+            Point3::new(1., 0., 0.),
+            Point3::new(3., 0., 0.),
+            0.5,
+            material,
+        );
+        let down = |x| Ray::new(Point3::new(x, 2., 0.), Vec3::new(0., -1., 0.));
+        let rec = cylinder.hit(down(2.), 0., f32::INFINITY).unwrap();
+        assert!((rec.t - 1.5).abs() < 1e-5);
+        assert!((rec.normal - Vec3::new(0., 1., 0.)).length() < 1e-5);
+        assert!(rec.front_face);
+        assert!((rec.v - 0.5).abs() < 1e-5);
+        assert!(cylinder.hit(down(3.5), 0., f32::INFINITY).is_none());
+
+        // Straight down the axis, through the base cap.
+        let along = Ray::new(Point3::new(-1., 0.2, 0.), Vec3::new(1., 0., 0.));
+        let rec = cylinder.hit(along, 0., f32::INFINITY).unwrap();
+        assert!((rec.p - Point3::new(1., 0.2, 0.)).length() < 1e-5);
+        assert!((rec.normal - Vec3::new(-1., 0., 0.)).length() < 1e-5);
+        assert!((rec.v - 0.4).abs() < 1e-5);
+
+        // Without caps the ray passes through, but still sees the inside.
+        let open = cylinder.clone().capped(false);
+        assert!(!open.is_capped());
+        assert!(open.hit(along, 0., f32::INFINITY).is_none());
+        let slanted = Ray::new(Point3::new(0., 0.2, 0.), Vec3::new(1., 0.2, 0.));
+        let rec = open.hit(slanted, 0., f32::INFINITY).unwrap();
+        assert!(!rec.front_face);
+        assert!((rec.p.y() - 0.5).abs() < 1e-5);
+
+        let bbox = cylinder.bounding_box().unwrap();
+        assert!((bbox.min() - Point3::new(1., -0.5, -0.5)).length() < 1e-3);
+        assert!((bbox.max() - Point3::new(3., 0.5, 0.5)).length() < 1e-3);
+    }
+}
@@ -0,0 +1,213 @@
+//! Voxel grids of density for heterogeneous media.
+
+use crate::perlin::Perlin;
+use crate::vec3::*;
+
+/// Densities on a regular lattice spanning the unit cube, with values at
+/// the voxel centres and trilinear interpolation between them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DensityGrid {
+    dims: [usize; 3],
+    data: Vec<f32>,
+    max: f32,
+}
+
+impl DensityGrid {
+    /// `data` runs along x fastest, then y, then z. Densities must be
+    /// finite and not negative.
+    pub fn new(dims: [usize; 3], data: Vec<f32>) -> Self {
+        assert!(dims.iter().all(|&n| n > 0), "grid must not be empty");
+        assert_eq!(Some(data.len()), voxel_count(dims), "grid size");
+        assert!(
+            data.iter().all(|d| d.is_finite() && *d >= 0.0),
+            "densities must be finite and not negative"
+        );
+        let max = data.iter().copied().fold(0.0, f32::max);
+        Self { dims, data, max }
+    }
+
+    /// Sample `density` at the voxel centres.
+    pub fn from_fn(dims: [usize; 3], density: impl Fn(Point3) -> f32) -> Self {
+        let [nx, ny, nz] = dims;
+        let mut data = Vec::with_capacity(nx * ny * nz);
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    data.push(density(Point3::new(
+                        (x as f32 + 0.5) / nx as f32,
+                        (y as f32 + 0.5) / ny as f32,
+                        (z as f32 + 0.5) / nz as f32,
+                    )));
+                }
+            }
+        }
+        Self::new(dims, data)
+    }
+
+    /// A puff of cloud `resolution` voxels across: dense in the middle,
+    /// thinning out towards the edges, which noise of spatial frequency
+    /// `scale` eats into. Nothing reaches the sides of the cube.
+    pub fn cloud(resolution: usize, seed: u64, scale: f32) -> Self {
+        let noise = Perlin::new(seed);
+        Self::from_fn([resolution; 3], |p| {
+            let centered = 2.0 * p - Vec3::new(1., 1., 1.);
+            let falloff = 1.0 - centered.length() / 0.7;
+            (falloff + 0.35 * noise.fbm(scale * p, 5)).clamp(0.0, 1.0)
+        })
+    }
+
+    pub fn dims(&self) -> [usize; 3] {
+        self.dims
+    }
+
+    /// The densities, along x fastest, then y, then z.
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+
+    /// The largest density anywhere in the grid.
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    /// The density at `p` in the unit cube; zero outside it.
+    pub fn density(&self, p: Point3) -> f32 {
+        if (0..3).any(|a| !(0.0..=1.0).contains(&p[a])) {
+            return 0.0;
+        }
+        // The lower corner of the cell of voxel centres around `p`, and how
+        // far across it `p` lies.
+        let mut corner = [0; 3];
+        let mut next = [0; 3];
+        let mut frac = [0.0; 3];
+        for a in 0..3 {
+            let n = self.dims[a];
+            let x = (p[a] * n as f32 - 0.5).clamp(0.0, (n - 1) as f32);
+            corner[a] = (x as usize).min(n - 1);
+            next[a] = (corner[a] + 1).min(n - 1);
+            frac[a] = x - corner[a] as f32;
+        }
+        let at =
+            |x: usize, y: usize, z: usize| self.data[x + self.dims[0] * (y + self.dims[1] * z)];
+        let mut sum = 0.0;
+        for i in 0..8 {
+            let pick = |a: usize| {
+                if i & (1 << a) == 0 {
+                    (corner[a], 1.0 - frac[a])
+                } else {
+                    (next[a], frac[a])
+                }
+            };
+            let ((x, wx), (y, wy), (z, wz)) = (pick(0), pick(1), pick(2));
+            sum += wx * wy * wz * at(x, y, z);
+        }
+        sum
+    }
+}
+
+/// The number of voxels in a grid of size `dims`, if that is representable.
+fn voxel_count(dims: [usize; 3]) -> Option<usize> {
+    dims[0].checked_mul(dims[1])?.checked_mul(dims[2])
+}
+
+/// Decode a density grid file: the ASCII header `GRID nx ny nz` ended by a
+/// newline, then `nx * ny * nz` little-endian 32 bit floats running along
+/// x fastest, then y, then z.
+pub fn read_grid(data: &[u8]) -> Result<DensityGrid, String> {
+    let end = data
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or_else(|| "truncated grid header".to_string())?;
+    let header = std::str::from_utf8(&data[..end]).map_err(|_| "not a grid file".to_string())?;
+    let mut fields = header.split_whitespace();
+    if fields.next() != Some("GRID") {
+        return Err("not a grid file".to_string());
+    }
+    let mut dims = [0usize; 3];
+    for n in dims.iter_mut() {
+        let text = fields
+            .next()
+            .ok_or_else(|| "truncated grid header".to_string())?;
+        *n = text
+            .parse()
+            .map_err(|_| format!("invalid grid size '{}'", text))?;
+    }
+    // Sizes too large to count the densities' bytes are as bad as zero.
+    let count = voxel_count(dims);
+    let bytes = count.and_then(|count| count.checked_mul(4));
+    let (Some(count), Some(bytes)) = (count, bytes) else {
+        return Err(format!("invalid grid header '{}'", header));
+    };
+    if dims.contains(&0) || fields.next().is_some() {
+        return Err(format!("invalid grid header '{}'", header));
+    }
+    let body = &data[end + 1..];
+    if body.len() != bytes {
+        return Err(format!(
+            "expected {} densities, got {} bytes",
+            count,
+            body.len()
+        ));
+    }
+    let values: Vec<f32> = body
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    if let Some(bad) = values.iter().find(|d| !d.is_finite() || **d < 0.0) {
+        return Err(format!("invalid density {}", bad));
+    }
+    Ok(DensityGrid::new(dims, values))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interpolates_between_voxel_centres() {
+        // Density equal to x along a 4 voxel row, constant in y and z.
+        let grid = DensityGrid::from_fn([4, 2, 1], |p| p.x());
+        assert_eq!(grid.dims(), [4, 2, 1]);
+        assert_eq!(grid.max(), 0.875);
+        for x in [0.125, 0.3, 0.5, 0.77, 0.875] {
+            assert!((grid.density(Point3::new(x, 0.4, 0.9)) - x).abs() < 1e-6);
+        }
+        // Flat beyond the outermost centres, nothing outside the cube.
+        assert_eq!(grid.density(Point3::new(0.05, 0.5, 0.5)), 0.125);
+        assert_eq!(grid.density(Point3::new(1.0, 0.5, 0.5)), 0.875);
+        assert_eq!(grid.density(Point3::new(1.01, 0.5, 0.5)), 0.0);
+
+        let cloud = DensityGrid::cloud(16, 3, 4.);
+        assert!(cloud.density(Point3::new(0.5, 0.5, 0.5)) > 0.5);
+        assert_eq!(cloud.density(Point3::new(0.02, 0.5, 0.5)), 0.0);
+    }
+
+    #[test]
+    fn reads_grid_files() {
+        let mut file = b"GRID 2 1 1\n".to_vec();
+        for d in [0.5f32, 2.0] {
+            file.extend_from_slice(&d.to_le_bytes());
+        }
+        let grid = read_grid(&file).unwrap();
+        assert_eq!(grid.dims(), [2, 1, 1]);
+        assert_eq!(grid.max(), 2.0);
+        assert_eq!(grid.density(Point3::new(0.25, 0.5, 0.5)), 0.5);
+
+        assert_eq!(
+            read_grid(&file[..file.len() - 1]).unwrap_err(),
+            "expected 2 densities, got 7 bytes"
+        );
+        assert_eq!(
+            read_grid(b"GRID 2 0 1\n").unwrap_err(),
+            "invalid grid header 'GRID 2 0 1'"
+        );
+        assert_eq!(
+            read_grid(b"GRID 4294967296 4294967296 2\n").unwrap_err(),
+            "invalid grid header 'GRID 4294967296 4294967296 2'"
+        );
+        assert_eq!(read_grid(b"P6 2 1 1\n").unwrap_err(), "not a grid file");
+        let mut negative = b"GRID 1 1 1\n".to_vec();
+        negative.extend_from_slice(&(-1f32).to_le_bytes());
+        assert_eq!(read_grid(&negative).unwrap_err(), "invalid density -1");
+    }
+}
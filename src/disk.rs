@@ -0,0 +1,141 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::*;
+use crate::material::Material;
+use crate::quad::area_pdf;
+use crate::ray::Ray;
+use crate::rtweekend::random;
+use crate::vec3::*;
+
+/// A flat disk of `radius` around `center`, facing along `normal`. `u`
+/// goes round the rim and `v` out from the centre.
+#[derive(Clone)]
+pub struct Disk {
+    center: Point3,
+    radius: f32,
+    frame: Onb,
+    material: Arc<dyn Material>,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f32, material: Arc<dyn Material>) -> Self {
+        Self {
+            center,
+            radius,
+            frame: Onb::new(unit(normal)),
+            material,
+        }
+    }
+
+    fn intersect(&self, r: Ray, t_min: f32, t_max: f32) -> Option<f32> {
+        let normal = self.frame.w();
+        let denom = dot(normal, r.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = dot(normal, self.center - r.origin()) / denom;
+        if !(t_min..=t_max).contains(&t) {
+            return None;
+        }
+        if (r.at(t) - self.center).length_squared() > self.radius * self.radius {
+            return None;
+        }
+        Some(t)
+    }
+
+    fn record(&self, r: Ray, t: f32) -> HitRecord {
+        let p = r.at(t);
+        let offset = p - self.center;
+        let angle = dot(offset, self.frame.v()).atan2(dot(offset, self.frame.u()));
+        let mut rec = HitRecord {
+            p,
+            t,
+            material: self.material.clone(),
+            normal: Vec3::default(),
+            u: (angle / (2.0 * PI)).rem_euclid(1.0),
+            v: offset.length() / self.radius,
+            front_face: false,
+        };
+        rec.set_face_normal(r, self.frame.w());
+        rec
+    }
+
+    fn area(&self) -> f32 {
+        PI * self.radius * self.radius
+    }
+}
+
+/// How far a circle of `radius` facing along the unit `normal` reaches from
+/// its centre along each axis: `radius` times the sine of the angle between
+/// the axis and the normal.
+pub(crate) fn rim_extent(normal: Vec3, radius: f32) -> Vec3 {
+    let extent = |c: f32| radius * (1.0 - c * c).max(0.0).sqrt();
+    Vec3::new(extent(normal.x()), extent(normal.y()), extent(normal.z()))
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let t = self.intersect(r, t_min, t_max)?;
+        Some(self.record(r, t))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let half = rim_extent(self.frame.w(), self.radius) + Vec3::new(1e-4, 1e-4, 1e-4);
+        Some(Aabb::new(self.center - half, self.center + half))
+    }
+
+    fn sample_towards(&self, origin: Point3) -> Option<(HitRecord, f32)> {
+        let r = self.radius * random::<f32>().sqrt();
+        let phi = 2.0 * PI * random::<f32>();
+        let p = self.center + r * phi.cos() * self.frame.u() + r * phi.sin() * self.frame.v();
+        let pdf = area_pdf(p - origin, self.frame.w(), self.area());
+        if pdf == 0.0 || !pdf.is_finite() {
+            return None;
+        }
+        Some((self.record(Ray::new(origin, p - origin), 1.0), pdf))
+    }
+
+    fn pdf_towards(&self, origin: Point3, direction: Vec3) -> f32 {
+        match self.intersect(Ray::new(origin, direction), 0.0, f32::INFINITY) {
+            Some(t) => area_pdf(t * direction, self.frame.w(), self.area()),
+            None => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn hits_within_the_radius() {
+        crate::rtweekend::seed_rng(6);
+        let disk = Disk::new(
+            Point3::new(0., 1., 0.),
+            Vec3::new(0., -2., 0.),
+            1.,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        let up = |x, z| Ray::new(Point3::new(x, -1., z), Vec3::new(0., 1., 0.));
+        let rec = disk.hit(up(0.6, 0.), 0., f32::INFINITY).unwrap();
+        assert!((rec.t - 2.).abs() < 1e-5);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0., -1., 0.));
+        assert!((rec.v - 0.6).abs() < 1e-5);
+        assert!(disk.hit(up(0.8, 0.8), 0., f32::INFINITY).is_none());
+
+        let bbox = disk.bounding_box().unwrap();
+        assert!((bbox.max() - Point3::new(1., 1., 1.)).length() < 1e-3);
+        assert!((bbox.min() - Point3::new(-1., 1., -1.)).length() < 1e-3);
+
+        let origin = Point3::new(0.3, -2., 0.2);
+        for _ in 0..100 {
+            let (rec, pdf) = disk.sample_towards(origin).unwrap();
+            assert!((rec.p - disk.center).length() <= 1. + 1e-5);
+            assert!((disk.pdf_towards(origin, rec.p - origin) - pdf).abs() < 1e-3 * pdf);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::image::Image;
+use crate::vec3::Color;
+
+/// A rectangular block of pixels rendered as one unit of work.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Accumulation buffer holding the sum of the linear samples of each pixel.
+///
+/// Nothing is clamped or gamma encoded here; that happens when the averaged
+/// `Image` is written out.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Film {
+    width: usize,
+    height: usize,
+    sums: Vec<Color>,
+    samples: Vec<u32>,
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            sums: vec![Color::default(); width * height],
+            samples: vec![0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Split the film into tiles of at most `size` by `size` pixels, in
+    /// scanline order.
+    pub fn tiles(&self, size: usize) -> Vec<Tile> {
+        let size = size.max(1);
+        let mut tiles = Vec::new();
+        for y in (0..self.height).step_by(size) {
+            for x in (0..self.width).step_by(size) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: size.min(self.width - x),
+                    height: size.min(self.height - y),
+                });
+            }
+        }
+        tiles
+    }
+
+    pub fn add_sample(&mut self, x: usize, y: usize, color: Color) {
+        let i = y * self.width + x;
+        self.sums[i] += color;
+        self.samples[i] += 1;
+    }
+
+    /// Add the per-pixel sums of a rendered tile, row-major within the tile,
+    /// each made of `samples` samples.
+    pub fn add_tile(&mut self, tile: &Tile, sums: &[Color], samples: u32) {
+        assert_eq!(sums.len(), tile.width * tile.height);
+        for (row, tile_row) in sums.chunks(tile.width).enumerate() {
+            let start = (tile.y + row) * self.width + tile.x;
+            for (i, &sum) in tile_row.iter().enumerate() {
+                self.sums[start + i] += sum;
+                self.samples[start + i] += samples;
+            }
+        }
+    }
+
+    /// Average the accumulated samples; pixels without any stay black.
+    pub fn to_image(&self) -> Image {
+        let mut image = Image::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let i = y * self.width + x;
+                if self.samples[i] > 0 {
+                    image.set(x, y, self.sums[i] / self.samples[i] as f32);
+                }
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tiles_cover_film_once() {
+        let mut film = Film::new(37, 21);
+        for tile in film.tiles(8) {
+            let sums = vec![Color::new(2., 4., 6.); tile.width * tile.height];
+            film.add_tile(&tile, &sums, 2);
+        }
+        let image = film.to_image();
+        assert!(image.pixels().iter().all(|&c| c == Color::new(1., 2., 3.)));
+    }
+}
@@ -1,37 +0,0 @@
-use std::sync::Arc;
-
-use crate::hittable::{HitRecord, Hittable};
-pub struct HittableList {
-    objects: Vec<Arc<dyn Hittable>>,
-}
-
-impl HittableList {
-    pub fn new() -> Self {
-        Self {
-            objects: Vec::new(),
-        }
-    }
-
-    #[allow(dead_code)]
-    pub fn clear(&mut self) {
-        self.objects.clear();
-    }
-
-    pub fn add(&mut self, object: Arc<dyn Hittable>) {
-        self.objects.push(object);
-    }
-}
-
-impl Hittable for HittableList {
-    fn hit(&self, r: crate::ray::Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
-        let mut rec = None;
-        let mut closest_so_far = t_max;
-        for object in self.objects.iter() {
-            if let Some(temp_rec) = object.hit(r, t_min, closest_so_far) {
-                closest_so_far = temp_rec.t;
-                rec = Some(temp_rec);
-            }
-        }
-        rec
-    }
-}
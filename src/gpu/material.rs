@@ -1,128 +0,0 @@
-use crate::hittable::*;
-use crate::ray::Ray;
-use crate::vec3::*;
-use rand::prelude::*;
-
-pub trait Material: Send + Sync {
-    fn scatter(&self, r_in: Ray, rec: HitRecord) -> Option<(Color, Ray)>;
-}
-
-pub struct Lambertian {
-    albedo: Color,
-}
-
-impl Lambertian {
-    pub fn new(albedo: Color) -> Self {
-        Self { albedo }
-    }
-}
-
-impl Material for Lambertian {
-    fn scatter(&self, _r_in: Ray, rec: HitRecord) -> Option<(Color, Ray)> {
-        let mut scatter_direction = rec.normal + random_unit_vector();
-
-        // Catch degenerate scatter direction
-        if scatter_direction.near_zero() {
-            scatter_direction = rec.normal;
-        }
-
-        let scattered = Ray::new(rec.p, scatter_direction);
-        let attenuation = self.albedo;
-        Some((attenuation, scattered))
-    }
-}
-
-pub struct Metal {
-    albedo: Color,
-    fuzz: f32,
-}
-
-impl Metal {
-    pub fn new(albedo: Color, fuzz: f32) -> Self {
-        Self {
-            albedo,
-            fuzz: fuzz.clamp(f32::MIN, 1.0),
-        }
-    }
-}
-
-impl Material for Metal {
-    fn scatter(&self, r_in: Ray, rec: HitRecord) -> Option<(Color, Ray)> {
-        let reflected = reflect(unit(r_in.direction()), rec.normal);
-        let scattered = Ray::new(rec.p, reflected + self.fuzz * random_in_unit_sphere());
-        let attenuation = self.albedo;
-        if dot(scattered.direction(), rec.normal) > 0. {
-            Some((attenuation, scattered))
-        } else {
-            None
-        }
-    }
-}
-
-pub struct Dielectric {
-    ir: f32,
-}
-
-impl Dielectric {
-    pub fn new(index_of_refraction: f32) -> Self {
-        Self {
-            ir: index_of_refraction,
-        }
-    }
-
-    fn reflectance(cosine: f32, ref_idx: f32) -> f32 {
-        // Use Schlick's approximation for reflectance.
-        let r0 = (1. - ref_idx) / (1. + ref_idx);
-        let r0 = r0 * r0;
-        r0 + (1. - r0) * (1. - cosine).powi(5)
-    }
-}
-
-impl Material for Dielectric {
-    fn scatter(&self, r_in: Ray, rec: HitRecord) -> Option<(Color, Ray)> {
-        let attenuation = Color::new(1.0, 1.0, 1.0);
-        let refraction_ratio = if rec.front_face {
-            1.0 / self.ir
-        } else {
-            self.ir
-        };
-
-        let unit_direction = unit(r_in.direction());
-        let cos_theta = dot(-unit_direction, rec.normal).min(1.0);
-        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
-
-        let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let direction;
-        if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > random::<f32>() {
-            direction = reflect(unit_direction, rec.normal);
-        } else {
-            direction = refract(unit_direction, rec.normal, refraction_ratio);
-        }
-
-        let scattered = Ray::new(rec.p, direction);
-        Some((attenuation, scattered))
-    }
-}
-
-pub struct TestMaterial {
-    albedo: Color,
-}
-
-impl TestMaterial {
-    pub fn new(albedo: Color) -> Self {
-        Self { albedo }
-    }
-}
-
-impl Material for TestMaterial {
-    fn scatter(&self, r_in: Ray, rec: HitRecord) -> Option<(Color, Ray)> {
-        let reflected = reflect(unit(r_in.direction()), rec.normal);
-        let scattered = Ray::new(rec.p, reflected);
-        let attenuation = self.albedo;
-        if dot(scattered.direction(), rec.normal) > 0. {
-            Some((attenuation, scattered))
-        } else {
-            None
-        }
-    }
-}
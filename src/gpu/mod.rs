@@ -1,8 +1,5 @@
-mod hittable;
-mod hittable_list;
-mod material;
-mod ray;
+//! Batched integrator and ArrayFire-backed vector arrays, built with the
+//! `gpu` feature.
+
 pub mod ray_color;
-mod scene;
-mod sphere;
-mod vec3;
+pub mod vec3;
@@ -1,25 +0,0 @@
-use crate::vec3::*;
-
-#[derive(Default, Debug, Clone, Copy, PartialEq)]
-pub struct Ray {
-    origin: Vec3,
-    direction: Vec3,
-}
-
-impl Ray {
-    pub fn new(origin: Vec3, direction: Vec3) -> Self {
-        Self { origin, direction }
-    }
-
-    pub fn origin(&self) -> Vec3 {
-        self.origin
-    }
-
-    pub fn direction(&self) -> Vec3 {
-        self.direction
-    }
-
-    pub fn at(&self, t: f32) -> Vec3 {
-        self.origin + t * self.direction
-    }
-}
@@ -1,59 +0,0 @@
-use std::sync::Arc;
-
-use crate::hittable::*;
-use crate::material::Material;
-use crate::ray::Ray;
-use crate::vec3::*;
-
-#[derive(Clone)]
-pub struct Sphere {
-    center: Point3,
-    radius: f32,
-    material: Arc<dyn Material>,
-}
-
-impl Sphere {
-    pub fn new(center: Point3, radius: f32, material: Arc<dyn Material>) -> Self {
-        Self {
-            center,
-            radius,
-            material,
-        }
-    }
-}
-
-impl Hittable for Sphere {
-    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
-        let oc = r.origin() - self.center;
-        let a = r.direction().length_squared();
-        let half_b = dot(oc, r.direction());
-        let c = oc.length_squared() - self.radius * self.radius;
-
-        let discriminant = half_b * half_b - a * c;
-        if discriminant < 0.0 {
-            return None;
-        }
-        let sqrtd = discriminant.sqrt();
-
-        // Find the nearest root that lies in the acceptable range.
-        let mut root = (-half_b - sqrtd) / a;
-        if root < t_min || t_max < root {
-            root = (-half_b + sqrtd) / a;
-            if root < t_min || t_max < root {
-                return None;
-            }
-        }
-
-        let mut rec = HitRecord {
-            p: r.at(root),
-            t: root,
-            material: self.material.clone(),
-            normal: Vec3::default(),
-            front_face: false,
-        };
-        let outward_normal = (rec.p - self.center) / self.radius;
-        rec.set_face_normal(r, outward_normal);
-
-        Some(rec)
-    }
-}
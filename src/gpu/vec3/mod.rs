@@ -1,4 +1,2 @@
-//mod utils;
 mod vec3array;
-//pub use utils::*;
 pub use vec3array::*;
@@ -0,0 +1,385 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::*;
+use crate::image::Image;
+use crate::input::linear_to_srgb;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::triangle::intersect_triangle;
+use crate::vec3::*;
+
+/// The lowest and highest point within each block of cells, one level per
+/// halving of the grid: a min-max mip pyramid.
+struct MinMaxLevel {
+    width: usize,
+    depth: usize,
+    ranges: Vec<(f32, f32)>,
+}
+
+/// Terrain from a grid of heights: `nx` by `nz` samples spread evenly over
+/// `size.x` by `size.z` from the corner `min`, each raised `size.y` times
+/// its height above `min.y`. Every cell between four samples is split into
+/// two triangles, smooth shaded with normals from the slope of the grid,
+/// and the texture coordinates run from 0 to 1 across the whole field.
+///
+/// Rays descend a min-max mip pyramid over the cells, so only the few
+/// cells near the ray are ever tested, however large the grid.
+pub struct Heightfield {
+    nx: usize,
+    nz: usize,
+    min: Point3,
+    size: Vec3,
+    /// The height of each sample in world space, along x fastest.
+    heights: Vec<f32>,
+    normals: Vec<Vec3>,
+    /// From single cells up to one block covering the whole grid.
+    levels: Vec<MinMaxLevel>,
+    material: Arc<dyn Material>,
+}
+
+/// Where along the ray a triangle was hit, its corner samples, and the
+/// barycentric coordinates of the hit.
+type TriangleHit = (f32, [(usize, usize); 3], f32, f32);
+
+impl Heightfield {
+    /// `heights` runs along x fastest, then z; there must be at least two
+    /// samples each way.
+    pub fn new(
+        nx: usize,
+        nz: usize,
+        heights: &[f32],
+        min: Point3,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        assert!(nx >= 2 && nz >= 2, "a heightfield needs 2 by 2 samples");
+        assert_eq!(heights.len(), nx * nz, "heightfield size");
+        assert!(
+            size.x() > 0.0 && size.z() > 0.0,
+            "the field must have an area"
+        );
+        let heights: Vec<f32> = heights.iter().map(|h| min.y() + size.y() * h).collect();
+
+        let (dx, dz) = (size.x() / (nx - 1) as f32, size.z() / (nz - 1) as f32);
+        let at = |i: usize, j: usize| heights[i + nx * j];
+        let mut normals = Vec::with_capacity(nx * nz);
+        for j in 0..nz {
+            for i in 0..nx {
+                // Central differences, one-sided along the edges.
+                let (i0, i1) = (i.saturating_sub(1), (i + 1).min(nx - 1));
+                let (j0, j1) = (j.saturating_sub(1), (j + 1).min(nz - 1));
+                let slope_x = (at(i1, j) - at(i0, j)) / ((i1 - i0) as f32 * dx);
+                let slope_z = (at(i, j1) - at(i, j0)) / ((j1 - j0) as f32 * dz);
+                normals.push(unit(Vec3::new(-slope_x, 1.0, -slope_z)));
+            }
+        }
+
+        let mut cells = MinMaxLevel {
+            width: nx - 1,
+            depth: nz - 1,
+            ranges: Vec::with_capacity((nx - 1) * (nz - 1)),
+        };
+        for j in 0..nz - 1 {
+            for i in 0..nx - 1 {
+                let corners = [at(i, j), at(i + 1, j), at(i, j + 1), at(i + 1, j + 1)];
+                cells.ranges.push((
+                    corners.iter().copied().fold(f32::INFINITY, f32::min),
+                    corners.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+                ));
+            }
+        }
+        let mut levels = vec![cells];
+        while let Some(below) = levels.last().filter(|l| l.width > 1 || l.depth > 1) {
+            let (width, depth) = (below.width.div_ceil(2), below.depth.div_ceil(2));
+            let mut ranges = Vec::with_capacity(width * depth);
+            for j in 0..depth {
+                for i in 0..width {
+                    let mut range = (f32::INFINITY, f32::NEG_INFINITY);
+                    for (ci, cj) in [
+                        (2 * i, 2 * j),
+                        (2 * i + 1, 2 * j),
+                        (2 * i, 2 * j + 1),
+                        (2 * i + 1, 2 * j + 1),
+                    ] {
+                        if ci < below.width && cj < below.depth {
+                            let (lo, hi) = below.ranges[ci + below.width * cj];
+                            range = (range.0.min(lo), range.1.max(hi));
+                        }
+                    }
+                    ranges.push(range);
+                }
+            }
+            levels.push(MinMaxLevel {
+                width,
+                depth,
+                ranges,
+            });
+        }
+
+        Self {
+            nx,
+            nz,
+            min,
+            size,
+            heights,
+            normals,
+            levels,
+            material,
+        }
+    }
+
+    /// Heights from the brightness of each pixel, the average of its three
+    /// channels, with x along the rows and z down the columns. Heights are
+    /// the values the file stored, not light: `srgb` says the image was
+    /// decoded from sRGB, as `load_image` does for all but `.hdr` and
+    /// `.pfm` files, and is encoded again first.
+    pub fn from_image(
+        image: &Image,
+        srgb: bool,
+        min: Point3,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let stored = |c: f32| if srgb { linear_to_srgb(c) } else { c };
+        let heights: Vec<f32> = image
+            .pixels()
+            .iter()
+            .map(|c| (stored(c.x()) + stored(c.y()) + stored(c.z())) / 3.0)
+            .collect();
+        Self::new(image.width(), image.height(), &heights, min, size, material)
+    }
+
+    /// The number of samples along x and z.
+    pub fn resolution(&self) -> (usize, usize) {
+        (self.nx, self.nz)
+    }
+
+    fn sample(&self, i: usize, j: usize) -> Point3 {
+        Point3::new(
+            self.min.x() + self.size.x() * i as f32 / (self.nx - 1) as f32,
+            self.heights[i + self.nx * j],
+            self.min.z() + self.size.z() * j as f32 / (self.nz - 1) as f32,
+        )
+    }
+
+    /// The two triangles of cell `(i, j)`, wound to face up, with the
+    /// samples of their corners.
+    fn triangles(&self, i: usize, j: usize) -> [[(usize, usize); 3]; 2] {
+        [
+            [(i, j), (i, j + 1), (i + 1, j + 1)],
+            [(i, j), (i + 1, j + 1), (i + 1, j)],
+        ]
+    }
+
+    /// Descend into block `(i, j)` of `level` if the ray passes through its
+    /// box before the nearest hit found so far, nearer children first.
+    fn traverse(
+        &self,
+        r: Ray,
+        level: usize,
+        (i, j): (usize, usize),
+        t_min: f32,
+        nearest: &mut Option<TriangleHit>,
+        t_max: &mut f32,
+    ) {
+        let blocks = &self.levels[level];
+        if i >= blocks.width || j >= blocks.depth {
+            return;
+        }
+        let (lo, hi) = blocks.ranges[i + blocks.width * j];
+        let span = 1 << level;
+        let corner = self.sample(i * span, j * span);
+        let far = self.sample(
+            ((i + 1) * span).min(self.nx - 1),
+            ((j + 1) * span).min(self.nz - 1),
+        );
+        // Padded so that flat blocks still have a volume.
+        let bbox = Aabb::new(
+            Point3::new(corner.x(), lo - 1e-4, corner.z()),
+            Point3::new(far.x(), hi + 1e-4, far.z()),
+        );
+        if bbox.clip(r, t_min, *t_max).is_none() {
+            return;
+        }
+
+        if level == 0 {
+            for corners in self.triangles(i, j) {
+                let vertices = corners.map(|(i, j)| self.sample(i, j));
+                if let Some((t, b1, b2)) = intersect_triangle(r, vertices, t_min, *t_max) {
+                    *t_max = t;
+                    *nearest = Some((t, corners, b1, b2));
+                }
+            }
+            return;
+        }
+        let xs = if r.direction().x() < 0.0 {
+            [1, 0]
+        } else {
+            [0, 1]
+        };
+        let zs = if r.direction().z() < 0.0 {
+            [1, 0]
+        } else {
+            [0, 1]
+        };
+        for dj in zs {
+            for di in xs {
+                self.traverse(
+                    r,
+                    level - 1,
+                    (2 * i + di, 2 * j + dj),
+                    t_min,
+                    nearest,
+                    t_max,
+                );
+            }
+        }
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let mut nearest = None;
+        let mut t_max = t_max;
+        self.traverse(
+            r,
+            self.levels.len() - 1,
+            (0, 0),
+            t_min,
+            &mut nearest,
+            &mut t_max,
+        );
+        let (t, corners, b1, b2) = nearest?;
+
+        let p = r.at(t);
+        let mut rec = HitRecord {
+            p,
+            t,
+            material: self.material.clone(),
+            normal: Vec3::default(),
+            u: (p.x() - self.min.x()) / self.size.x(),
+            v: (p.z() - self.min.z()) / self.size.z(),
+            front_face: false,
+        };
+        let [v0, v1, v2] = corners.map(|(i, j)| self.sample(i, j));
+        let geometric_normal = unit(cross(v1 - v0, v2 - v0));
+        rec.set_face_normal(r, geometric_normal);
+
+        let [n0, n1, n2] = corners.map(|(i, j)| self.normals[i + self.nx * j]);
+        let shading_normal = unit((1.0 - b1 - b2) * n0 + b1 * n1 + b2 * n2);
+        // Both point up, so the shading normal only needs flipping for rays
+        // from below.
+        rec.normal = if rec.front_face {
+            shading_normal
+        } else {
+            -shading_normal
+        };
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let (lo, hi) = self.levels.last().unwrap().ranges[0];
+        Some(Aabb::new(
+            Point3::new(self.min.x(), lo - 1e-4, self.min.z()),
+            Point3::new(
+                self.min.x() + self.size.x(),
+                hi + 1e-4,
+                self.min.z() + self.size.z(),
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hittable_list::HittableList;
+    use crate::material::Lambertian;
+    use crate::rtweekend::{random_float, seed_rng};
+    use crate::triangle::Triangle;
+
+    fn bumpy(nx: usize, nz: usize) -> Vec<f32> {
+        (0..nx * nz)
+            .map(|k| {
+                let (x, z) = ((k % nx) as f32, (k / nx) as f32);
+                0.5 + 0.3 * (0.3 * x).sin() * (0.4 * z).cos()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_hits_as_the_triangles() {
+        seed_rng(23);
+        let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let (nx, nz) = (37, 21);
+        let field = Heightfield::new(
+            nx,
+            nz,
+            &bumpy(nx, nz),
+            Point3::new(-4., -1., -2.),
+            Vec3::new(8., 1., 4.),
+            material.clone(),
+        );
+        assert_eq!(field.resolution(), (37, 21));
+        let mut triangles = HittableList::new();
+        for j in 0..nz - 1 {
+            for i in 0..nx - 1 {
+                for corners in field.triangles(i, j) {
+                    let [a, b, c] = corners.map(|(i, j)| field.sample(i, j));
+                    triangles.add(Arc::new(Triangle::new(a, b, c, material.clone())));
+                }
+            }
+        }
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let origin = Point3::new(
+                random_float(-6., 6.),
+                random_float(-2., 3.),
+                random_float(-4., 4.),
+            );
+            let target = Point3::new(random_float(-4., 4.), 0., random_float(-2., 2.));
+            let r = Ray::new(origin, target - origin);
+            let expected = triangles.hit(r, 0.001, f32::INFINITY);
+            let rec = field.hit(r, 0.001, f32::INFINITY);
+            assert_eq!(rec.is_some(), expected.is_some());
+            if let (Some(rec), Some(expected)) = (rec, expected) {
+                assert!((rec.t - expected.t).abs() < 1e-4);
+                assert_eq!(rec.front_face, expected.front_face);
+                // Smooth shading stays close to the facets.
+                assert!(dot(rec.normal, expected.normal) > 0.9);
+                assert!((0.0..=1.0).contains(&rec.u) && (0.0..=1.0).contains(&rec.v));
+                hits += 1;
+            }
+        }
+        assert!(hits > 500);
+    }
+
+    #[test]
+    fn slopes_and_images() {
+        // A ramp rising 1 along x over a 2 by 1 field.
+        let mut image = Image::new(3, 2);
+        for z in 0..2 {
+            for x in 0..3 {
+                let h = x as f32 / 2.;
+                image.set(x, z, Color::new(h, h, h));
+            }
+        }
+        let field = Heightfield::from_image(
+            &image,
+            false,
+            Point3::default(),
+            Vec3::new(2., 1., 1.),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        let down = Ray::new(Point3::new(0.5, 5., 0.25), Vec3::new(0., -1., 0.));
+        let rec = field.hit(down, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.p.y() - 0.25).abs() < 1e-5);
+        assert!((rec.normal - unit(Vec3::new(-0.5, 1., 0.))).length() < 1e-5);
+        assert!((rec.u - 0.25).abs() < 1e-5 && (rec.v - 0.25).abs() < 1e-5);
+        let bbox = field.bounding_box().unwrap();
+        assert!(bbox.min().y() < 0. && bbox.max().y() > 1.);
+        let up = Ray::new(Point3::new(1.5, -1., 0.5), Vec3::new(0., 1., 0.));
+        assert!(!field.hit(up, 0.001, f32::INFINITY).unwrap().front_face);
+    }
+}
@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::density_grid::DensityGrid;
+use crate::hittable::*;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::rtweekend::random;
+use crate::vec3::*;
+
+/// Smoke or cloud whose density varies through a box: a `DensityGrid`
+/// stretched over it, scaled by `density`. Collisions are found by delta
+/// tracking and shadow rays estimated by ratio tracking, both against the
+/// grid's largest density, so neither steps through the voxels.
+#[derive(Clone)]
+pub struct HeterogeneousMedium {
+    grid: Arc<DensityGrid>,
+    bbox: Aabb,
+    density: f32,
+    /// The largest density anywhere inside, bounding the real one.
+    majorant: f32,
+    phase_function: Arc<dyn Material>,
+}
+
+impl HeterogeneousMedium {
+    /// The grid fills the box from `min` to `max`, which must have a volume.
+    pub fn new(
+        grid: Arc<DensityGrid>,
+        min: Point3,
+        max: Point3,
+        density: f32,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        assert!(
+            (0..3).all(|a| min[a] < max[a]),
+            "the box must have a volume"
+        );
+        assert!(density > 0.0, "density must be positive");
+        Self {
+            majorant: density * grid.max(),
+            grid,
+            bbox: Aabb::new(min, max),
+            density,
+            phase_function,
+        }
+    }
+
+    pub fn grid(&self) -> &Arc<DensityGrid> {
+        &self.grid
+    }
+
+    fn density_at(&self, p: Point3) -> f32 {
+        let (min, max) = (self.bbox.min(), self.bbox.max());
+        let local = |a: usize| (p[a] - min[a]) / (max[a] - min[a]);
+        self.density * self.grid.density(Point3::new(local(0), local(1), local(2)))
+    }
+
+    /// Tentative collisions along `r` within `[t_min, t_max]` at the rate of
+    /// the majorant, calling `visit` with each until it returns `false`.
+    fn track(&self, r: Ray, t_min: f32, t_max: f32, mut visit: impl FnMut(f32) -> bool) {
+        if self.majorant == 0.0 {
+            return;
+        }
+        let Some((mut t, t_end)) = self.bbox.clip(r, t_min, t_max) else {
+            return;
+        };
+        let rate = self.majorant * r.direction().length();
+        loop {
+            t -= (1.0 - random::<f32>()).ln() / rate;
+            if t >= t_end || !visit(t) {
+                return;
+            }
+        }
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    /// Delta tracking: each tentative collision is a real one with the
+    /// probability of the density there over the majorant.
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let mut collision = None;
+        self.track(r, t_min, t_max, |t| {
+            if random::<f32>() * self.majorant < self.density_at(r.at(t)) {
+                collision = Some(t);
+            }
+            collision.is_none()
+        });
+        let t = collision?;
+        Some(HitRecord {
+            p: r.at(t),
+            t,
+            material: self.phase_function.clone(),
+            // Arbitrary: phase functions ignore it.
+            normal: Vec3::new(1., 0., 0.),
+            u: 0.,
+            v: 0.,
+            front_face: true,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+
+    /// Ratio tracking: rather than stopping at the first real collision,
+    /// weight by the chance of each tentative one being a null collision.
+    fn transmittance(&self, r: Ray, t_min: f32, t_max: f32) -> f32 {
+        let mut transmittance = 1.0;
+        self.track(r, t_min, t_max, |t| {
+            transmittance *= 1.0 - self.density_at(r.at(t)) / self.majorant;
+            transmittance > 0.0
+        });
+        transmittance
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bvh::accelerate;
+    use crate::hittable_list::HittableList;
+    use crate::material::Isotropic;
+    use crate::rtweekend::seed_rng;
+
+    #[test]
+    fn tracking_matches_the_optical_depth() {
+        seed_rng(22);
+        // Dense in the front half along -z, empty behind it: the optical
+        // depth straight through is 1 * 2 units * 0.5.
+        let grid = DensityGrid::from_fn([1, 1, 8], |p| if p.z() > 0.5 { 1. } else { 0. });
+        let medium = HeterogeneousMedium::new(
+            Arc::new(grid),
+            Point3::new(-1., -1., -2.),
+            Point3::new(1., 1., 2.),
+            0.5,
+            Arc::new(Isotropic::new(Color::new(0.9, 0.9, 0.9))),
+        );
+        let r = Ray::new(Point3::new(0., 0., 5.), Vec3::new(0., 0., -2.));
+        let expected = (-1f32).exp();
+        let n = 20000;
+        let mut passed = 0;
+        let mut transmittance = 0.0;
+        for _ in 0..n {
+            match medium.hit(r, 0.001, f32::INFINITY) {
+                // Interpolation softens the step over half a voxel.
+                Some(rec) => assert!(rec.p.z() > -0.25 && rec.p.z() <= 2.),
+                None => passed += 1,
+            }
+            let estimate = medium.transmittance(r, 0.001, f32::INFINITY);
+            assert!((0.0..=1.0).contains(&estimate));
+            transmittance += estimate / n as f32;
+        }
+        assert!((passed as f32 / n as f32 - expected).abs() < 0.01);
+        assert!((transmittance - expected).abs() < 0.01);
+
+        // The same through an accelerated world.
+        let mut list = HittableList::new();
+        list.add(Arc::new(medium.clone()));
+        let world = accelerate(&list);
+        let passed = (0..n)
+            .filter(|_| world.hit(r, 0.001, f32::INFINITY).is_none())
+            .count();
+        assert!((passed as f32 / n as f32 - expected).abs() < 0.01);
+
+        // Nothing in the empty half, or outside the box.
+        let behind = Ray::new(Point3::new(0., 0., -0.5), Vec3::new(0., 0., -1.));
+        assert!(medium.hit(behind, 0.001, f32::INFINITY).is_none());
+        assert_eq!(medium.transmittance(behind, 0.001, f32::INFINITY), 1.);
+        let beside = Ray::new(Point3::new(3., 0., 5.), Vec3::new(0., 0., -1.));
+        assert_eq!(medium.transmittance(beside, 0.001, f32::INFINITY), 1.);
+    }
+}
@@ -1,17 +1,20 @@
 use std::sync::Arc;
 
+use crate::aabb::Aabb;
 use crate::material::Material;
 use crate::ray::*;
 use crate::vec3::*;
 
-//class material;
-
+/// Where and how a ray hit a surface.
 #[derive(Clone)]
 pub struct HitRecord {
     pub p: Point3,
     pub normal: Vec3,
     pub material: Arc<dyn Material>,
     pub t: f32,
+    /// Surface coordinates of `p`, for textures.
+    pub u: f32,
+    pub v: f32,
     pub front_face: bool,
 }
 
@@ -26,6 +29,91 @@ impl HitRecord {
     }
 }
 
+/// Where a line passes through a solid: in at `enter`, out at `exit`.
+#[derive(Clone)]
+pub struct Interval {
+    pub enter: HitRecord,
+    pub exit: HitRecord,
+}
+
+/// Anything a ray can intersect.
 pub trait Hittable: Send + Sync {
     fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord>;
+
+    /// Axis-aligned box enclosing the object, or `None` if it is unbounded.
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    /// Whether anything lies on the ray between `t_min` and `t_max`. Shadow
+    /// rays need no hit record, so aggregates can stop at the first hit.
+    fn occluded(&self, r: Ray, t_min: f32, t_max: f32) -> bool {
+        self.hit(r, t_min, t_max).is_some()
+    }
+
+    /// The fraction of light getting through between `t_min` and `t_max`:
+    /// zero behind anything opaque, in between for participating media.
+    /// Media may give a noisy but unbiased estimate.
+    fn transmittance(&self, r: Ray, t_min: f32, t_max: f32) -> f32 {
+        if self.occluded(r, t_min, t_max) {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Pick a point on the surface as seen from `origin`, for sampling it as
+    /// a light, together with the solid angle density of the direction
+    /// towards it. `None` for shapes that cannot be sampled.
+    fn sample_towards(&self, _origin: Point3) -> Option<(HitRecord, f32)> {
+        None
+    }
+
+    /// Solid angle density with which `sample_towards(origin)` picks the
+    /// point a ray from `origin` along `direction` hits first.
+    fn pdf_towards(&self, _origin: Point3, _direction: Vec3) -> f32 {
+        0.0
+    }
+
+    /// Whether the surface encloses a volume, so that `intervals` can say
+    /// where a ray is inside it.
+    fn is_closed(&self) -> bool {
+        false
+    }
+
+    /// Every stretch of the whole line through `r`, at any `t`, that lies
+    /// inside the object, in order along the ray. Only closed objects have
+    /// an inside; CSG combines these.
+    fn intervals(&self, _r: Ray) -> Vec<Interval> {
+        Vec::new()
+    }
+}
+
+/// The stretch between the first and last crossing of a line with a
+/// convex surface, which stays right when the line grazes an edge and
+/// crosses two faces at once.
+pub fn convex_interval(crossings: impl IntoIterator<Item = HitRecord>) -> Vec<Interval> {
+    let mut enter: Option<HitRecord> = None;
+    let mut exit: Option<HitRecord> = None;
+    for rec in crossings {
+        if enter.as_ref().is_none_or(|e| rec.t < e.t) {
+            enter = Some(rec.clone());
+        }
+        if exit.as_ref().is_none_or(|e| rec.t > e.t) {
+            exit = Some(rec);
+        }
+    }
+    match (enter, exit) {
+        (Some(enter), Some(exit)) if enter.t < exit.t => vec![Interval { enter, exit }],
+        _ => Vec::new(),
+    }
+}
+
+/// Pair up the crossings of a line with a closed surface, sorted by `t`:
+/// coming from infinitely far away, the line alternately enters and leaves.
+pub fn pair_crossings(crossings: impl IntoIterator<Item = HitRecord>) -> Vec<Interval> {
+    let mut crossings = crossings.into_iter();
+    let mut intervals = Vec::new();
+    while let (Some(enter), Some(exit)) = (crossings.next(), crossings.next()) {
+        intervals.push(Interval { enter, exit });
+    }
+    intervals
 }
@@ -1,6 +1,13 @@
 use std::sync::Arc;
 
+use crate::aabb::{surrounding_box, Aabb};
 use crate::hittable::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::rtweekend::random;
+use crate::vec3::{Point3, Vec3};
+
+/// A flat list of objects, tested one after another.
+#[derive(Default, Clone)]
 pub struct HittableList {
     objects: Vec<Arc<dyn Hittable>>,
 }
@@ -20,10 +27,18 @@ impl HittableList {
     pub fn add(&mut self, object: Arc<dyn Hittable>) {
         self.objects.push(object);
     }
+
+    pub fn objects(&self) -> &[Arc<dyn Hittable>] {
+        &self.objects
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
 }
 
 impl Hittable for HittableList {
-    fn hit(&self, r: crate::ray::Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
         let mut rec = None;
         let mut closest_so_far = t_max;
         for object in self.objects.iter() {
@@ -34,4 +49,54 @@ impl Hittable for HittableList {
         }
         rec
     }
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut objects = self.objects.iter();
+        let mut output_box = objects.next()?.bounding_box()?;
+        for object in objects {
+            output_box = surrounding_box(output_box, object.bounding_box()?);
+        }
+        Some(output_box)
+    }
+
+    fn occluded(&self, r: Ray, t_min: f32, t_max: f32) -> bool {
+        self.objects
+            .iter()
+            .any(|object| object.occluded(r, t_min, t_max))
+    }
+
+    fn transmittance(&self, r: Ray, t_min: f32, t_max: f32) -> f32 {
+        let mut transmittance = 1.0;
+        for object in &self.objects {
+            transmittance *= object.transmittance(r, t_min, t_max);
+            if transmittance == 0.0 {
+                break;
+            }
+        }
+        transmittance
+    }
+
+    /// Samples one object picked uniformly; the density is that of the whole
+    /// list, as several objects may lie in the same direction.
+    fn sample_towards(&self, origin: Point3) -> Option<(HitRecord, f32)> {
+        if self.objects.is_empty() {
+            return None;
+        }
+        let index =
+            ((random::<f32>() * self.objects.len() as f32) as usize).min(self.objects.len() - 1);
+        let (rec, _) = self.objects[index].sample_towards(origin)?;
+        let pdf = self.pdf_towards(origin, rec.p - origin);
+        Some((rec, pdf))
+    }
+
+    fn pdf_towards(&self, origin: Point3, direction: Vec3) -> f32 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+        let total: f32 = self
+            .objects
+            .iter()
+            .map(|object| object.pdf_towards(origin, direction))
+            .sum();
+        total / self.objects.len() as f32
+    }
 }
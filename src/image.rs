@@ -0,0 +1,53 @@
+use std::io::{Result, Write};
+
+use crate::color::write_color;
+use crate::vec3::Color;
+
+/// A rendered image of linear, already averaged `Color`s.
+///
+/// Rows are stored top to bottom, the order image files want them in.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color::default(); width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    /// Write the image as an ASCII (P3) PPM with gamma 2 encoding.
+    pub fn write_ppm<W: Write>(&self, out: &mut W) -> Result<()> {
+        writeln!(out, "P3\n{} {}\n255", self.width, self.height)?;
+        for &pixel in self.pixels.iter() {
+            write_color(out, pixel, 1)?;
+        }
+        Ok(())
+    }
+}
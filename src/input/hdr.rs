@@ -0,0 +1,255 @@
+//! Readers for the floating point formats written by `output`.
+
+use crate::image::Image;
+use crate::vec3::Color;
+
+/// Decode a colour (`PF`) or greyscale (`Pf`) PFM of either byte order.
+pub fn read_pfm(data: &[u8]) -> Result<Image, String> {
+    let mut pos = 0;
+    let mut tokens = Vec::with_capacity(4);
+    while tokens.len() < 4 {
+        while data.get(pos).is_some_and(|b| b.is_ascii_whitespace()) {
+            pos += 1;
+        }
+        let start = pos;
+        while data.get(pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+            pos += 1;
+        }
+        if start == pos {
+            return Err("truncated PFM header".to_string());
+        }
+        tokens.push(String::from_utf8_lossy(&data[start..pos]).into_owned());
+    }
+    // A single whitespace byte separates the header from the data.
+    pos += 1;
+
+    let channels = match tokens[0].as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        _ => return Err("not a PFM file".to_string()),
+    };
+    let (width, height) = dimensions(&tokens[1], &tokens[2])?;
+    let scale: f32 = tokens[3]
+        .parse()
+        .map_err(|_| format!("invalid scale '{}'", tokens[3]))?;
+    let little_endian = scale < 0.0;
+
+    let row_len = width * channels;
+    let pixel_data = data.get(pos..pos + 4 * row_len * height);
+    let pixel_data = pixel_data.ok_or_else(|| "truncated pixel data".to_string())?;
+    let values: Vec<f32> = pixel_data
+        .chunks(4)
+        .map(|b| {
+            let bytes = b.try_into().unwrap();
+            if little_endian {
+                f32::from_le_bytes(bytes)
+            } else {
+                f32::from_be_bytes(bytes)
+            }
+        })
+        .collect();
+
+    let mut image = Image::new(width, height);
+    // Scanlines are stored bottom to top.
+    for (row, y) in values.chunks(row_len).zip((0..height).rev()) {
+        for (x, v) in row.chunks(channels).enumerate() {
+            let color = match v {
+                [r, g, b] => Color::new(*r, *g, *b),
+                _ => Color::new(v[0], v[0], v[0]),
+            };
+            image.set(x, y, color);
+        }
+    }
+    Ok(image)
+}
+
+/// Decode a Radiance RGBE image in the usual `-Y height +X width` layout,
+/// with flat, old-style or new-style run-length encoded scanlines.
+pub fn read_hdr(data: &[u8]) -> Result<Image, String> {
+    let mut pos = 0;
+    if !next_line(data, &mut pos)?.starts_with("#?") {
+        return Err("not a Radiance HDR file".to_string());
+    }
+    loop {
+        let line = next_line(data, &mut pos)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format.trim() != "32-bit_rle_rgbe" {
+                return Err(format!("unsupported pixel format '{}'", format.trim()));
+            }
+        }
+    }
+    let resolution = next_line(data, &mut pos)?;
+    let (width, height) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+        ["-Y", height, "+X", width] => dimensions(width, height)?,
+        _ => return Err(format!("unsupported image orientation '{}'", resolution)),
+    };
+
+    let mut image = Image::new(width, height);
+    for y in 0..height {
+        let scanline = read_scanline(data, &mut pos, width)?;
+        for (x, &rgbe) in scanline.iter().enumerate() {
+            image.set(x, y, from_rgbe(rgbe));
+        }
+    }
+    Ok(image)
+}
+
+fn dimensions(width: &str, height: &str) -> Result<(usize, usize), String> {
+    match (width.parse(), height.parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(format!("invalid image size {} x {}", width, height)),
+    }
+}
+
+fn next_line<'a>(data: &'a [u8], pos: &mut usize) -> Result<&'a str, String> {
+    let rest = &data[*pos..];
+    let len = rest
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or_else(|| "truncated header".to_string())?;
+    *pos += len + 1;
+    std::str::from_utf8(&rest[..len]).map_err(|_| "header is not valid text".to_string())
+}
+
+fn read_scanline(data: &[u8], pos: &mut usize, width: usize) -> Result<Vec<[u8; 4]>, String> {
+    let truncated = || "truncated pixel data".to_string();
+    let byte = |pos: &mut usize| {
+        let b = data.get(*pos).copied().ok_or_else(truncated);
+        *pos += 1;
+        b
+    };
+
+    let head = data.get(*pos..*pos + 4).ok_or_else(truncated)?;
+    if (8..0x8000).contains(&width) && head[..2] == [2, 2] && head[2] < 128 {
+        if (head[2] as usize) << 8 | head[3] as usize != width {
+            return Err("scanline length does not match the image width".to_string());
+        }
+        *pos += 4;
+        // Each component is run-length encoded separately.
+        let mut pixels = vec![[0u8; 4]; width];
+        for component in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = byte(pos)? as usize;
+                let run = count > 128;
+                let count = if run { count - 128 } else { count };
+                if count == 0 || x + count > width {
+                    return Err("bad run length in scanline".to_string());
+                }
+                if run {
+                    let value = byte(pos)?;
+                    for pixel in pixels[x..x + count].iter_mut() {
+                        pixel[component] = value;
+                    }
+                } else {
+                    for pixel in pixels[x..x + count].iter_mut() {
+                        pixel[component] = byte(pos)?;
+                    }
+                }
+                x += count;
+            }
+        }
+        return Ok(pixels);
+    }
+
+    // Flat pixels, where (1, 1, 1, n) repeats the previous pixel n times,
+    // shifted by 8 bits for every consecutive repeat.
+    let mut pixels: Vec<[u8; 4]> = Vec::with_capacity(width);
+    let mut shift = 0;
+    while pixels.len() < width {
+        let pixel: [u8; 4] = data
+            .get(*pos..*pos + 4)
+            .ok_or_else(truncated)?
+            .try_into()
+            .unwrap();
+        *pos += 4;
+        match (pixel, pixels.last()) {
+            ([1, 1, 1, n], Some(&last)) => {
+                let count = (n as usize) << shift;
+                if pixels.len() + count > width {
+                    return Err("bad run length in scanline".to_string());
+                }
+                pixels.extend(std::iter::repeat_n(last, count));
+                shift += 8;
+            }
+            _ => {
+                pixels.push(pixel);
+                shift = 0;
+            }
+        }
+    }
+    Ok(pixels)
+}
+
+fn from_rgbe([r, g, b, e]: [u8; 4]) -> Color {
+    if e == 0 {
+        return Color::default();
+    }
+    let f = 2f32.powi(e as i32 - (128 + 8));
+    Color::new(
+        (r as f32 + 0.5) * f,
+        (g as f32 + 0.5) * f,
+        (b as f32 + 0.5) * f,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::output::{write_hdr, write_pfm};
+
+    fn gradient(width: usize, height: usize) -> Image {
+        let mut image = Image::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let c = Color::new(x as f32 * 0.25, y as f32 * 3.0, 100.0 / (1 + x) as f32);
+                image.set(x, y, c);
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn pfm_round_trip() {
+        let image = gradient(7, 3);
+        let mut pfm = Vec::new();
+        write_pfm(&image, &mut pfm).unwrap();
+        assert_eq!(read_pfm(&pfm), Ok(image));
+
+        let big_endian = b"Pf\n2 1\n1.0\n\x3f\x80\x00\x00\x40\x00\x00\x00";
+        let image = read_pfm(big_endian).unwrap();
+        assert_eq!(
+            image.pixels(),
+            [Color::new(1., 1., 1.), Color::new(2., 2., 2.)]
+        );
+        assert!(read_pfm(b"PF\n2 1\n-1.0\n\0\0\0\0").is_err());
+    }
+
+    #[test]
+    fn hdr_round_trip() {
+        // Narrow images are written flat, wide ones run-length encoded.
+        for width in [5, 40] {
+            let image = gradient(width, 4);
+            let mut hdr = Vec::new();
+            write_hdr(&image, &mut hdr).unwrap();
+            let decoded = read_hdr(&hdr).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (width, 4));
+            for (a, b) in image.pixels().iter().zip(decoded.pixels()) {
+                assert!((*a - *b).length() <= 0.01 * a.length());
+            }
+        }
+    }
+
+    #[test]
+    fn hdr_old_style_runs() {
+        let mut hdr = b"#?RADIANCE\n\n-Y 1 +X 5\n".to_vec();
+        hdr.extend_from_slice(&[128, 64, 32, 129, 1, 1, 1, 3, 0, 0, 0, 0]);
+        let image = read_hdr(&hdr).unwrap();
+        assert_eq!(image.get(3, 0), image.get(0, 0));
+        assert_eq!(image.get(4, 0), Color::default());
+        assert!(read_hdr(b"#?RADIANCE\n\n+Y 1 +X 5\n").is_err());
+    }
+}
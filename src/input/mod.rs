@@ -0,0 +1,81 @@
+//! Image file readers.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use crate::image::Image;
+
+mod hdr;
+mod png;
+mod ppm;
+
+pub use hdr::{read_hdr, read_pfm};
+pub use png::read_png;
+pub use ppm::read_ppm;
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io { file: String, source: io::Error },
+    Decode { file: String, message: String },
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { file, source } => write!(f, "{}: {}", file, source),
+            Self::Decode { file, message } => write!(f, "{}: {}", file, message),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Decode { .. } => None,
+        }
+    }
+}
+
+/// Load an image, picking the decoder from the file extension. The pixels
+/// come back as linear colours, 8 and 16 bit formats being decoded from
+/// sRGB.
+pub fn load_image(path: &Path) -> Result<Image, ImageError> {
+    let file = path.display().to_string();
+    let data = std::fs::read(path).map_err(|source| ImageError::Io {
+        file: file.clone(),
+        source,
+    })?;
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    let decoded = match extension.as_deref() {
+        Some("hdr") => read_hdr(&data),
+        Some("pfm") => read_pfm(&data),
+        Some("png") => read_png(&data),
+        Some("ppm" | "pgm") => read_ppm(&data),
+        _ => Err("unsupported image format (expected .hdr, .pfm, .png or .ppm)".to_string()),
+    };
+    decoded.map_err(|message| ImageError::Decode { file, message })
+}
+
+/// Apply the sRGB transfer curve to a value in [0, 1], recovering what an
+/// 8 or 16 bit file stored; the inverse of `srgb_to_linear`.
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Undo the sRGB transfer curve of a value in [0, 1].
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -0,0 +1,414 @@
+use miniz_oxide::inflate::decompress_to_vec_zlib;
+
+use super::srgb_to_linear;
+use crate::image::Image;
+use crate::vec3::Color;
+
+/// Starting column, starting row, column step and row step of the seven
+/// Adam7 passes.
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Decode a PNG of any standard colour type and bit depth, interlaced or
+/// not. Samples are taken to be sRGB encoded; alpha is dropped.
+pub fn read_png(data: &[u8]) -> Result<Image, String> {
+    let rest = data
+        .strip_prefix(b"\x89PNG\r\n\x1a\n")
+        .ok_or_else(|| "not a PNG file".to_string())?;
+
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut compressed = Vec::new();
+    let mut pos = 0;
+    loop {
+        let length = rest
+            .get(pos..pos + 4)
+            .ok_or_else(|| "missing IEND chunk".to_string())?;
+        let length = u32::from_be_bytes(length.try_into().unwrap()) as usize;
+        let kind = rest
+            .get(pos + 4..pos + 8)
+            .ok_or_else(|| "truncated chunk".to_string())?;
+        let body = rest
+            .get(pos + 8..pos + 8 + length)
+            .ok_or_else(|| format!("truncated {} chunk", String::from_utf8_lossy(kind)))?;
+        // The CRC is not checked.
+        pos += 12 + length;
+        match kind {
+            b"IHDR" => header = Some(Header::parse(body)?),
+            b"PLTE" => palette = body.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect(),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            // Other critical chunks would change the meaning of the pixels.
+            _ if kind[0].is_ascii_uppercase() => {
+                return Err(format!(
+                    "unsupported critical chunk {}",
+                    String::from_utf8_lossy(kind)
+                ))
+            }
+            _ => {}
+        }
+    }
+    let header = header.ok_or_else(|| "missing IHDR chunk".to_string())?;
+    if header.color_type == 3 && palette.is_empty() {
+        return Err("missing PLTE chunk".to_string());
+    }
+    let raw = decompress_to_vec_zlib(&compressed)
+        .map_err(|err| format!("corrupt image data: {:?}", err.status))?;
+
+    let passes = if header.interlaced {
+        &ADAM7[..]
+    } else {
+        &[(0, 0, 1, 1)][..]
+    };
+    let passes: Vec<_> = passes
+        .iter()
+        .map(|&(x0, y0, dx, dy)| {
+            let width = (header.width + dx - 1 - x0) / dx;
+            let height = (header.height + dy - 1 - y0) / dy;
+            (x0, y0, dx, dy, width, height)
+        })
+        .filter(|&(.., width, height)| width > 0 && height > 0)
+        .collect();
+    // Check the size the header claims against the data before allocating
+    // anything that large.
+    let size = passes.iter().try_fold(0usize, |sum, &(.., width, height)| {
+        sum.checked_add(header.data_size(width, height)?)
+    });
+    if size.is_none_or(|size| raw.len() < size) {
+        return Err("truncated image data".to_string());
+    }
+
+    let mut image = Image::new(header.width, header.height);
+    let mut raw = raw.as_slice();
+    for (x0, y0, dx, dy, width, height) in passes {
+        let samples = header.unfilter(&mut raw, width, height)?;
+        for (y, row) in samples.chunks(width * header.channels()).enumerate() {
+            for (x, pixel) in row.chunks(header.channels()).enumerate() {
+                let color = header.color(pixel, &palette)?;
+                image.set(x0 + x * dx, y0 + y * dy, color);
+            }
+        }
+    }
+    Ok(image)
+}
+
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn parse(body: &[u8]) -> Result<Self, String> {
+        if body.len() != 13 {
+            return Err("bad IHDR chunk".to_string());
+        }
+        let width = u32::from_be_bytes(body[0..4].try_into().unwrap()) as usize;
+        let height = u32::from_be_bytes(body[4..8].try_into().unwrap()) as usize;
+        let [bit_depth, color_type, compression, filter, interlace] = body[8..13] else {
+            unreachable!()
+        };
+        if width == 0 || height == 0 {
+            return Err(format!("invalid image size {} x {}", width, height));
+        }
+        let depth_ok = match color_type {
+            0 => matches!(bit_depth, 1 | 2 | 4 | 8 | 16),
+            3 => matches!(bit_depth, 1 | 2 | 4 | 8),
+            2 | 4 | 6 => matches!(bit_depth, 8 | 16),
+            _ => return Err(format!("invalid colour type {}", color_type)),
+        };
+        if !depth_ok {
+            return Err(format!(
+                "invalid bit depth {} for colour type {}",
+                bit_depth, color_type
+            ));
+        }
+        if compression != 0 || filter != 0 || interlace > 1 {
+            return Err("unknown compression, filter or interlace method".to_string());
+        }
+        Ok(Self {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            interlaced: interlace == 1,
+        })
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    /// The bytes of filtered scanlines holding a `width` x `height`
+    /// (sub)image, or `None` if that does not fit in memory at all.
+    fn data_size(&self, width: usize, height: usize) -> Option<usize> {
+        let bits = width.checked_mul(self.channels() * self.bit_depth as usize)?;
+        height.checked_mul(bits.div_ceil(8) + 1)
+    }
+
+    /// Undo the per-scanline filters of a `width` x `height` (sub)image at
+    /// the start of `raw`, consuming it, and split it into samples.
+    fn unfilter(&self, raw: &mut &[u8], width: usize, height: usize) -> Result<Vec<u16>, String> {
+        let bits = self.channels() * self.bit_depth as usize;
+        let stride = (width * bits).div_ceil(8);
+        // Filters look at the corresponding byte of the previous pixel.
+        let bpp = bits.div_ceil(8);
+        let size = self
+            .data_size(width, height)
+            .ok_or_else(|| "truncated image data".to_string())?;
+        let data = raw
+            .get(..size)
+            .ok_or_else(|| "truncated image data".to_string())?;
+        *raw = &raw[size..];
+
+        let mut previous = vec![0u8; stride];
+        let mut row = vec![0u8; stride];
+        let mut samples = Vec::with_capacity(width * height * self.channels());
+        for line in data.chunks(stride + 1) {
+            let (filter, line) = (line[0], &line[1..]);
+            for i in 0..stride {
+                let a = if i >= bpp { row[i - bpp] } else { 0 };
+                let b = previous[i];
+                let c = if i >= bpp { previous[i - bpp] } else { 0 };
+                let predictor = match filter {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    4 => paeth(a, b, c),
+                    _ => return Err(format!("invalid filter type {}", filter)),
+                };
+                row[i] = line[i].wrapping_add(predictor);
+            }
+
+            let count = width * self.channels();
+            match self.bit_depth {
+                16 => samples.extend(
+                    row.chunks(2)
+                        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                        .take(count),
+                ),
+                8 => samples.extend(row.iter().map(|&b| b as u16).take(count)),
+                depth => {
+                    // Packed most significant bits first.
+                    let per_byte = 8 / depth as usize;
+                    let mask = (1u16 << depth) - 1;
+                    samples.extend((0..count).map(|i| {
+                        let shift = 8 - depth as usize * (i % per_byte + 1);
+                        (row[i / per_byte] as u16 >> shift) & mask
+                    }));
+                }
+            }
+            std::mem::swap(&mut previous, &mut row);
+        }
+        Ok(samples)
+    }
+
+    fn color(&self, pixel: &[u16], palette: &[[u8; 3]]) -> Result<Color, String> {
+        let max = ((1u32 << self.bit_depth) - 1) as f32;
+        let decode = |v: u16| srgb_to_linear(v as f32 / max);
+        Ok(match self.color_type {
+            3 => {
+                let [r, g, b] = palette
+                    .get(pixel[0] as usize)
+                    .ok_or_else(|| format!("palette index {} out of range", pixel[0]))?
+                    .map(|c| srgb_to_linear(c as f32 / 255.0));
+                Color::new(r, g, b)
+            }
+            2 | 6 => Color::new(decode(pixel[0]), decode(pixel[1]), decode(pixel[2])),
+            _ => {
+                let grey = decode(pixel[0]);
+                Color::new(grey, grey, grey)
+            }
+        })
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::output::write_png;
+    use miniz_oxide::deflate::compress_to_vec_zlib;
+
+    /// A PNG around already filtered scanlines, with dummy CRCs.
+    fn png_file(
+        width: u32,
+        height: u32,
+        depth: u8,
+        color_type: u8,
+        interlace: u8,
+        chunks: &[(&[u8; 4], Vec<u8>)],
+    ) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend_from_slice(&width.to_be_bytes());
+        header.extend_from_slice(&height.to_be_bytes());
+        header.extend_from_slice(&[depth, color_type, 0, 0, interlace]);
+
+        let mut file = b"\x89PNG\r\n\x1a\n".to_vec();
+        let mut chunks = chunks.to_vec();
+        chunks.insert(0, (b"IHDR", header));
+        chunks.push((b"IEND", Vec::new()));
+        for (kind, body) in chunks {
+            file.extend_from_slice(&(body.len() as u32).to_be_bytes());
+            file.extend_from_slice(kind);
+            file.extend_from_slice(&body);
+            file.extend_from_slice(&[0; 4]);
+        }
+        file
+    }
+
+    /// Filter `rows` of `bpp`-byte pixels, cycling through all five filters.
+    fn filter_rows(rows: &[Vec<u8>], bpp: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut previous = vec![0u8; rows[0].len()];
+        for (y, row) in rows.iter().enumerate() {
+            let filter = (y % 5) as u8;
+            out.push(filter);
+            for i in 0..row.len() {
+                let a = if i >= bpp { row[i - bpp] } else { 0 };
+                let b = previous[i];
+                let c = if i >= bpp { previous[i - bpp] } else { 0 };
+                let predictor = match filter {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    _ => paeth(a, b, c),
+                };
+                out.push(row[i].wrapping_sub(predictor));
+            }
+            previous = row.clone();
+        }
+        out
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut image = Image::new(5, 3);
+        for y in 0..3 {
+            for x in 0..5 {
+                image.set(x, y, Color::new(x as f32 / 4., y as f32 / 2., 0.3));
+            }
+        }
+        let mut png = Vec::new();
+        write_png(&image, &mut png).unwrap();
+        let decoded = read_png(&png).unwrap();
+        for (a, b) in image.pixels().iter().zip(decoded.pixels()) {
+            assert!((*a - *b).length() < 0.01);
+        }
+    }
+
+    #[test]
+    fn all_filters_at_sixteen_bits() {
+        // Three RGB rows of 4 pixels with 16-bit samples.
+        let rows: Vec<Vec<u8>> = (0..7u16)
+            .map(|y| {
+                (0..12u16)
+                    .flat_map(|i| (i * 5000 + y * 1234).to_be_bytes())
+                    .collect()
+            })
+            .collect();
+        let raw = filter_rows(&rows, 6);
+        let png = png_file(4, 7, 16, 2, 0, &[(b"IDAT", compress_to_vec_zlib(&raw, 6))]);
+        let image = read_png(&png).unwrap();
+        let value = |x: u16, y: u16, c: u16| {
+            srgb_to_linear((3 * x + c) as f32 * 5000. / 65535. + (y * 1234) as f32 / 65535.)
+        };
+        for (x, y) in [(0, 0), (3, 2), (1, 4), (2, 6)] {
+            let expected = Color::new(value(x, y, 0), value(x, y, 1), value(x, y, 2));
+            assert!((image.get(x as usize, y as usize) - expected).length() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn interlaced_palette() {
+        // A 2-bit palette image, 10 x 9, each pixel indexing (x + y) % 4.
+        let palette = vec![0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255];
+        let mut raw = Vec::new();
+        for &(x0, y0, dx, dy) in ADAM7.iter() {
+            let xs: Vec<usize> = (x0..10).step_by(dx).collect();
+            for y in (y0..9).step_by(dy) {
+                if xs.is_empty() {
+                    continue;
+                }
+                raw.push(0);
+                for pixels in xs.chunks(4) {
+                    let mut byte = 0u8;
+                    for (i, x) in pixels.iter().enumerate() {
+                        byte |= (((x + y) % 4) as u8) << (6 - 2 * i);
+                    }
+                    raw.push(byte);
+                }
+            }
+        }
+        let png = png_file(
+            10,
+            9,
+            2,
+            3,
+            1,
+            &[
+                (b"PLTE", palette),
+                (b"tEXt", b"a\0b".to_vec()),
+                (b"IDAT", compress_to_vec_zlib(&raw, 6)),
+            ],
+        );
+        let image = read_png(&png).unwrap();
+        let colors = [
+            Color::new(0., 0., 0.),
+            Color::new(1., 0., 0.),
+            Color::new(0., 1., 0.),
+            Color::new(0., 0., 1.),
+        ];
+        for y in 0..9 {
+            for x in 0..10 {
+                assert_eq!(image.get(x, y), colors[(x + y) % 4], "pixel {} {}", x, y);
+            }
+        }
+
+        assert!(read_png(&png[..40]).is_err());
+        let grey16_palette = png_file(1, 1, 16, 3, 0, &[]);
+        assert!(read_png(&grey16_palette).is_err());
+    }
+
+    #[test]
+    fn size_must_match_the_data() {
+        let idat = compress_to_vec_zlib(&[0, 1, 2, 3], 6);
+        for (width, height) in [(u32::MAX, u32::MAX), (1 << 20, 1 << 20), (3, 2)] {
+            let png = png_file(width, height, 8, 0, 0, &[(b"IDAT", idat.clone())]);
+            assert_eq!(read_png(&png).unwrap_err(), "truncated image data");
+        }
+        let png = png_file(3, 1, 8, 0, 0, &[(b"IDAT", idat)]);
+        assert_eq!(read_png(&png).unwrap().width(), 3);
+    }
+}
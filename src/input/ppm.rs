@@ -0,0 +1,131 @@
+use super::srgb_to_linear;
+use crate::image::Image;
+use crate::vec3::Color;
+
+/// Decode an ASCII (`P2`, `P3`) or binary (`P5`, `P6`) greyscale or colour
+/// PNM, with 8 or 16 bit samples taken to be sRGB encoded.
+pub fn read_ppm(data: &[u8]) -> Result<Image, String> {
+    let mut pos = 0;
+    let magic = token(data, &mut pos).ok_or_else(|| "truncated PPM header".to_string())?;
+    let (channels, binary) = match magic {
+        b"P2" => (1, false),
+        b"P3" => (3, false),
+        b"P5" => (1, true),
+        b"P6" => (3, true),
+        _ => return Err("not a PPM or PGM file".to_string()),
+    };
+    let mut header = [0usize; 3];
+    for value in header.iter_mut() {
+        let text = token(data, &mut pos).ok_or_else(|| "truncated PPM header".to_string())?;
+        *value = std::str::from_utf8(text)
+            .ok()
+            .and_then(|text| text.parse().ok())
+            .ok_or_else(|| format!("invalid header value '{}'", String::from_utf8_lossy(text)))?;
+    }
+    let [width, height, max_value] = header;
+    if width == 0 || height == 0 {
+        return Err(format!("invalid image size {} x {}", width, height));
+    }
+    if !(1..=65535).contains(&max_value) {
+        return Err(format!("invalid maximum value {}", max_value));
+    }
+
+    let count = width * height * channels;
+    let samples: Vec<usize> = if binary {
+        // A single whitespace byte separates the header from the data.
+        pos += 1;
+        let size = if max_value < 256 { 1 } else { 2 };
+        let bytes = data
+            .get(pos..pos + count * size)
+            .ok_or_else(|| "truncated pixel data".to_string())?;
+        bytes
+            .chunks(size)
+            .map(|b| b.iter().fold(0, |acc, &b| acc << 8 | b as usize))
+            .collect()
+    } else {
+        let mut samples = Vec::with_capacity(count);
+        while samples.len() < count {
+            let text = token(data, &mut pos).ok_or_else(|| "truncated pixel data".to_string())?;
+            let value = std::str::from_utf8(text)
+                .ok()
+                .and_then(|text| text.parse().ok())
+                .ok_or_else(|| format!("invalid sample '{}'", String::from_utf8_lossy(text)))?;
+            samples.push(value);
+        }
+        samples
+    };
+    if let Some(&value) = samples.iter().find(|&&value| value > max_value) {
+        return Err(format!(
+            "sample {} exceeds the maximum {}",
+            value, max_value
+        ));
+    }
+
+    let decode = |value: usize| srgb_to_linear(value as f32 / max_value as f32);
+    let mut image = Image::new(width, height);
+    for (i, pixel) in samples.chunks(channels).enumerate() {
+        let color = match pixel {
+            [r, g, b] => Color::new(decode(*r), decode(*g), decode(*b)),
+            _ => {
+                let grey = decode(pixel[0]);
+                Color::new(grey, grey, grey)
+            }
+        };
+        image.set(i % width, i / width, color);
+    }
+    Ok(image)
+}
+
+/// The next whitespace separated token, skipping `#` comments.
+fn token<'a>(data: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    loop {
+        match data.get(*pos)? {
+            b'#' => {
+                while data.get(*pos).is_some_and(|&b| b != b'\n') {
+                    *pos += 1;
+                }
+            }
+            b if b.is_ascii_whitespace() => *pos += 1,
+            _ => break,
+        }
+    }
+    let start = *pos;
+    while data.get(*pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+        *pos += 1;
+    }
+    Some(&data[start..*pos])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::output::write_ppm_binary;
+
+    #[test]
+    fn binary_round_trip() {
+        let mut image = Image::new(3, 2);
+        for (i, c) in [0.0, 0.001, 0.05, 0.2, 0.5, 1.0].iter().enumerate() {
+            image.set(i % 3, i / 3, Color::new(*c, 1.0 - c, 0.5));
+        }
+        let mut ppm = Vec::new();
+        write_ppm_binary(&image, &mut ppm).unwrap();
+        let decoded = read_ppm(&ppm).unwrap();
+        for (a, b) in image.pixels().iter().zip(decoded.pixels()) {
+            assert!((*a - *b).length() < 0.01);
+        }
+    }
+
+    #[test]
+    fn ascii_with_comments() {
+        let source = b"P3\n# made by hand\n2 1 # size\n255\n255 0 0  0 0 255\n";
+        let image = read_ppm(source).unwrap();
+        assert_eq!(image.get(0, 0), Color::new(1., 0., 0.));
+        assert_eq!(image.get(1, 0), Color::new(0., 0., 1.));
+
+        let grey = read_ppm(b"P5 1 1 65535\n\x80\x00").unwrap();
+        assert!((grey.get(0, 0).x() - srgb_to_linear(32768. / 65535.)).abs() < 1e-6);
+
+        assert!(read_ppm(b"P3\n1 1\n255\n256 0 0\n").is_err());
+        assert!(read_ppm(b"P6\n2 2\n255\n\0\0\0").is_err());
+    }
+}
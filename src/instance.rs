@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::*;
+use crate::ray::Ray;
+use crate::transform::Transform;
+use crate::vec3::*;
+
+/// Another object placed by a `Transform`. Rays are taken into the object's
+/// own space rather than the object into the world, so any number of
+/// instances can share one copy of heavy geometry.
+#[derive(Clone)]
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    transform: Transform,
+    inverse: Transform,
+    bbox: Option<Aabb>,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Self {
+        let bbox = object.bounding_box().map(|b| transform.bounding_box(b));
+        Self {
+            object,
+            transform,
+            inverse: transform.inverse(),
+            bbox,
+        }
+    }
+
+    pub fn object(&self) -> &Arc<dyn Hittable> {
+        &self.object
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn to_object(&self, r: Ray) -> Ray {
+        Ray::with_time(
+            self.inverse.point(r.origin()),
+            self.inverse.vector(r.direction()),
+            r.time(),
+        )
+    }
+
+    fn to_world(&self, mut rec: HitRecord) -> HitRecord {
+        // Distances along the ray carry over, since the direction was
+        // transformed along with the origin and not renormalised.
+        rec.p = self.transform.point(rec.p);
+        rec.normal = unit(self.transform.normal(rec.normal));
+        rec
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let rec = self.object.hit(self.to_object(r), t_min, t_max)?;
+        Some(self.to_world(rec))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bbox
+    }
+
+    fn occluded(&self, r: Ray, t_min: f32, t_max: f32) -> bool {
+        self.object.occluded(self.to_object(r), t_min, t_max)
+    }
+
+    fn transmittance(&self, r: Ray, t_min: f32, t_max: f32) -> f32 {
+        self.object.transmittance(self.to_object(r), t_min, t_max)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.object.is_closed()
+    }
+
+    fn intervals(&self, r: Ray) -> Vec<Interval> {
+        self.object
+            .intervals(self.to_object(r))
+            .into_iter()
+            .map(|Interval { enter, exit }| Interval {
+                enter: self.to_world(enter),
+                exit: self.to_world(exit),
+            })
+            .collect()
+    }
+
+    /// Only transforms that keep angles keep the object's solid angle
+    /// density too, so other instances are not sampled as lights.
+    fn sample_towards(&self, origin: Point3) -> Option<(HitRecord, f32)> {
+        if !self.transform.is_similarity() {
+            return None;
+        }
+        let (rec, pdf) = self.object.sample_towards(self.inverse.point(origin))?;
+        Some((self.to_world(rec), pdf))
+    }
+
+    fn pdf_towards(&self, origin: Point3, direction: Vec3) -> f32 {
+        if !self.transform.is_similarity() {
+            return 0.0;
+        }
+        self.object
+            .pdf_towards(self.inverse.point(origin), self.inverse.vector(direction))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::DiffuseLight;
+    use crate::sphere::Sphere;
+    use crate::triangle::Triangle;
+
+    #[test]
+    fn hits_in_world_space() {
+        let light = Arc::new(DiffuseLight::new(Color::new(1., 1., 1.)));
+        // A right triangle in the xy plane, turned to face +x and moved.
+        let triangle: Arc<dyn Hittable> = Arc::new(Triangle::new(
+            Point3::new(0., 0., 0.),
+            Point3::new(1., 0., 0.),
+            Point3::new(0., 1., 0.),
+            light.clone(),
+        ));
+        let placed = Instance::new(
+            triangle.clone(),
+            Transform::rotate(Vec3::new(0., 1., 0.), 90.)
+                .then(&Transform::translate(Vec3::new(0., 0., -5.))),
+        );
+        let r = Ray::new(Point3::new(3., 0.2, -5.4), Vec3::new(-1., 0., 0.));
+        let rec = placed.hit(r, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 3.).abs() < 1e-5);
+        assert!((rec.p - Point3::new(0., 0.2, -5.4)).length() < 1e-5);
+        assert!((rec.normal - Vec3::new(1., 0., 0.)).length() < 1e-5);
+        assert!(placed.occluded(r, 0.001, 3.5));
+        assert!(!placed.occluded(r, 0.001, 2.5));
+        // The original still sits where it was.
+        assert!(triangle.hit(r, 0.001, f32::INFINITY).is_none());
+
+        // The triangle's box is padded a little around its flat side.
+        let bbox = placed.bounding_box().unwrap();
+        assert!((bbox.min() - Point3::new(0., 0., -6.)).length() < 1e-3);
+        assert!((bbox.max() - Point3::new(0., 1., -5.)).length() < 1e-3);
+        assert!(bbox.min().x() < 0. && bbox.max().x() > 0.);
+    }
+
+    #[test]
+    fn samples_similar_instances_as_lights() {
+        crate::rtweekend::seed_rng(5);
+        let light = Arc::new(DiffuseLight::new(Color::new(1., 1., 1.)));
+        let unit_sphere: Arc<dyn Hittable> =
+            Arc::new(Sphere::new(Point3::default(), 1., light.clone()));
+        let placed = Instance::new(
+            unit_sphere,
+            Transform::scale(Vec3::new(2., 2., 2.))
+                .then(&Transform::translate(Vec3::new(0., 0., -4.))),
+        );
+        let direct = Sphere::new(Point3::new(0., 0., -4.), 2., light);
+        let origin = Point3::default();
+        for _ in 0..100 {
+            let (rec, pdf) = placed.sample_towards(origin).unwrap();
+            assert!(((rec.p - Point3::new(0., 0., -4.)).length() - 2.).abs() < 1e-4);
+            let expected = direct.pdf_towards(origin, rec.p - origin);
+            assert!((pdf - expected).abs() < 1e-3 * expected);
+            assert!(
+                (placed.pdf_towards(origin, rec.p - origin) - expected).abs() < 1e-3 * expected
+            );
+        }
+
+        let squashed = Instance::new(
+            placed.object().clone(),
+            Transform::scale(Vec3::new(1., 2., 1.)),
+        );
+        assert!(squashed.sample_towards(Point3::new(0., 0., 5.)).is_none());
+    }
+}
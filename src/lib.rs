@@ -0,0 +1,84 @@
+//! A path tracer following *Ray Tracing in One Weekend*.
+//!
+//! Build a world out of `Hittable` objects and `Material`s (or load one with
+//! `scene_file::load_scene` / `obj::load_obj`), point a `Camera` at it and
+//! call `render::render`:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use ray_tracing_one_weekend::bvh::BvhNode;
+//! use ray_tracing_one_weekend::camera::CameraSettings;
+//! use ray_tracing_one_weekend::hittable_list::HittableList;
+//! use ray_tracing_one_weekend::material::Lambertian;
+//! use ray_tracing_one_weekend::render::{render, RenderSettings};
+//! use ray_tracing_one_weekend::sphere::Sphere;
+//! use ray_tracing_one_weekend::vec3::{Color, Point3, Vec3};
+//!
+//! let mut world = HittableList::new();
+//! let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+//! world.add(Arc::new(Sphere::new(Point3::new(0., 0., -1.), 0.5, material)));
+//!
+//! let settings = RenderSettings::new(16.0 / 9.0, 400, 50, 20);
+//! let camera = CameraSettings {
+//!     look_from: Point3::new(0., 0., 0.),
+//!     look_at: Point3::new(0., 0., -1.),
+//!     vup: Vec3::new(0., 1., 0.),
+//!     vfov: 90.,
+//!     aperture: 0.,
+//!     focus_dist: 1.,
+//!     shutter_open: 0.,
+//!     shutter_close: 0.,
+//! }
+//! .build(settings.aspect_ratio);
+//!
+//! let image = render(&BvhNode::new(&world), &camera, &settings);
+//! image.write_ppm(&mut std::io::stdout()).unwrap();
+//! ```
+
+pub mod aabb;
+pub mod background;
+pub mod bvh;
+pub mod camera;
+pub mod color;
+pub mod cone;
+pub mod constant_medium;
+pub mod csg;
+pub mod cuboid;
+pub mod cylinder;
+pub mod density_grid;
+pub mod disk;
+pub mod film;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod heightfield;
+pub mod heterogeneous_medium;
+pub mod hittable;
+pub mod hittable_list;
+pub mod image;
+pub mod input;
+pub mod instance;
+pub mod material;
+pub mod moving_sphere;
+pub mod obj;
+pub mod output;
+pub mod perlin;
+pub mod plane;
+pub mod quad;
+pub mod ray;
+pub mod ray_color;
+pub mod render;
+pub mod roots;
+pub mod rtweekend;
+pub mod sampling;
+pub mod scene;
+pub mod scene_file;
+pub mod sdf;
+pub mod sdf_object;
+pub mod sphere;
+pub mod texture;
+pub mod torus;
+pub mod transform;
+pub mod triangle;
+pub mod triangle_mesh;
+pub mod vec3;
@@ -1,79 +1,155 @@
-use std::io::Write;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
 
-use rand::random;
-use vec3::Point3;
+use ray_tracing_one_weekend::background::{Background, SkyGradient, SolidBackground};
+use ray_tracing_one_weekend::bvh::accelerate;
+use ray_tracing_one_weekend::camera::CameraSettings;
+use ray_tracing_one_weekend::hittable_list::HittableList;
+use ray_tracing_one_weekend::output::write_image;
+use ray_tracing_one_weekend::render::{RenderSettings, Renderer};
+use ray_tracing_one_weekend::rtweekend::seed_rng;
+use ray_tracing_one_weekend::scene::*;
+use ray_tracing_one_weekend::scene_file::load_scene;
+use ray_tracing_one_weekend::vec3::{Color, Point3, Vec3};
 
-use crate::{color::write_color, vec3::Vec3};
-use camera::*;
-use ray_color::*;
-use scene::*;
+use cli::*;
 
-mod camera;
-mod color;
-mod gpu;
-mod hittable;
-mod hittable_list;
-mod material;
-mod ray;
-mod ray_color;
-mod rtweekend;
-mod scene;
-mod sphere;
-mod vec3;
+mod cli;
 
-#[allow(non_upper_case_globals)]
 fn main() {
-    // Image
-    static aspect_ratio: f32 = 3.0 / 2.0;
-    static image_width: i32 = 600;
-    static image_height: i32 = (image_width as f32 / aspect_ratio) as i32;
-    static samples_per_pixel: i32 = 100;
-    static max_depth: i32 = 50;
+    let options = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(Command::Render(options)) => options,
+        Ok(Command::Help) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(err) = run(&options) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
 
-    // World
+fn run(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(threads) = options.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
+    }
+    if let Some(seed) = options.seed {
+        seed_rng(seed);
+    }
 
-    let world = test_scene_metal();
+    let (scene_settings, world, lights, camera, background) = match &options.scene {
+        SceneSource::File(path) => {
+            let scene = load_scene(path)?;
+            (
+                scene.settings,
+                scene.world,
+                scene.lights,
+                scene.camera,
+                scene.background,
+            )
+        }
+        SceneSource::Builtin(builtin) => {
+            // World
 
-    // Camera
+            let (world, lights) = match builtin {
+                BuiltinScene::Test => (test_scene(), HittableList::new()),
+                BuiltinScene::Metal => (test_scene_metal(), HittableList::new()),
+                BuiltinScene::Random => (random_scene(), HittableList::new()),
+                BuiltinScene::Cornell => cornell_box(),
+            };
 
-    let lookfrom = Point3::new(13., 2., 3.);
-    let lookat = Point3::new(0., 0., 0.);
-    let vup = Vec3::new(0., 1., 0.);
-    let dist_to_focus = 10.0;
-    let aperture = 0.1;
+            // Camera
 
-    let cam = Camera::new(
-        lookfrom,
-        lookat,
-        vup,
-        20.,
+            if *builtin == BuiltinScene::Cornell {
+                let camera = CameraSettings {
+                    look_from: Point3::new(278., 278., -800.),
+                    look_at: Point3::new(278., 278., 0.),
+                    vup: Vec3::new(0., 1., 0.),
+                    vfov: 40.,
+                    aperture: 0.,
+                    focus_dist: 10.0,
+                    shutter_open: 0.,
+                    shutter_close: 0.,
+                };
+                // Lit by its ceiling light alone.
+                let settings = RenderSettings::new(1.0, 600, 200, 50);
+                let background: Arc<dyn Background> =
+                    Arc::new(SolidBackground::new(Color::default()));
+                (settings, world, lights, camera, background)
+            } else {
+                let camera = CameraSettings {
+                    look_from: Point3::new(13., 2., 3.),
+                    look_at: Point3::new(0., 0., 0.),
+                    vup: Vec3::new(0., 1., 0.),
+                    vfov: 20.,
+                    aperture: 0.1,
+                    focus_dist: 10.0,
+                    shutter_open: 0.,
+                    shutter_close: 0.,
+                };
+                let background: Arc<dyn Background> = Arc::new(SkyGradient::default());
+                (RenderSettings::default(), world, lights, camera, background)
+            }
+        }
+    };
+
+    // Image
+
+    let image_width = options.width.unwrap_or(scene_settings.image_width);
+    let (aspect_ratio, image_height) = match options.height {
+        Some(height) => (image_width as f32 / height as f32, height),
+        None => (
+            scene_settings.aspect_ratio,
+            (image_width as f32 / scene_settings.aspect_ratio) as i32,
+        ),
+    };
+    if image_height < 1 {
+        return Err("the image must be at least one pixel high".into());
+    }
+    let settings = RenderSettings {
         aspect_ratio,
-        aperture,
-        dist_to_focus,
-    );
+        image_width,
+        image_height,
+        samples_per_pixel: options
+            .samples_per_pixel
+            .unwrap_or(scene_settings.samples_per_pixel),
+        max_depth: options.max_depth.unwrap_or(scene_settings.max_depth),
+        backend: options.backend,
+    };
 
     // Render
-    println!("P3\n{} {}\n255", image_width, image_height);
 
-    for j in (0..image_height).rev() {
-        eprint!("\rScanlines remaining: {} ", j);
-        std::io::stderr().flush().unwrap();
-        for i in 0..image_width {
-            let rays: Vec<_> = (0..samples_per_pixel)
-                .into_iter()
-                .map(|_| {
-                    let u = (i as f32 + random::<f32>()) / (image_width - 1) as f32;
-                    let v = (j as f32 + random::<f32>()) / (image_height - 1) as f32;
-                    cam.get_ray(u, v)
-                })
-                .collect();
-            let pixel_color = rays
-                .into_iter()
-                .map(|r| ray_color_default(r, &world, max_depth))
-                .reduce(|acc, x| acc + x)
-                .unwrap();
-            write_color(&mut std::io::stdout(), pixel_color, samples_per_pixel).unwrap();
-        }
+    let mut renderer = Renderer::new(settings)
+        .lights(lights)
+        .background(background);
+    if let Some(seed) = options.seed {
+        renderer = renderer.seed(seed);
     }
+    let image = renderer.render_with_progress(
+        &accelerate(&world),
+        &camera.build(aspect_ratio),
+        |progress| {
+            eprint!(
+                "\rTiles remaining: {} ",
+                progress.tiles_total - progress.tiles_done
+            );
+        },
+    );
     eprintln!("\nDone.");
+
+    let mut out: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(std::io::stdout())),
+    };
+    write_image(&image, options.format, &mut out)?;
+    out.flush()?;
+    Ok(())
 }
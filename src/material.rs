@@ -1,44 +1,118 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
 use crate::hittable::*;
 use crate::ray::Ray;
+use crate::rtweekend::random;
+use crate::texture::{SolidColor, Texture};
 use crate::vec3::*;
-use rand::prelude::*;
 
+/// A direction picked by `Material::sample`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScatterSample {
+    pub ray: Ray,
+    /// What the path throughput is multiplied by: `eval` over `pdf` for
+    /// the direction taken, or the lobe's colour for a specular one.
+    pub attenuation: Color,
+    /// Solid angle density of the direction; unused for specular samples.
+    pub pdf: f32,
+    /// Picked from a delta (perfectly or fuzzily mirrored, or refracted)
+    /// lobe, which `eval` and `pdf` know nothing about, so light sampling
+    /// cannot help.
+    pub specular: bool,
+}
+
+impl ScatterSample {
+    /// A sample from a delta lobe.
+    pub fn specular(ray: Ray, attenuation: Color) -> Self {
+        Self {
+            ray,
+            attenuation,
+            pdf: 0.0,
+            specular: true,
+        }
+    }
+}
+
+/// Decides how light scatters off a surface.
+///
+/// `r_in` is always the ray that hit the surface and directions point away
+/// from it, so `eval(r_in, rec, d)` is the light leaving back along `r_in`
+/// per unit of light arriving from `d`.
 pub trait Material: Send + Sync {
-    fn scatter(&self, r_in: Ray, rec: HitRecord) -> Option<(Color, Ray)>;
+    /// Pick the direction to continue a path in; `None` if it is absorbed.
+    fn sample(&self, r_in: Ray, rec: &HitRecord) -> Option<ScatterSample>;
+
+    /// The BSDF times the cosine of `direction` with the normal; black for
+    /// materials with only specular lobes.
+    fn eval(&self, _r_in: Ray, _rec: &HitRecord, _direction: Vec3) -> Color {
+        Color::default()
+    }
+
+    /// Solid angle density with which `sample` picks `direction`, ignoring
+    /// specular lobes.
+    fn pdf(&self, _r_in: Ray, _rec: &HitRecord, _direction: Vec3) -> f32 {
+        0.0
+    }
+
+    /// Light given off at the hit point; black for everything but lights.
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        Color::default()
+    }
 }
 
 pub struct Lambertian {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
+        Self::textured(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>) -> Self {
         Self { albedo }
     }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        self.albedo.value(rec.u, rec.v, rec.p)
+    }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _r_in: Ray, rec: HitRecord) -> Option<(Color, Ray)> {
-        let mut scatter_direction = rec.normal + random_unit_vector();
+    fn sample(&self, r_in: Ray, rec: &HitRecord) -> Option<ScatterSample> {
+        let direction = Onb::new(rec.normal).local(random_cosine_direction());
+        // Cosine-weighted sampling cancels the BSDF down to the albedo.
+        Some(ScatterSample {
+            ray: Ray::with_time(rec.p, direction, r_in.time()),
+            attenuation: self.albedo(rec),
+            pdf: dot(rec.normal, direction).max(0.0) / PI,
+            specular: false,
+        })
+    }
 
-        // Catch degenerate scatter direction
-        if scatter_direction.near_zero() {
-            scatter_direction = rec.normal;
-        }
+    fn eval(&self, _r_in: Ray, rec: &HitRecord, direction: Vec3) -> Color {
+        let cosine = dot(rec.normal, unit(direction));
+        self.albedo(rec) * (cosine.max(0.0) / PI)
+    }
 
-        let scattered = Ray::new(rec.p, scatter_direction);
-        let attenuation = self.albedo;
-        Some((attenuation, scattered))
+    fn pdf(&self, _r_in: Ray, rec: &HitRecord, direction: Vec3) -> f32 {
+        let cosine = dot(rec.normal, unit(direction));
+        cosine.max(0.0) / PI
     }
 }
 
 pub struct Metal {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
     fuzz: f32,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f32) -> Self {
+        Self::textured(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>, fuzz: f32) -> Self {
         Self {
             albedo,
             fuzz: fuzz.clamp(f32::MIN, 1.0),
@@ -47,12 +121,18 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: Ray, rec: HitRecord) -> Option<(Color, Ray)> {
+    /// Fuzz blurs the mirror direction without a density to go with it, so
+    /// even rough metal counts as specular.
+    fn sample(&self, r_in: Ray, rec: &HitRecord) -> Option<ScatterSample> {
         let reflected = reflect(unit(r_in.direction()), rec.normal);
-        let scattered = Ray::new(rec.p, reflected + self.fuzz * random_in_unit_sphere());
-        let attenuation = self.albedo;
+        let scattered = Ray::with_time(
+            rec.p,
+            reflected + self.fuzz * random_in_unit_sphere(),
+            r_in.time(),
+        );
+        let attenuation = self.albedo.value(rec.u, rec.v, rec.p);
         if dot(scattered.direction(), rec.normal) > 0. {
-            Some((attenuation, scattered))
+            Some(ScatterSample::specular(scattered, attenuation))
         } else {
             None
         }
@@ -79,7 +159,7 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r_in: Ray, rec: HitRecord) -> Option<(Color, Ray)> {
+    fn sample(&self, r_in: Ray, rec: &HitRecord) -> Option<ScatterSample> {
         let attenuation = Color::new(1.0, 1.0, 1.0);
         let refraction_ratio = if rec.front_face {
             1.0 / self.ir
@@ -92,15 +172,136 @@ impl Material for Dielectric {
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let direction;
-        if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > random::<f32>() {
-            direction = reflect(unit_direction, rec.normal);
+        let direction =
+            if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > random::<f32>() {
+                reflect(unit_direction, rec.normal)
+            } else {
+                refract(unit_direction, rec.normal, refraction_ratio)
+            };
+
+        let scattered = Ray::with_time(rec.p, direction, r_in.time());
+        Some(ScatterSample::specular(scattered, attenuation))
+    }
+}
+
+/// An area light: emits `emit` from both faces and absorbs everything.
+pub struct DiffuseLight {
+    emit: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn sample(&self, _r_in: Ray, _rec: &HitRecord) -> Option<ScatterSample> {
+        None
+    }
+
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        self.emit
+    }
+}
+
+/// Scatters equally in every direction, regardless of the "normal": the
+/// phase function of a participating medium such as a `ConstantMedium`.
+pub struct Isotropic {
+    albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self::textured(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn sample(&self, r_in: Ray, rec: &HitRecord) -> Option<ScatterSample> {
+        Some(ScatterSample {
+            ray: Ray::with_time(rec.p, random_unit_vector(), r_in.time()),
+            attenuation: self.albedo.value(rec.u, rec.v, rec.p),
+            pdf: 1.0 / (4.0 * PI),
+            specular: false,
+        })
+    }
+
+    /// A phase function has no cosine term.
+    fn eval(&self, _r_in: Ray, rec: &HitRecord, _direction: Vec3) -> Color {
+        self.albedo.value(rec.u, rec.v, rec.p) / (4.0 * PI)
+    }
+
+    fn pdf(&self, _r_in: Ray, _rec: &HitRecord, _direction: Vec3) -> f32 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+/// The Henyey–Greenstein phase function, scattering mostly forwards for
+/// `g > 0` (like clouds) and backwards for `g < 0`; `g = 0` is isotropic.
+/// `g` is the mean cosine of the turn a scattered path takes.
+pub struct HenyeyGreenstein {
+    albedo: Arc<dyn Texture>,
+    g: f32,
+}
+
+impl HenyeyGreenstein {
+    /// `g` must lie strictly between -1 and 1.
+    pub fn new(albedo: Color, g: f32) -> Self {
+        Self::textured(Arc::new(SolidColor::new(albedo)), g)
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>, g: f32) -> Self {
+        assert!(g > -1.0 && g < 1.0, "g must lie strictly between -1 and 1");
+        Self { albedo, g }
+    }
+
+    /// Density of turning by an angle with cosine `cosine`.
+    fn phase(&self, cosine: f32) -> f32 {
+        let g = self.g;
+        let denom = 1.0 + g * g - 2.0 * g * cosine;
+        (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn sample(&self, r_in: Ray, rec: &HitRecord) -> Option<ScatterSample> {
+        let g = self.g;
+        let xi = random::<f32>();
+        // Inverting the cumulative distribution of the cosine.
+        let cosine = if g.abs() < 1e-3 {
+            1.0 - 2.0 * xi
         } else {
-            direction = refract(unit_direction, rec.normal, refraction_ratio);
-        }
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+            ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sine = (1.0 - cosine * cosine).max(0.0).sqrt();
+        let phi = 2.0 * PI * random::<f32>();
+        let direction = Onb::new(unit(r_in.direction())).local(Vec3::new(
+            sine * phi.cos(),
+            sine * phi.sin(),
+            cosine,
+        ));
+        Some(ScatterSample {
+            ray: Ray::with_time(rec.p, direction, r_in.time()),
+            attenuation: self.albedo.value(rec.u, rec.v, rec.p),
+            pdf: self.phase(cosine),
+            specular: false,
+        })
+    }
 
-        let scattered = Ray::new(rec.p, direction);
-        Some((attenuation, scattered))
+    /// As an importance-sampled phase function, `eval` is the albedo times
+    /// `pdf`.
+    fn eval(&self, r_in: Ray, rec: &HitRecord, direction: Vec3) -> Color {
+        self.albedo.value(rec.u, rec.v, rec.p) * self.pdf(r_in, rec, direction)
+    }
+
+    fn pdf(&self, r_in: Ray, _rec: &HitRecord, direction: Vec3) -> f32 {
+        self.phase(dot(unit(r_in.direction()), unit(direction)))
     }
 }
 
@@ -115,14 +316,126 @@ impl TestMaterial {
 }
 
 impl Material for TestMaterial {
-    fn scatter(&self, r_in: Ray, rec: HitRecord) -> Option<(Color, Ray)> {
+    fn sample(&self, r_in: Ray, rec: &HitRecord) -> Option<ScatterSample> {
         let reflected = reflect(unit(r_in.direction()), rec.normal);
-        let scattered = Ray::new(rec.p, reflected);
+        let scattered = Ray::with_time(rec.p, reflected, r_in.time());
         let attenuation = self.albedo;
         if dot(scattered.direction(), rec.normal) > 0. {
-            Some((attenuation, scattered))
+            Some(ScatterSample::specular(scattered, attenuation))
         } else {
             None
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rtweekend::seed_rng;
+
+    fn hit_record(material: Arc<dyn Material>) -> (Ray, HitRecord) {
+        let r_in = Ray::new(Point3::new(1., 1., 0.), Vec3::new(-1., -1., 0.));
+        let normal = unit(Vec3::new(0., 1., 1.));
+        let rec = HitRecord {
+            p: Point3::default(),
+            normal,
+            material,
+            t: 1.,
+            u: 0.,
+            v: 0.,
+            front_face: true,
+        };
+        (r_in, rec)
+    }
+
+    #[test]
+    fn lambertian_sampling_matches_eval() {
+        seed_rng(17);
+        let albedo = Color::new(0.8, 0.4, 0.2);
+        let lambertian = Lambertian::new(albedo);
+        let (r_in, rec) = hit_record(Arc::new(Lambertian::new(albedo)));
+        let n = 20000;
+        let mut mean_cosine = 0.0;
+        for _ in 0..n {
+            let sample = lambertian.sample(r_in, &rec).unwrap();
+            let direction = sample.ray.direction();
+            assert!(!sample.specular);
+            assert!((direction.length() - 1.).abs() < 1e-4);
+            assert!(dot(direction, rec.normal) >= 0.);
+            let pdf = lambertian.pdf(r_in, &rec, direction);
+            assert!((sample.pdf - pdf).abs() < 1e-5);
+            if pdf > 1e-3 {
+                let ratio = lambertian.eval(r_in, &rec, direction) / pdf;
+                assert!((ratio - sample.attenuation).length() < 1e-4);
+            }
+            mean_cosine += dot(direction, rec.normal) / n as f32;
+        }
+        // E[cos] under a cosine-weighted hemisphere is 2/3.
+        assert!((mean_cosine - 2. / 3.).abs() < 0.01);
+        assert_eq!(lambertian.eval(r_in, &rec, -rec.normal), Color::default());
+    }
+
+    #[test]
+    fn mirrors_are_specular() {
+        let (r_in, rec) = hit_record(Arc::new(Metal::new(Color::new(0.9, 0.9, 0.9), 0.0)));
+        let sample = rec.material.sample(r_in, &rec).unwrap();
+        assert!(sample.specular);
+        let mirrored = reflect(unit(r_in.direction()), rec.normal);
+        assert!((sample.ray.direction() - mirrored).length() < 1e-6);
+        assert_eq!(rec.material.pdf(r_in, &rec, mirrored), 0.);
+        assert_eq!(rec.material.eval(r_in, &rec, mirrored), Color::default());
+
+        let glass = Dielectric::new(1.5);
+        assert!(glass.sample(r_in, &rec).unwrap().specular);
+    }
+
+    #[test]
+    fn isotropic_scatters_everywhere() {
+        seed_rng(18);
+        let albedo = Color::new(0.5, 0.6, 0.7);
+        let (r_in, rec) = hit_record(Arc::new(Isotropic::new(albedo)));
+        let n = 20000;
+        let mut mean = Vec3::default();
+        for _ in 0..n {
+            let sample = rec.material.sample(r_in, &rec).unwrap();
+            let direction = sample.ray.direction();
+            assert!(!sample.specular);
+            assert_eq!(sample.attenuation, albedo);
+            let pdf = rec.material.pdf(r_in, &rec, direction);
+            assert_eq!(sample.pdf, pdf);
+            let ratio = rec.material.eval(r_in, &rec, direction) / pdf;
+            assert!((ratio - albedo).length() < 1e-5);
+            mean += direction / n as f32;
+        }
+        // Backwards as often as forwards, and off either face of the normal.
+        assert!(mean.length() < 0.02);
+    }
+
+    #[test]
+    fn henyey_greenstein_mean_cosine_is_g() {
+        seed_rng(21);
+        let albedo = Color::new(0.9, 0.8, 0.7);
+        for g in [-0.5, 0.0, 0.3, 0.85] {
+            let (r_in, rec) = hit_record(Arc::new(HenyeyGreenstein::new(albedo, g)));
+            let forward = unit(r_in.direction());
+            let n = 20000;
+            let mut mean_cosine = 0.0;
+            for _ in 0..n {
+                let sample = rec.material.sample(r_in, &rec).unwrap();
+                let direction = sample.ray.direction();
+                assert!((direction.length() - 1.).abs() < 1e-4);
+                let pdf = rec.material.pdf(r_in, &rec, direction);
+                assert!((sample.pdf - pdf).abs() < 1e-3 * pdf);
+                let ratio = rec.material.eval(r_in, &rec, direction) / pdf;
+                assert!((ratio - albedo).length() < 1e-4);
+                mean_cosine += dot(direction, forward) / n as f32;
+            }
+            assert!(
+                (mean_cosine - g).abs() < 0.015,
+                "g = {}: {}",
+                g,
+                mean_cosine
+            );
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use crate::aabb::{surrounding_box, Aabb};
+use crate::hittable::*;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sphere::{hit_sphere, sphere_intervals};
+use crate::vec3::*;
+
+/// A sphere sliding in a straight line from `center0` at `time0` to
+/// `center1` at `time1`, holding still before and after.
+///
+/// It is not sampled as a light: `sample_towards` has no time to place it
+/// at, so a glowing moving sphere is only found by scattered rays.
+#[derive(Clone)]
+pub struct MovingSphere {
+    center0: Point3,
+    center1: Point3,
+    time0: f32,
+    time1: f32,
+    radius: f32,
+    material: Arc<dyn Material>,
+}
+
+impl MovingSphere {
+    pub fn new(
+        center0: Point3,
+        center1: Point3,
+        time0: f32,
+        time1: f32,
+        radius: f32,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            material,
+        }
+    }
+
+    pub fn center(&self, time: f32) -> Point3 {
+        if self.time1 <= self.time0 {
+            return if time < self.time0 {
+                self.center0
+            } else {
+                self.center1
+            };
+        }
+        let s = ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0);
+        self.center0 + s * (self.center1 - self.center0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        hit_sphere(
+            self.center(r.time()),
+            self.radius,
+            &self.material,
+            r,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // The path is a segment, so the boxes at either end cover it.
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        let start = Aabb::new(self.center0 - r, self.center0 + r);
+        let end = Aabb::new(self.center1 - r, self.center1 + r);
+        Some(surrounding_box(start, end))
+    }
+
+    fn is_closed(&self) -> bool {
+        true
+    }
+
+    fn intervals(&self, r: Ray) -> Vec<Interval> {
+        sphere_intervals(self.center(r.time()), self.radius, &self.material, r)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn follows_the_shutter_time() {
+        let sphere = MovingSphere::new(
+            Point3::new(0., 0., -5.),
+            Point3::new(2., 0., -5.),
+            0.,
+            1.,
+            0.5,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        assert_eq!(sphere.center(0.5), Point3::new(1., 0., -5.));
+        assert_eq!(sphere.center(-1.), Point3::new(0., 0., -5.));
+        assert_eq!(sphere.center(3.), Point3::new(2., 0., -5.));
+
+        let towards =
+            |x, time| Ray::with_time(Point3::new(x, 0., 0.), Vec3::new(0., 0., -1.), time);
+        assert!(sphere.hit(towards(0., 0.), 0., f32::INFINITY).is_some());
+        assert!(sphere.hit(towards(0., 1.), 0., f32::INFINITY).is_none());
+        let rec = sphere.hit(towards(2., 1.), 0., f32::INFINITY).unwrap();
+        assert!((rec.t - 4.5).abs() < 1e-5);
+        assert_eq!(rec.normal, Vec3::new(0., 0., 1.));
+
+        let bbox = sphere.bounding_box().unwrap();
+        assert_eq!(bbox.min(), Point3::new(-0.5, -0.5, -5.5));
+        assert_eq!(bbox.max(), Point3::new(2.5, 0.5, -4.5));
+    }
+}
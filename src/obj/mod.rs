@@ -0,0 +1,6 @@
+mod error;
+mod mtl;
+mod parser;
+pub use error::*;
+pub use mtl::*;
+pub use parser::*;
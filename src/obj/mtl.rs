@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::error::ObjError;
+use crate::material::*;
+use crate::vec3::Color;
+
+/// The subset of an MTL material definition the renderer understands.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MtlMaterial {
+    pub name: String,
+    /// `Kd`
+    pub diffuse: Color,
+    /// `Ks`, if given
+    pub specular: Option<Color>,
+    /// `Ns`
+    pub shininess: f32,
+    /// `Ni`
+    pub optical_density: Option<f32>,
+    /// `d`, or `1 - Tr`
+    pub dissolve: f32,
+    /// `illum`
+    pub illum: u32,
+}
+
+impl MtlMaterial {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: None,
+            shininess: 0.0,
+            optical_density: None,
+            dissolve: 1.0,
+            illum: 2,
+        }
+    }
+
+    /// Map the MTL illumination model onto one of our materials.
+    ///
+    /// Transparent models (or `d < 1`) become `Dielectric` with `Ni` as the
+    /// index of refraction, reflective models become `Metal` tinted by `Ks`
+    /// (or `Kd` without one) with a fuzz derived from `Ns`, and everything
+    /// else is `Lambertian`.
+    pub fn to_material(&self) -> Arc<dyn Material> {
+        match self.illum {
+            _ if self.dissolve < 1.0 => self.dielectric(),
+            4 | 6 | 7 | 9 => self.dielectric(),
+            3 | 5 | 8 => {
+                // Phong exponent to a roughness-like fuzz in [0, 1].
+                let fuzz = (2.0 / (self.shininess.max(0.0) + 2.0)).sqrt();
+                Arc::new(Metal::new(self.specular.unwrap_or(self.diffuse), fuzz))
+            }
+            _ => Arc::new(Lambertian::new(self.diffuse)),
+        }
+    }
+
+    fn dielectric(&self) -> Arc<dyn Material> {
+        Arc::new(Dielectric::new(self.optical_density.unwrap_or(1.5)))
+    }
+}
+
+pub fn load_mtl(path: &Path) -> Result<Vec<MtlMaterial>, ObjError> {
+    let file = path.display().to_string();
+    let source = std::fs::read_to_string(path).map_err(|source| ObjError::Io {
+        file: file.clone(),
+        source,
+    })?;
+    parse_mtl(&source, &file)
+}
+
+pub fn parse_mtl(source: &str, file: &str) -> Result<Vec<MtlMaterial>, ObjError> {
+    let mut materials: Vec<MtlMaterial> = Vec::new();
+
+    for (line_number, line) in source.lines().enumerate() {
+        let line_number = line_number + 1;
+        let line = line.split('#').next().unwrap().trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+        let error = |message: String| ObjError::parse(file, line_number, message);
+
+        if keyword == "newmtl" {
+            let name = args.join(" ");
+            if name.is_empty() {
+                return Err(error("newmtl without a material name".to_string()));
+            }
+            materials.push(MtlMaterial::new(&name));
+            continue;
+        }
+
+        let current = match materials.last_mut() {
+            Some(material) => material,
+            None if matches!(keyword, "Kd" | "Ks" | "Ns" | "Ni" | "d" | "Tr" | "illum") => {
+                return Err(error(format!("'{}' before any newmtl", keyword)))
+            }
+            None => continue,
+        };
+        match keyword {
+            "Kd" => current.diffuse = parse_color(&args).map_err(error)?,
+            "Ks" => current.specular = Some(parse_color(&args).map_err(error)?),
+            "Ns" => current.shininess = parse_scalar(keyword, &args).map_err(error)?,
+            "Ni" => current.optical_density = Some(parse_scalar(keyword, &args).map_err(error)?),
+            "d" => current.dissolve = parse_scalar(keyword, &args).map_err(error)?,
+            "Tr" => current.dissolve = 1.0 - parse_scalar(keyword, &args).map_err(error)?,
+            "illum" => {
+                current.illum = match args.as_slice() {
+                    [value] => value
+                        .parse()
+                        .map_err(|_| error(format!("invalid illum model '{}'", value)))?,
+                    _ => return Err(error("illum expects one integer".to_string())),
+                }
+            }
+            // Texture maps, ambient and emissive terms are not supported.
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
+
+/// Build the renderer materials for a parsed library, keyed by name.
+pub fn to_materials(materials: &[MtlMaterial]) -> HashMap<String, Arc<dyn Material>> {
+    materials
+        .iter()
+        .map(|m| (m.name.clone(), m.to_material()))
+        .collect()
+}
+
+fn parse_scalar(keyword: &str, args: &[&str]) -> Result<f32, String> {
+    match args {
+        [value] => value
+            .parse()
+            .map_err(|_| format!("invalid number '{}' for {}", value, keyword)),
+        _ => Err(format!(
+            "{} expects one number, got {}",
+            keyword,
+            args.len()
+        )),
+    }
+}
+
+fn parse_color(args: &[&str]) -> Result<Color, String> {
+    if matches!(args.first(), Some(&"spectral") | Some(&"xyz")) {
+        return Err(format!("'{}' colors are not supported", args[0]));
+    }
+    let values = args
+        .iter()
+        .map(|value| {
+            value
+                .parse::<f32>()
+                .map_err(|_| format!("invalid color component '{}'", value))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match values.as_slice() {
+        [v] => Ok(Color::new(*v, *v, *v)),
+        [r, g, b] => Ok(Color::new(*r, *g, *b)),
+        _ => Err(format!(
+            "expected 1 or 3 color components, got {}",
+            values.len()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hittable::HitRecord;
+    use crate::ray::Ray;
+    use crate::vec3::{Point3, Vec3};
+
+    #[test]
+    fn parse_library() {
+        let source = "\
+# two materials
+newmtl red
+Kd 0.8 0.1 0.1
+illum 2
+
+newmtl glass
+Ni 1.45
+d 0.2 # mostly transparent
+illum 7
+";
+        let materials = parse_mtl(source, "test.mtl").unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].name, "red");
+        assert_eq!(materials[0].diffuse, Color::new(0.8, 0.1, 0.1));
+        assert_eq!(materials[1].optical_density, Some(1.45));
+        assert_eq!(materials[1].dissolve, 0.2);
+        assert_eq!(materials[1].illum, 7);
+    }
+
+    #[test]
+    fn mirrors_without_ks_keep_their_colour() {
+        let source = "newmtl gold\nKd 0.9 0.7 0.2\nNs 1000\nillum 3\n\
+                      newmtl tinted\nKs 0.1 0.2 0.3\nNs 1000\nillum 3\n";
+        let materials = parse_mtl(source, "test.mtl").unwrap();
+        assert_eq!(materials[0].specular, None);
+        let r_in = Ray::new(Point3::new(0., 0., 1.), Vec3::new(0., 0., -1.));
+        for (material, expected) in materials
+            .iter()
+            .zip([Color::new(0.9, 0.7, 0.2), Color::new(0.1, 0.2, 0.3)])
+        {
+            let rec = HitRecord {
+                p: Point3::default(),
+                normal: Vec3::new(0., 0., 1.),
+                material: material.to_material(),
+                t: 1.,
+                u: 0.,
+                v: 0.,
+                front_face: true,
+            };
+            let sample = rec.material.sample(r_in, &rec).unwrap();
+            assert!(sample.specular);
+            assert_eq!(sample.attenuation, expected);
+        }
+    }
+
+    #[test]
+    fn errors_point_at_line() {
+        let err = parse_mtl("newmtl a\nKd 1 2\n", "bad.mtl").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "bad.mtl:2: expected 1 or 3 color components, got 2"
+        );
+        let err = parse_mtl("Ns 10\n", "bad.mtl").unwrap_err();
+        assert_eq!(err.to_string(), "bad.mtl:1: 'Ns' before any newmtl");
+    }
+}
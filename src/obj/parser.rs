@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::error::ObjError;
+use super::mtl::*;
+use crate::hittable_list::HittableList;
+use crate::material::{Lambertian, Material};
+use crate::triangle_mesh::{Face, TriangleMesh};
+use crate::vec3::*;
+
+/// Load a Wavefront OBJ file, resolving `mtllib` statements relative to it.
+///
+/// Every group/material combination becomes one `TriangleMesh` in the
+/// returned list. Faces without a `usemtl` get a grey `Lambertian`.
+pub fn load_obj(path: &Path) -> Result<HittableList, ObjError> {
+    let file = path.display().to_string();
+    let source = std::fs::read_to_string(path).map_err(|source| ObjError::Io {
+        file: file.clone(),
+        source,
+    })?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    parse_obj(&source, &file, |library| load_mtl(&dir.join(library)))
+}
+
+/// Parse OBJ source text; `load_library` is called for each `mtllib` name.
+pub fn parse_obj(
+    source: &str,
+    file: &str,
+    mut load_library: impl FnMut(&str) -> Result<Vec<MtlMaterial>, ObjError>,
+) -> Result<HittableList, ObjError> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+
+    let default_material: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut current_material = (String::new(), default_material);
+    let mut current_group = String::from("default");
+
+    let mut meshes: Vec<MeshBuilder> = Vec::new();
+    let mut mesh_index: HashMap<(String, String), usize> = HashMap::new();
+
+    for (line_number, line) in source.lines().enumerate() {
+        let line_number = line_number + 1;
+        let line = line.split('#').next().unwrap().trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+        let error = |message: String| ObjError::parse(file, line_number, message);
+
+        match keyword {
+            "v" => {
+                let v = parse_floats(keyword, &args, 3, 4).map_err(error)?;
+                positions.push(Point3::new(v[0], v[1], v[2]));
+            }
+            "vn" => {
+                let v = parse_floats(keyword, &args, 3, 3).map_err(error)?;
+                normals.push(Vec3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let v = parse_floats(keyword, &args, 1, 3).map_err(error)?;
+                uvs.push((v[0], v.get(1).copied().unwrap_or(0.0)));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(error(format!(
+                        "face needs at least 3 vertices, got {}",
+                        args.len()
+                    )));
+                }
+                let vertices = args
+                    .iter()
+                    .map(|vertex| {
+                        parse_vertex(vertex, positions.len(), uvs.len(), normals.len())
+                            .map_err(error)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let key = (current_group.clone(), current_material.0.clone());
+                let index = *mesh_index.entry(key).or_insert_with(|| {
+                    meshes.push(MeshBuilder::new(current_material.1.clone()));
+                    meshes.len() - 1
+                });
+                let polygon: Vec<Point3> = vertices.iter().map(|v| positions[v.0]).collect();
+                for [a, b, c] in triangulate(&polygon) {
+                    let (a, b, c) = (vertices[a], vertices[b], vertices[c]);
+                    meshes[index].faces.push(Face {
+                        positions: [a.0, b.0, c.0],
+                        uvs: a.1.zip(b.1).zip(c.1).map(|((a, b), c)| [a, b, c]),
+                        normals: a.2.zip(b.2).zip(c.2).map(|((a, b), c)| [a, b, c]),
+                    });
+                }
+            }
+            "g" | "o" => {
+                current_group = if args.is_empty() {
+                    String::from("default")
+                } else {
+                    args.join(" ")
+                };
+            }
+            "usemtl" => {
+                let name = args.join(" ");
+                let material = materials
+                    .get(&name)
+                    .ok_or_else(|| error(format!("unknown material '{}'", name)))?;
+                current_material = (name, material.clone());
+            }
+            "mtllib" => {
+                if args.is_empty() {
+                    return Err(error("mtllib without a file name".to_string()));
+                }
+                for library in args {
+                    materials.extend(to_materials(&load_library(library)?));
+                }
+            }
+            // Smoothing groups, lines, points, free-form geometry and anything
+            // else unsupported are ignored, as in `parse_mtl`.
+            _ => {}
+        }
+    }
+
+    let mut world = HittableList::new();
+    for mesh in meshes {
+        world.add(Arc::new(mesh.build(&positions, &normals, &uvs)));
+    }
+    Ok(world)
+}
+
+/// Faces of one group/material pair, indexing the file-wide vertex buffers.
+struct MeshBuilder {
+    material: Arc<dyn Material>,
+    faces: Vec<Face>,
+}
+
+impl MeshBuilder {
+    fn new(material: Arc<dyn Material>) -> Self {
+        Self {
+            material,
+            faces: Vec::new(),
+        }
+    }
+
+    /// Copy only the vertices this mesh uses into its own compact buffers.
+    fn build(self, positions: &[Point3], normals: &[Vec3], uvs: &[(f32, f32)]) -> TriangleMesh {
+        fn remap<T: Copy>(
+            indices: [usize; 3],
+            source: &[T],
+            target: &mut Vec<T>,
+            map: &mut HashMap<usize, usize>,
+        ) -> [usize; 3] {
+            indices.map(|i| {
+                *map.entry(i).or_insert_with(|| {
+                    target.push(source[i]);
+                    target.len() - 1
+                })
+            })
+        }
+
+        let (mut mesh_positions, mut mesh_normals, mut mesh_uvs) =
+            (Vec::new(), Vec::new(), Vec::new());
+        let (mut position_map, mut normal_map, mut uv_map) =
+            (HashMap::new(), HashMap::new(), HashMap::new());
+        let faces = self
+            .faces
+            .iter()
+            .map(|face| Face {
+                positions: remap(
+                    face.positions,
+                    positions,
+                    &mut mesh_positions,
+                    &mut position_map,
+                ),
+                normals: face
+                    .normals
+                    .map(|n| remap(n, normals, &mut mesh_normals, &mut normal_map)),
+                uvs: face.uvs.map(|t| remap(t, uvs, &mut mesh_uvs, &mut uv_map)),
+            })
+            .collect();
+        TriangleMesh::new(mesh_positions, mesh_normals, mesh_uvs, faces, self.material)
+    }
+}
+
+fn parse_floats(keyword: &str, args: &[&str], min: usize, max: usize) -> Result<Vec<f32>, String> {
+    if args.len() < min || args.len() > max {
+        return Err(if min == max {
+            format!("{} expects {} numbers, got {}", keyword, min, args.len())
+        } else {
+            format!(
+                "{} expects {} to {} numbers, got {}",
+                keyword,
+                min,
+                max,
+                args.len()
+            )
+        });
+    }
+    args.iter()
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("invalid number '{}' in {}", value, keyword))
+        })
+        .collect()
+}
+
+/// Parse a `v`, `v/vt`, `v//vn` or `v/vt/vn` face vertex into zero-based indices.
+fn parse_vertex(
+    vertex: &str,
+    positions: usize,
+    uvs: usize,
+    normals: usize,
+) -> Result<(usize, Option<usize>, Option<usize>), String> {
+    let mut parts = vertex.split('/');
+    let position = resolve_index(parts.next().unwrap_or(""), positions, "vertex")?;
+    let uv = match parts.next() {
+        Some("") | None => None,
+        Some(index) => Some(resolve_index(index, uvs, "texture coordinate")?),
+    };
+    let normal = match parts.next() {
+        Some("") | None => None,
+        Some(index) => Some(resolve_index(index, normals, "normal")?),
+    };
+    if parts.next().is_some() {
+        return Err(format!("malformed face vertex '{}'", vertex));
+    }
+    Ok((position, uv, normal))
+}
+
+/// OBJ indices are one-based, and negative values count back from the end.
+fn resolve_index(index: &str, len: usize, what: &str) -> Result<usize, String> {
+    let i: i64 = index
+        .parse()
+        .map_err(|_| format!("invalid {} index '{}'", what, index))?;
+    let resolved = if i > 0 { i - 1 } else { len as i64 + i };
+    if i == 0 || resolved < 0 || resolved >= len as i64 {
+        return Err(format!(
+            "{} index {} out of range ({} defined)",
+            what, i, len
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+/// Split a planar polygon into triangles by ear clipping.
+///
+/// The polygon is projected onto the plane of its dominant normal axis so
+/// concave faces come out right; degenerate input falls back to a fan.
+fn triangulate(polygon: &[Point3]) -> Vec<[usize; 3]> {
+    let n = polygon.len();
+    if n == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // Newell's method for the polygon normal.
+    let mut normal = Vec3::default();
+    for i in 0..n {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        normal += Vec3::new(
+            (a.y() - b.y()) * (a.z() + b.z()),
+            (a.z() - b.z()) * (a.x() + b.x()),
+            (a.x() - b.x()) * (a.y() + b.y()),
+        );
+    }
+    let (ax, ay) = if normal.x().abs() > normal.y().abs() && normal.x().abs() > normal.z().abs() {
+        (1, 2)
+    } else if normal.y().abs() > normal.z().abs() {
+        (2, 0)
+    } else {
+        (0, 1)
+    };
+    let points: Vec<(f32, f32)> = polygon.iter().map(|p| (p[ax], p[ay])).collect();
+    let area2 = |a: usize, b: usize, c: usize| {
+        let (a, b, c) = (points[a], points[b], points[c]);
+        (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+    };
+    let winding: f32 = (0..n)
+        .map(|i| area2(0, i, (i + 1) % n))
+        .sum::<f32>()
+        .signum();
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let ear = (0..m).find(|&i| {
+            let (a, b, c) = (
+                remaining[(i + m - 1) % m],
+                remaining[i],
+                remaining[(i + 1) % m],
+            );
+            if area2(a, b, c) * winding <= 0.0 {
+                return false;
+            }
+            remaining.iter().all(|&p| {
+                p == a
+                    || p == b
+                    || p == c
+                    || area2(a, b, p) * winding < 0.0
+                    || area2(b, c, p) * winding < 0.0
+                    || area2(c, a, p) * winding < 0.0
+            })
+        });
+        match ear {
+            Some(i) => {
+                triangles.push([
+                    remaining[(i + m - 1) % m],
+                    remaining[i],
+                    remaining[(i + 1) % m],
+                ]);
+                remaining.remove(i);
+            }
+            None => break,
+        }
+    }
+    for i in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+    triangles
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hittable::Hittable;
+    use crate::ray::Ray;
+
+    fn no_libraries(name: &str) -> Result<Vec<MtlMaterial>, ObjError> {
+        panic!("unexpected mtllib {}", name)
+    }
+
+    #[test]
+    fn concave_polygon() {
+        // An L shape; a fan from vertex 0 would cover the notch.
+        let polygon = [
+            Point3::new(0., 0., 0.),
+            Point3::new(2., 0., 0.),
+            Point3::new(2., 1., 0.),
+            Point3::new(1., 1., 0.),
+            Point3::new(1., 2., 0.),
+            Point3::new(0., 2., 0.),
+        ];
+        let triangles = triangulate(&polygon);
+        assert_eq!(triangles.len(), 4);
+        let area: f32 = triangles
+            .iter()
+            .map(|&[a, b, c]| cross(polygon[b] - polygon[a], polygon[c] - polygon[a]).length() / 2.)
+            .sum();
+        assert!((area - 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn groups_and_materials() {
+        let source = "\
+mtllib scene.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+g floor
+usemtl red
+f 1/1/1 2/2/1 3/3/1 4/4/1
+g wall
+f -4//1 -3//1 -2//1
+usemtl glass
+f 1 2 3
+";
+        let world = parse_obj(source, "test.obj", |name| {
+            assert_eq!(name, "scene.mtl");
+            parse_mtl("newmtl red\nKd 1 0 0\nnewmtl glass\nillum 7\n", name)
+        })
+        .unwrap();
+        assert_eq!(world.objects().len(), 3);
+
+        let r = Ray::new(Point3::new(0.75, 0.25, 1.), Vec3::new(0., 0., -1.));
+        let rec = world.hit(r, 0.001, f32::INFINITY).unwrap();
+        assert_eq!(rec.t, 1.0);
+        assert_eq!(rec.normal, Vec3::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn skips_unsupported_statements() {
+        let source = "\
+mg 1 0.5
+usemap brick
+lod 2
+v 0 0 0
+v 1 0 0
+v 0 1 0
+cstype bspline
+deg 3
+curv 0 1 1 2 3
+parm u 0 0 0 1 1 1
+end
+bevel on
+s 1
+f 1 2 3
+";
+        let world = parse_obj(source, "test.obj", no_libraries).unwrap();
+        assert_eq!(world.objects().len(), 1);
+    }
+
+    #[test]
+    fn errors_point_at_line() {
+        let err = parse_obj("v 0 0 0\nv 1 0\n", "bad.obj", no_libraries)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "bad.obj:2: v expects 3 to 4 numbers, got 2"
+        );
+
+        let err = parse_obj(
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\n\nf 1 2 4\n",
+            "bad.obj",
+            no_libraries,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "bad.obj:5: vertex index 4 out of range (3 defined)"
+        );
+
+        let err = parse_obj("usemtl missing\n", "bad.obj", no_libraries)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "bad.obj:1: unknown material 'missing'");
+    }
+}
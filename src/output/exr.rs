@@ -0,0 +1,236 @@
+//! Scanline OpenEXR with 32-bit float channels.
+
+use std::io::{Result, Write};
+
+use miniz_oxide::deflate::compress_to_vec_zlib;
+
+use crate::image::Image;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExrCompression {
+    None,
+    /// zlib over blocks of 16 scanlines.
+    #[default]
+    Zip,
+}
+
+impl ExrCompression {
+    fn id(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zip => 3,
+        }
+    }
+
+    fn lines_per_block(self) -> usize {
+        match self {
+            Self::None => 1,
+            Self::Zip => 16,
+        }
+    }
+}
+
+/// Channel names in the alphabetical order EXR stores them, with the index
+/// of the matching colour component.
+const CHANNELS: [(&str, usize); 3] = [("B", 2), ("G", 1), ("R", 0)];
+const PIXEL_TYPE_FLOAT: i32 = 2;
+
+pub fn write_exr<W: Write>(image: &Image, compression: ExrCompression, out: &mut W) -> Result<()> {
+    let (width, height) = (image.width(), image.height());
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]);
+    // Version 2, single-part scanline file.
+    header.extend_from_slice(&2u32.to_le_bytes());
+
+    let mut channels = Vec::new();
+    for (name, _) in CHANNELS {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&PIXEL_TYPE_FLOAT.to_le_bytes());
+        // pLinear and three reserved bytes, then x and y sampling.
+        channels.extend_from_slice(&[0; 4]);
+        channels.extend_from_slice(&1i32.to_le_bytes());
+        channels.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channels.push(0);
+    let window: Vec<u8> = [0, 0, width as i32 - 1, height as i32 - 1]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+
+    attribute(&mut header, "channels", "chlist", &channels);
+    attribute(
+        &mut header,
+        "compression",
+        "compression",
+        &[compression.id()],
+    );
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    // Increasing y.
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    let lines = compression.lines_per_block();
+    let blocks: Vec<Vec<u8>> = (0..height)
+        .step_by(lines)
+        .map(|y| {
+            let raw = raw_block(image, y, lines.min(height - y));
+            let data = match compression {
+                ExrCompression::None => raw,
+                ExrCompression::Zip => zip_block(raw),
+            };
+            let mut block = Vec::with_capacity(8 + data.len());
+            block.extend_from_slice(&(y as i32).to_le_bytes());
+            block.extend_from_slice(&(data.len() as i32).to_le_bytes());
+            block.extend(data);
+            block
+        })
+        .collect();
+
+    // The offset table gives the absolute file position of every block.
+    let mut offset = (header.len() + 8 * blocks.len()) as u64;
+    for block in blocks.iter() {
+        header.extend_from_slice(&offset.to_le_bytes());
+        offset += block.len() as u64;
+    }
+    out.write_all(&header)?;
+    for block in blocks.iter() {
+        out.write_all(block)?;
+    }
+    Ok(())
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    for s in [name, kind] {
+        header.extend_from_slice(s.as_bytes());
+        header.push(0);
+    }
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+/// Scanlines `y..y + lines`, each holding one channel after the other.
+fn raw_block(image: &Image, y: usize, lines: usize) -> Vec<u8> {
+    let width = image.width();
+    let mut raw = Vec::with_capacity(12 * width * lines);
+    for row in image.pixels()[y * width..(y + lines) * width].chunks(width) {
+        for (_, component) in CHANNELS {
+            for c in row {
+                let v = [c.x(), c.y(), c.z()][component];
+                raw.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+    }
+    raw
+}
+
+/// Deinterleave the bytes, delta encode them and deflate the result. Blocks
+/// that do not shrink are stored raw; readers recognise them by their size.
+fn zip_block(raw: Vec<u8>) -> Vec<u8> {
+    let mut bytes: Vec<u8> = raw.iter().step_by(2).copied().collect();
+    bytes.extend(raw.iter().skip(1).step_by(2));
+    for i in (1..bytes.len()).rev() {
+        bytes[i] = bytes[i].wrapping_sub(bytes[i - 1]).wrapping_add(128);
+    }
+    let compressed = compress_to_vec_zlib(&bytes, 6);
+    if compressed.len() < raw.len() {
+        compressed
+    } else {
+        raw
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vec3::Color;
+    use miniz_oxide::inflate::decompress_to_vec_zlib;
+
+    fn unzip_block(data: &[u8]) -> Vec<u8> {
+        let mut bytes = decompress_to_vec_zlib(data).unwrap();
+        for i in 1..bytes.len() {
+            bytes[i] = bytes[i].wrapping_add(bytes[i - 1]).wrapping_sub(128);
+        }
+        let (even, odd) = bytes.split_at(bytes.len().div_ceil(2));
+        let mut raw = Vec::with_capacity(bytes.len());
+        for i in 0..bytes.len() {
+            raw.push(if i % 2 == 0 { even[i / 2] } else { odd[i / 2] });
+        }
+        raw
+    }
+
+    /// The `(y, data)` of every block, read through the offset table that
+    /// follows the header.
+    fn blocks(exr: &[u8], count: usize) -> Vec<(i32, Vec<u8>)> {
+        let table = header_len(exr);
+        (0..count)
+            .map(|i| {
+                let at = table + 8 * i;
+                let offset = u64::from_le_bytes(exr[at..at + 8].try_into().unwrap()) as usize;
+                let y = i32::from_le_bytes(exr[offset..offset + 4].try_into().unwrap());
+                let len = i32::from_le_bytes(exr[offset + 4..offset + 8].try_into().unwrap());
+                (y, exr[offset + 8..offset + 8 + len as usize].to_vec())
+            })
+            .collect()
+    }
+
+    /// Skip the attributes up to the empty name ending the header.
+    fn header_len(exr: &[u8]) -> usize {
+        let mut pos = 8;
+        while exr[pos] != 0 {
+            for _ in 0..2 {
+                pos += exr[pos..].iter().position(|&b| b == 0).unwrap() + 1;
+            }
+            let size = i32::from_le_bytes(exr[pos..pos + 4].try_into().unwrap()) as usize;
+            pos += 4 + size;
+        }
+        pos + 1
+    }
+
+    #[test]
+    fn zip_matches_uncompressed() {
+        let mut image = Image::new(5, 20);
+        for y in 0..20 {
+            for x in 0..5 {
+                image.set(x, y, Color::new(x as f32, y as f32 * 10., 0.5));
+            }
+        }
+        let mut plain = Vec::new();
+        write_exr(&image, ExrCompression::None, &mut plain).unwrap();
+        let mut zip = Vec::new();
+        write_exr(&image, ExrCompression::Zip, &mut zip).unwrap();
+        assert_eq!(&plain[..4], [0x76, 0x2f, 0x31, 0x01]);
+
+        let lines = blocks(&plain, 20);
+        assert_eq!(lines[3].0, 3);
+        // Samples from the B, G and R channels of the fourth scanline.
+        let first =
+            |data: &[u8], at: usize| f32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+        assert_eq!(first(&lines[3].1, 4 * 4), 0.5);
+        assert_eq!(first(&lines[3].1, 20 + 4), 30.);
+        assert_eq!(first(&lines[3].1, 40 + 8), 2.);
+
+        let zipped = blocks(&zip, 2);
+        assert_eq!((zipped[0].0, zipped[1].0), (0, 16));
+        for (block, lines) in zipped.iter().zip([&lines[..16], &lines[16..]]) {
+            let raw: Vec<u8> = lines.iter().flat_map(|(_, data)| data.clone()).collect();
+            assert!(block.1.len() < raw.len());
+            assert_eq!(unzip_block(&block.1), raw);
+        }
+    }
+}
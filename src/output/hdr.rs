@@ -0,0 +1,158 @@
+//! Floating point formats keeping the linear, unclamped radiance.
+
+use std::io::{Result, Write};
+
+use crate::image::Image;
+use crate::vec3::Color;
+
+/// Write a little-endian colour PFM. Scanlines run bottom to top.
+pub fn write_pfm<W: Write>(image: &Image, out: &mut W) -> Result<()> {
+    // A negative scale marks the data as little-endian.
+    write!(out, "PF\n{} {}\n-1.0\n", image.width(), image.height())?;
+    let mut bytes = Vec::with_capacity(12 * image.pixels().len());
+    for row in image.pixels().chunks(image.width().max(1)).rev() {
+        for c in row {
+            for v in [c.x(), c.y(), c.z()] {
+                bytes.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+    }
+    out.write_all(&bytes)
+}
+
+/// Write a Radiance RGBE (`.hdr`) image with run-length encoded scanlines.
+pub fn write_hdr<W: Write>(image: &Image, out: &mut W) -> Result<()> {
+    write!(
+        out,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        image.height(),
+        image.width()
+    )?;
+    let mut bytes = Vec::with_capacity(4 * image.pixels().len());
+    for row in image.pixels().chunks(image.width().max(1)) {
+        let pixels: Vec<[u8; 4]> = row.iter().map(|&c| rgbe(c)).collect();
+        write_rgbe_scanline(&pixels, &mut bytes);
+    }
+    out.write_all(&bytes)
+}
+
+/// Shared-exponent encoding of a colour. Negative and NaN components become
+/// zero, anything beyond the largest exponent is clamped.
+fn rgbe(color: Color) -> [u8; 4] {
+    const MAX: f32 = 1.7e38;
+    let c =
+        [color.x(), color.y(), color.z()].map(|v| if v.is_nan() { 0.0 } else { v.clamp(0.0, MAX) });
+    let max = c[0].max(c[1]).max(c[2]);
+    if max < 1e-32 {
+        return [0; 4];
+    }
+    // max = m * 2^e with m in [0.5, 1), read off the (normal) float's exponent.
+    let e = ((max.to_bits() >> 23) & 0xff) as i32 - 126;
+    let scale = 2f32.powi(8 - e);
+    [
+        (c[0] * scale) as u8,
+        (c[1] * scale) as u8,
+        (c[2] * scale) as u8,
+        (e + 128) as u8,
+    ]
+}
+
+/// Append one scanline in the "new" RLE layout: each of the four components
+/// is run-length encoded separately. Widths the layout cannot describe are
+/// written flat.
+fn write_rgbe_scanline(pixels: &[[u8; 4]], out: &mut Vec<u8>) {
+    let width = pixels.len();
+    if !(8..0x8000).contains(&width) {
+        out.extend(pixels.iter().flatten());
+        return;
+    }
+    out.extend_from_slice(&[2, 2, (width >> 8) as u8, width as u8]);
+    for component in 0..4 {
+        let values: Vec<u8> = pixels.iter().map(|p| p[component]).collect();
+        write_runs(&values, out);
+    }
+}
+
+/// Runs are a count byte above 128 followed by the repeated value, literal
+/// spans a count byte of at most 128 followed by the values.
+fn write_runs(values: &[u8], out: &mut Vec<u8>) {
+    const MIN_RUN: usize = 4;
+    let run_at = |i: usize, limit: usize| {
+        values[i..]
+            .iter()
+            .take(limit)
+            .take_while(|&&v| v == values[i])
+            .count()
+    };
+
+    let mut i = 0;
+    while i < values.len() {
+        let run = run_at(i, 127);
+        if run >= MIN_RUN {
+            out.extend_from_slice(&[128 + run as u8, values[i]]);
+            i += run;
+            continue;
+        }
+        let start = i;
+        while i < values.len() && i - start < 128 && run_at(i, MIN_RUN) < MIN_RUN {
+            i += 1;
+        }
+        out.push((i - start) as u8);
+        out.extend_from_slice(&values[start..i]);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn read_runs(mut data: &[u8], len: usize) -> Vec<u8> {
+        let mut values = Vec::new();
+        while values.len() < len {
+            let count = data[0] as usize;
+            if count > 128 {
+                values.extend(std::iter::repeat_n(data[1], count - 128));
+                data = &data[2..];
+            } else {
+                values.extend_from_slice(&data[1..1 + count]);
+                data = &data[1 + count..];
+            }
+        }
+        assert!(data.is_empty());
+        values
+    }
+
+    #[test]
+    fn rgbe_encoding() {
+        assert_eq!(rgbe(Color::new(1., 0.5, 0.25)), [128, 64, 32, 129]);
+        assert_eq!(rgbe(Color::new(0., 0., 0.)), [0, 0, 0, 0]);
+        assert_eq!(rgbe(Color::new(-1., f32::NAN, 12.)), [0, 0, 192, 132]);
+    }
+
+    #[test]
+    fn runs_round_trip() {
+        let mut values = vec![7; 300];
+        values.extend(0..=200);
+        values.extend([1, 1, 1, 2, 2, 9, 9, 9, 9, 9]);
+        let mut encoded = Vec::new();
+        write_runs(&values, &mut encoded);
+        assert!(encoded.len() < values.len());
+        assert_eq!(read_runs(&encoded, values.len()), values);
+    }
+
+    #[test]
+    fn pfm_rows_bottom_up() {
+        let mut image = Image::new(1, 2);
+        image.set(0, 1, Color::new(2.5, 0., -1.));
+        let mut pfm = Vec::new();
+        write_pfm(&image, &mut pfm).unwrap();
+        let header = b"PF\n1 2\n-1.0\n";
+        assert_eq!(&pfm[..header.len()], header);
+        let first: Vec<f32> = pfm[header.len()..header.len() + 12]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(first, [2.5, 0., -1.]);
+        assert_eq!(pfm.len(), header.len() + 24);
+    }
+}
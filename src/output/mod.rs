@@ -0,0 +1,117 @@
+//! Image file writers.
+//!
+//! The 8-bit formats clamp and encode the colours; PFM, Radiance HDR and
+//! OpenEXR store the linear, unclamped averages straight from the `Film`.
+
+use std::io::{Result, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::image::Image;
+use crate::vec3::Color;
+
+mod exr;
+mod hdr;
+mod png;
+
+pub use exr::{write_exr, ExrCompression};
+pub use hdr::{write_hdr, write_pfm};
+pub use png::write_png;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Binary (P6) PPM, 8-bit sRGB.
+    Ppm,
+    /// ASCII (P3) PPM with gamma 2 encoding, as written by `write_color`.
+    PpmAscii,
+    /// 8-bit sRGB PNG.
+    Png,
+    /// Little-endian float PFM, linear.
+    Pfm,
+    /// Radiance RGBE, linear.
+    Hdr,
+    /// Scanline OpenEXR with float channels, linear.
+    Exr(ExrCompression),
+}
+
+impl OutputFormat {
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "ppm" => Some(Self::Ppm),
+            "png" => Some(Self::Png),
+            "pfm" => Some(Self::Pfm),
+            "hdr" => Some(Self::Hdr),
+            "exr" => Some(Self::Exr(ExrCompression::default())),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "ppm" => Ok(Self::Ppm),
+            "ppm-ascii" => Ok(Self::PpmAscii),
+            "png" => Ok(Self::Png),
+            "pfm" => Ok(Self::Pfm),
+            "hdr" => Ok(Self::Hdr),
+            "exr" => Ok(Self::Exr(ExrCompression::Zip)),
+            "exr-uncompressed" => Ok(Self::Exr(ExrCompression::None)),
+            _ => Err(format!(
+                "unknown output format '{}' (expected ppm, ppm-ascii, png, pfm, hdr, exr or exr-uncompressed)",
+                s
+            )),
+        }
+    }
+}
+
+pub fn write_image<W: Write>(image: &Image, format: OutputFormat, out: &mut W) -> Result<()> {
+    match format {
+        OutputFormat::Ppm => write_ppm_binary(image, out),
+        OutputFormat::PpmAscii => image.write_ppm(out),
+        OutputFormat::Png => write_png(image, out),
+        OutputFormat::Pfm => write_pfm(image, out),
+        OutputFormat::Hdr => write_hdr(image, out),
+        OutputFormat::Exr(compression) => write_exr(image, compression, out),
+    }
+}
+
+/// Encode a linear component with the sRGB transfer function.
+pub fn linear_to_srgb8(c: f32) -> u8 {
+    let c = if c.is_nan() { 0.0 } else { c.clamp(0.0, 1.0) };
+    let encoded = if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0 + 0.5) as u8
+}
+
+fn srgb8(color: Color) -> [u8; 3] {
+    [
+        linear_to_srgb8(color.x()),
+        linear_to_srgb8(color.y()),
+        linear_to_srgb8(color.z()),
+    ]
+}
+
+pub fn write_ppm_binary<W: Write>(image: &Image, out: &mut W) -> Result<()> {
+    write!(out, "P6\n{} {}\n255\n", image.width(), image.height())?;
+    let bytes: Vec<u8> = image.pixels().iter().flat_map(|&c| srgb8(c)).collect();
+    out.write_all(&bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn srgb_encoding() {
+        assert_eq!(linear_to_srgb8(0.0), 0);
+        assert_eq!(linear_to_srgb8(1.0), 255);
+        assert_eq!(linear_to_srgb8(4.0), 255);
+        assert_eq!(linear_to_srgb8(0.5), 188);
+    }
+}
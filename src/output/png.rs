@@ -0,0 +1,166 @@
+use std::io::{Result, Write};
+
+use super::srgb8;
+use crate::image::Image;
+
+pub fn write_png<W: Write>(image: &Image, out: &mut W) -> Result<()> {
+    out.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(image.width() as u32).to_be_bytes());
+    header.extend_from_slice(&(image.height() as u32).to_be_bytes());
+    // 8 bits per channel, RGB, deflate, adaptive filtering, no interlace.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(out, b"IHDR", &header)?;
+    // Perceptual rendering intent.
+    write_chunk(out, b"sRGB", &[0])?;
+
+    // Each scanline is prefixed with its filter type; we always use "None".
+    let mut raw = Vec::with_capacity(image.height() * (1 + 3 * image.width()));
+    for row in image.pixels().chunks(image.width().max(1)) {
+        raw.push(0);
+        raw.extend(row.iter().flat_map(|&c| srgb8(c)));
+    }
+    write_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+    write_chunk(out, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+    out.write_all(&crc.finish().to_be_bytes())
+}
+
+/// Wrap `data` in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 65535;
+    let blocks = data.len() / MAX_BLOCK + 1;
+    let mut stream = Vec::with_capacity(data.len() + 5 * blocks + 6);
+    // CM = 8 (deflate), 32K window, no dictionary, FCHECK making it a multiple of 31.
+    stream.extend_from_slice(&[0x78, 0x01]);
+
+    let mut chunks = data.chunks(MAX_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        stream.push(last as u8);
+        stream.extend_from_slice(&len.to_le_bytes());
+        stream.extend_from_slice(&(!len).to_le_bytes());
+        stream.extend_from_slice(chunk);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest run that cannot overflow `b` before reducing.
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+struct Crc32 {
+    table: [u32; 256],
+    crc: u32,
+}
+
+impl Crc32 {
+    fn new() -> Self {
+        let mut table = [0u32; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+            for _ in 0..8 {
+                c = if c & 1 != 0 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+            }
+            *entry = c;
+        }
+        Self {
+            table,
+            crc: 0xffff_ffff,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.crc = self.table[((self.crc ^ byte as u32) & 0xff) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        self.crc ^ 0xffff_ffff
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vec3::Color;
+
+    #[test]
+    fn checksums() {
+        let mut crc = Crc32::new();
+        crc.update(b"IEND");
+        assert_eq!(crc.finish(), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn png_framing() {
+        let mut image = Image::new(3, 2);
+        image.set(0, 0, Color::new(1., 0., 0.));
+        image.set(2, 1, Color::new(0., 0., 1.));
+        let mut png = Vec::new();
+        write_png(&image, &mut png).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        // Walk the chunks, checking each CRC, and collect the image data.
+        let mut pos = 8;
+        let mut kinds = Vec::new();
+        let mut idat = Vec::new();
+        while pos < png.len() {
+            let len = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+            let kind = &png[pos + 4..pos + 8];
+            let data = &png[pos + 8..pos + 8 + len];
+            let mut crc = Crc32::new();
+            crc.update(kind);
+            crc.update(data);
+            let stored = u32::from_be_bytes(png[pos + 8 + len..pos + 12 + len].try_into().unwrap());
+            assert_eq!(crc.finish(), stored);
+            if kind == b"IDAT" {
+                idat.extend_from_slice(data);
+            }
+            kinds.push(String::from_utf8(kind.to_vec()).unwrap());
+            pos += 12 + len;
+        }
+        assert_eq!(kinds, ["IHDR", "sRGB", "IDAT", "IEND"]);
+
+        // One final stored block holding both filtered scanlines.
+        assert_eq!((idat[0] as u16 * 256 + idat[1] as u16) % 31, 0);
+        assert_eq!(idat[2], 1);
+        let raw = &idat[7..idat.len() - 4];
+        assert_eq!(
+            raw,
+            [0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255]
+        );
+        assert_eq!(&idat[idat.len() - 4..], adler32(raw).to_be_bytes());
+    }
+}
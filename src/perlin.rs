@@ -0,0 +1,143 @@
+//! Gradient noise for procedural textures.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::vec3::*;
+
+/// Ken Perlin's improved noise, with its permutation shuffled from a seed
+/// so that the same seed always gives the same pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Perlin {
+    perm: Vec<u8>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut perm: Vec<u8> = (0..=255).collect();
+        perm.shuffle(&mut StdRng::seed_from_u64(seed));
+        // Doubled, so corner hashes never need wrapping.
+        perm.extend_from_within(..);
+        Self { perm }
+    }
+
+    /// Smooth noise in about [-1, 1], zero at every lattice point.
+    pub fn noise(&self, p: Point3) -> f32 {
+        let cell = [p.x().floor(), p.y().floor(), p.z().floor()];
+        let [x, y, z] = [p.x() - cell[0], p.y() - cell[1], p.z() - cell[2]];
+        let [i, j, k] = cell.map(|c| (c as i64 & 255) as usize);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let perm = &self.perm;
+        let a = perm[i] as usize + j;
+        let (aa, ab) = (perm[a] as usize + k, perm[a + 1] as usize + k);
+        let b = perm[i + 1] as usize + j;
+        let (ba, bb) = (perm[b] as usize + k, perm[b + 1] as usize + k);
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(perm[aa], x, y, z), grad(perm[ba], x - 1.0, y, z)),
+                lerp(
+                    u,
+                    grad(perm[ab], x, y - 1.0, z),
+                    grad(perm[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(perm[aa + 1], x, y, z - 1.0),
+                    grad(perm[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    grad(perm[ab + 1], x, y - 1.0, z - 1.0),
+                    grad(perm[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+
+    /// Fractional Brownian motion: `octaves` layers of noise, each at twice
+    /// the frequency and half the amplitude of the one before. Signed, in
+    /// about [-2, 2].
+    pub fn fbm(&self, p: Point3, octaves: u32) -> f32 {
+        self.octaves(p, octaves, |n| n)
+    }
+
+    /// Like `fbm`, but summing the absolute value of each layer, which puts
+    /// creases where the noise crosses zero. In about [0, 2].
+    pub fn turbulence(&self, p: Point3, octaves: u32) -> f32 {
+        self.octaves(p, octaves, f32::abs)
+    }
+
+    fn octaves(&self, p: Point3, octaves: u32, layer: impl Fn(f32) -> f32) -> f32 {
+        let mut sum = 0.0;
+        let mut p = p;
+        let mut amplitude = 1.0;
+        for _ in 0..octaves {
+            sum += amplitude * layer(self.noise(p));
+            p = 2.0 * p;
+            amplitude *= 0.5;
+        }
+        sum
+    }
+}
+
+/// 6t^5 - 15t^4 + 10t^3, flat in its first and second derivatives at 0 and 1.
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// Dot product of `(x, y, z)` with one of the twelve edge directions of a
+/// cube, picked by `hash`.
+fn grad(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn seeded_and_smooth() {
+        let noise = Perlin::new(1);
+        assert_eq!(noise, Perlin::new(1));
+        assert_ne!(noise, Perlin::new(2));
+
+        let mut spread = 0.0f32;
+        for i in 0..2000 {
+            let t = i as f32 * 0.0173;
+            let p = Point3::new(3.1 * t - 7., 1.7 * t, -2.3 * t + 0.4);
+            let n = noise.noise(p);
+            assert!(n.abs() <= 1.05);
+            spread = spread.max(n.abs());
+            // Lattice points are zero crossings.
+            let lattice = Point3::new(p.x().round(), p.y().round(), p.z().round());
+            assert_eq!(noise.noise(lattice), 0.);
+            // Gradients are bounded, so nearby points get similar values.
+            let q = p + Vec3::new(1e-3, -1e-3, 1e-3);
+            assert!((noise.noise(q) - n).abs() < 1e-2);
+
+            let turbulence = noise.turbulence(p, 7);
+            assert!((0.0..=2.0).contains(&turbulence));
+            assert!(noise.fbm(p, 7).abs() <= 2.0);
+        }
+        assert!(spread > 0.5);
+    }
+}
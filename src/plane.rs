@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::*;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::*;
+
+/// An infinite plane through `point`, facing along `normal`. Texture
+/// coordinates are distances along two directions in the plane, so
+/// textures should repeat.
+///
+/// It has no bounding box: keep it out of a `BvhNode`, as
+/// `bvh::accelerate` does.
+#[derive(Clone)]
+pub struct Plane {
+    point: Point3,
+    frame: Onb,
+    material: Arc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, material: Arc<dyn Material>) -> Self {
+        Self {
+            point,
+            frame: Onb::new(unit(normal)),
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let normal = self.frame.w();
+        let denom = dot(normal, r.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = dot(normal, self.point - r.origin()) / denom;
+        if !(t_min..=t_max).contains(&t) {
+            return None;
+        }
+        let p = r.at(t);
+        let offset = p - self.point;
+        let mut rec = HitRecord {
+            p,
+            t,
+            material: self.material.clone(),
+            normal: Vec3::default(),
+            u: dot(offset, self.frame.u()),
+            v: dot(offset, self.frame.v()),
+            front_face: false,
+        };
+        rec.set_face_normal(r, normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn hits_everywhere_but_edge_on() {
+        let plane = Plane::new(
+            Point3::new(0., -1., 0.),
+            Vec3::new(0., 1., 0.),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        let r = Ray::new(Point3::new(100., 1., -50.), Vec3::new(1., -1., 0.));
+        let rec = plane.hit(r, 0., f32::INFINITY).unwrap();
+        assert!((rec.p - Point3::new(102., -1., -50.)).length() < 1e-4);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0., 1., 0.));
+        // The texture coordinates are a distance in the plane.
+        assert!(
+            ((rec.u * rec.u + rec.v * rec.v).sqrt() - (102f32 * 102. + 50. * 50.).sqrt()).abs()
+                < 1e-2
+        );
+
+        let below = Ray::new(Point3::new(0., -3., 0.), Vec3::new(0., 1., 1.));
+        assert!(!plane.hit(below, 0., f32::INFINITY).unwrap().front_face);
+        assert!(plane
+            .hit(
+                Ray::new(Point3::default(), Vec3::new(1., 0., 0.)),
+                0.,
+                f32::INFINITY
+            )
+            .is_none());
+        assert!(plane.bounding_box().is_none());
+    }
+}
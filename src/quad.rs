@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::*;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::rtweekend::random;
+use crate::vec3::*;
+
+/// The parallelogram with corner `q` spanned by the edges `u` and `v`,
+/// facing along `u × v`. Texture coordinates run from 0 to 1 along each
+/// edge.
+#[derive(Clone)]
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    /// `u × v` scaled by its inverse squared length, for projecting points
+    /// onto the edges.
+    w: Vec3,
+    normal: Vec3,
+    area: f32,
+    material: Arc<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Self {
+        let n = cross(u, v);
+        Self {
+            q,
+            u,
+            v,
+            w: n / n.length_squared(),
+            normal: unit(n),
+            area: n.length(),
+            material,
+        }
+    }
+
+    /// The rectangle `[x0, x1] × [y0, y1]` at `z = k`, facing +z.
+    pub fn xy(x0: f32, x1: f32, y0: f32, y1: f32, k: f32, material: Arc<dyn Material>) -> Self {
+        Self::new(
+            Point3::new(x0, y0, k),
+            Vec3::new(x1 - x0, 0., 0.),
+            Vec3::new(0., y1 - y0, 0.),
+            material,
+        )
+    }
+
+    /// The rectangle `[x0, x1] × [z0, z1]` at `y = k`, facing +y.
+    pub fn xz(x0: f32, x1: f32, z0: f32, z1: f32, k: f32, material: Arc<dyn Material>) -> Self {
+        Self::new(
+            Point3::new(x0, k, z0),
+            Vec3::new(0., 0., z1 - z0),
+            Vec3::new(x1 - x0, 0., 0.),
+            material,
+        )
+    }
+
+    /// The rectangle `[y0, y1] × [z0, z1]` at `x = k`, facing +x.
+    pub fn yz(y0: f32, y1: f32, z0: f32, z1: f32, k: f32, material: Arc<dyn Material>) -> Self {
+        Self::new(
+            Point3::new(k, y0, z0),
+            Vec3::new(0., y1 - y0, 0.),
+            Vec3::new(0., 0., z1 - z0),
+            material,
+        )
+    }
+
+    /// The ray parameter and edge coordinates `(alpha, beta)` where `r`
+    /// crosses the quad.
+    fn intersect(&self, r: Ray, t_min: f32, t_max: f32) -> Option<(f32, f32, f32)> {
+        let denom = dot(self.normal, r.direction());
+        // Parallel to the plane.
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = dot(self.normal, self.q - r.origin()) / denom;
+        if !(t_min..=t_max).contains(&t) {
+            return None;
+        }
+        let planar = r.at(t) - self.q;
+        let alpha = dot(self.w, cross(planar, self.v));
+        let beta = dot(self.w, cross(self.u, planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+        Some((t, alpha, beta))
+    }
+
+    fn record(&self, r: Ray, t: f32, alpha: f32, beta: f32) -> HitRecord {
+        let mut rec = HitRecord {
+            p: r.at(t),
+            t,
+            material: self.material.clone(),
+            normal: Vec3::default(),
+            u: alpha,
+            v: beta,
+            front_face: false,
+        };
+        rec.set_face_normal(r, self.normal);
+        rec
+    }
+}
+
+/// Solid angle density of picking `to_point` (from the viewer) uniformly
+/// on a flat shape of `area` facing along `normal`.
+pub(crate) fn area_pdf(to_point: Vec3, normal: Vec3, area: f32) -> f32 {
+    let cosine = dot(normal, unit(to_point)).abs();
+    if area == 0.0 || cosine == 0.0 {
+        return 0.0;
+    }
+    to_point.length_squared() / (cosine * area)
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let (t, alpha, beta) = self.intersect(r, t_min, t_max)?;
+        Some(self.record(r, t, alpha, beta))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let corners = [self.q + self.u, self.q + self.v, self.q + self.u + self.v];
+        let (mut min, mut max) = (self.q, self.q);
+        for c in corners {
+            min = Point3::new(min.x().min(c.x()), min.y().min(c.y()), min.z().min(c.z()));
+            max = Point3::new(max.x().max(c.x()), max.y().max(c.y()), max.z().max(c.z()));
+        }
+        // Pad so axis-aligned quads still have a volume.
+        let pad = Vec3::new(1e-4, 1e-4, 1e-4);
+        Some(Aabb::new(min - pad, max + pad))
+    }
+
+    fn sample_towards(&self, origin: Point3) -> Option<(HitRecord, f32)> {
+        let (alpha, beta) = (random::<f32>(), random::<f32>());
+        let p = self.q + alpha * self.u + beta * self.v;
+        let pdf = area_pdf(p - origin, self.normal, self.area);
+        if pdf == 0.0 || !pdf.is_finite() {
+            return None;
+        }
+        Some((
+            self.record(Ray::new(origin, p - origin), 1.0, alpha, beta),
+            pdf,
+        ))
+    }
+
+    fn pdf_towards(&self, origin: Point3, direction: Vec3) -> f32 {
+        match self.intersect(Ray::new(origin, direction), 0.0, f32::INFINITY) {
+            Some((t, _, _)) => area_pdf(t * direction, self.normal, self.area),
+            None => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn hits_inside_the_edges() {
+        let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        // Slanted, so neither edge lines up with an axis.
+        let quad = Quad::new(
+            Point3::new(0., 0., -2.),
+            Vec3::new(2., 0., 0.),
+            Vec3::new(0., 1., -1.),
+            material.clone(),
+        );
+        let down = |x, y| Ray::new(Point3::new(x, y, 5.), Vec3::new(0., 0., -1.));
+        let rec = quad.hit(down(0.5, 0.5), 0., f32::INFINITY).unwrap();
+        assert!((rec.p - Point3::new(0.5, 0.5, -2.5)).length() < 1e-5);
+        assert!((rec.u - 0.25).abs() < 1e-5 && (rec.v - 0.5).abs() < 1e-5);
+        assert!(rec.front_face);
+        assert!((rec.normal - unit(Vec3::new(0., 1., 1.))).length() < 1e-5);
+        assert!(quad.hit(down(2.5, 0.5), 0., f32::INFINITY).is_none());
+        assert!(quad.hit(down(0.5, 1.5), 0., f32::INFINITY).is_none());
+
+        let up = Ray::new(Point3::new(0.5, 0.5, -5.), Vec3::new(0., 0., 1.));
+        let rec = quad.hit(up, 0., f32::INFINITY).unwrap();
+        assert!(!rec.front_face);
+        assert!((rec.normal + unit(Vec3::new(0., 1., 1.))).length() < 1e-5);
+
+        let floor = Quad::xz(-1., 1., -1., 1., 0., material);
+        assert!(floor.hit(down(0., 0.), 0., f32::INFINITY).is_none());
+        let from_above = Ray::new(Point3::new(0., 1., 0.), Vec3::new(0., -1., 0.));
+        let rec = floor.hit(from_above, 0., f32::INFINITY).unwrap();
+        assert_eq!(rec.normal, Vec3::new(0., 1., 0.));
+        let bbox = floor.bounding_box().unwrap();
+        assert!(bbox.min().y() < 0. && bbox.max().y() > 0.);
+    }
+
+    #[test]
+    fn area_sampling() {
+        crate::rtweekend::seed_rng(4);
+        let quad = Quad::xy(
+            -1.,
+            1.,
+            -1.,
+            1.,
+            -2.,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        let origin = Point3::default();
+        // Averaging 1 / pdf over the samples estimates the solid angle,
+        // 4 asin(1 / 5) for a square of side 2 at distance 2.
+        let n = 20000;
+        let mut solid_angle = 0.0;
+        for _ in 0..n {
+            let (rec, pdf) = quad.sample_towards(origin).unwrap();
+            assert!((rec.p.z() + 2.).abs() < 1e-5);
+            assert!((quad.pdf_towards(origin, rec.p - origin) - pdf).abs() < 1e-3 * pdf);
+            solid_angle += 1. / pdf / n as f32;
+        }
+        assert!((solid_angle - 4. * (1f32 / 5.).asin()).abs() < 0.01);
+        assert_eq!(quad.pdf_towards(origin, Vec3::new(0., 0., 1.)), 0.);
+    }
+}
@@ -1,14 +1,27 @@
 use crate::vec3::*;
 
+/// A half-line `origin + t * direction`; `direction` is not normalised.
+/// `time` is the instant within the shutter interval the ray samples, which
+/// moving objects use to place themselves.
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct Ray {
     origin: Vec3,
     direction: Vec3,
+    time: f32,
 }
 
 impl Ray {
+    /// A ray at time zero.
     pub fn new(origin: Vec3, direction: Vec3) -> Self {
-        Self { origin, direction }
+        Self::with_time(origin, direction, 0.0)
+    }
+
+    pub fn with_time(origin: Vec3, direction: Vec3, time: f32) -> Self {
+        Self {
+            origin,
+            direction,
+            time,
+        }
     }
 
     pub fn origin(&self) -> Vec3 {
@@ -19,6 +32,10 @@ impl Ray {
         self.direction
     }
 
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
     pub fn at(&self, t: f32) -> Vec3 {
         self.origin + t * self.direction
     }
@@ -1,54 +1,214 @@
-use std::f32::INFINITY;
-
-use crate::hittable::Hittable;
+use crate::background::Background;
+use crate::hittable::{HitRecord, Hittable};
+use crate::hittable_list::HittableList;
 use crate::ray::Ray;
-use crate::vec3::{unit, Color};
+use crate::rtweekend::random;
+use crate::vec3::{Color, Point3, Vec3};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-pub fn ray_color(rays: Vec<Ray>, world: &impl Hittable, depth: i32) -> Vec<Color> {
+pub fn ray_color(
+    rays: Vec<Ray>,
+    world: &impl Hittable,
+    lights: &HittableList,
+    background: &dyn Background,
+    depth: i32,
+) -> Vec<Color> {
     rays.into_par_iter()
-        .map(|r| {
+        .map(|r| ray_color_iterative(r, world, lights, background, depth))
+        .collect()
+}
+
+/// Veach's power heuristic (with exponent two) weighting a sample taken
+/// with density `f` against another strategy with density `g`.
+fn power_heuristic(f: f32, g: f32) -> f32 {
+    let (f2, g2) = (f * f, g * g);
+    if f2 + g2 > 0.0 {
+        f2 / (f2 + g2)
+    } else {
+        0.0
+    }
+}
+
+/// What next-event estimation aims at: the emitting objects in `objects`
+/// and, if it can be sampled, the background, each picked with a fixed
+/// probability.
+struct Lights<'a> {
+    objects: &'a HittableList,
+    background: &'a dyn Background,
+    object_share: f32,
+    background_share: f32,
+}
+
+impl<'a> Lights<'a> {
+    fn new(objects: &'a HittableList, background: &'a dyn Background) -> Self {
+        let object_share = match (objects.is_empty(), background.is_light()) {
+            (true, _) => 0.0,
+            (false, true) => 0.5,
+            (false, false) => 1.0,
+        };
+        let background_share = if background.is_light() {
+            1.0 - object_share
+        } else {
+            0.0
+        };
+        Self {
+            objects,
+            background,
+            object_share,
+            background_share,
+        }
+    }
+
+    /// Density of light sampling from `origin` picking the first object hit
+    /// along `direction`.
+    fn object_pdf(&self, origin: Point3, direction: Vec3) -> f32 {
+        if self.object_share == 0.0 {
+            return 0.0;
+        }
+        self.object_share * self.objects.pdf_towards(origin, direction)
+    }
+
+    /// Density of light sampling picking the background along `direction`.
+    fn background_pdf(&self, direction: Vec3) -> f32 {
+        if self.background_share == 0.0 {
+            return 0.0;
+        }
+        self.background_share * self.background.pdf(direction)
+    }
+
+    /// Light reaching `rec` straight from one sampled light and scattered
+    /// back along `r_in`, weighted against the material finding it by itself.
+    fn direct(&self, world: &impl Hittable, r_in: Ray, rec: &HitRecord) -> Color {
+        let (direction, distance, emitted, light_pdf) = if random::<f32>() < self.object_share {
+            let Some((light, pdf)) = self.objects.sample_towards(rec.p) else {
+                return Color::default();
+            };
+            let to_light = light.p - rec.p;
+            let distance = to_light.length();
+            let emitted = light.material.emitted(&light);
+            (
+                to_light / distance,
+                distance,
+                emitted,
+                self.object_share * pdf,
+            )
+        } else if self.background_share > 0.0 {
+            let Some((direction, pdf)) = self.background.sample() else {
+                return Color::default();
+            };
+            let emitted = self.background.color(direction);
+            (
+                direction,
+                f32::INFINITY,
+                emitted,
+                self.background_share * pdf,
+            )
+        } else {
+            return Color::default();
+        };
+
+        let f = rec.material.eval(r_in, rec, direction);
+        if light_pdf <= 0.0 || f == Color::default() || emitted == Color::default() {
+            return Color::default();
+        }
+        // Stop just short of the light itself.
+        let transmittance = world.transmittance(
+            Ray::with_time(rec.p, direction, r_in.time()),
+            0.001,
+            distance - 0.001,
+        );
+        if transmittance == 0.0 {
+            return Color::default();
+        }
+        let material_pdf = rec.material.pdf(r_in, rec, direction);
+        f * emitted * (transmittance * power_heuristic(light_pdf, material_pdf) / light_pdf)
+    }
+}
+
+/// Trace a single ray with the bounce loop used by `ray_color`.
+///
+/// Non-specular bounces also sample `lights` and the background directly,
+/// with a shadow ray, and combine that with the light their scattered rays
+/// find by multiple importance sampling.
+pub fn ray_color_iterative(
+    r: Ray,
+    world: &impl Hittable,
+    lights: &HittableList,
+    background: &dyn Background,
+    depth: i32,
+) -> Color {
+    // If we've exceeded the ray bounce limit, no more light is gathered.
+    if depth <= 0 {
+        if cfg!(debug_assertions) {
+            // in debug mode, when exceed the bounce limit, return RED for clearer visualization
+            return Color::new(1., 0., 0.);
+        } else {
+            return Color::new(0., 0., 0.);
+        }
+    }
+
+    let lights = Lights::new(lights, background);
+    let mut radiance = Color::default();
+    let mut throughput = Color::new(1.0, 1.0, 1.0);
+    let mut remain_depth = depth;
+    let mut cur_ray = r;
+    // Where the ray was scattered from, and the density of its direction,
+    // if light sampling could have picked the same direction.
+    let mut last_bounce: Option<(Point3, f32)> = None;
+    while let Some(rec) = world.hit(cur_ray, 0.001, f32::INFINITY) {
+        let emitted = rec.material.emitted(&rec);
+        if emitted != Color::default() {
+            let weight = match last_bounce {
+                Some((origin, material_pdf)) => {
+                    power_heuristic(material_pdf, lights.object_pdf(origin, cur_ray.direction()))
+                }
+                None => 1.0,
+            };
+            radiance += throughput * emitted * weight;
+        }
+
+        if let Some(sample) = rec.material.sample(cur_ray, &rec) {
+            remain_depth -= 1;
             // If we've exceeded the ray bounce limit, no more light is gathered.
-            if depth <= 0 {
+            if remain_depth <= 0 {
                 if cfg!(debug_assertions) {
                     // in debug mode, when exceed the bounce limit, return RED for clearer visualization
-                    return Color::new(1., 0., 0.);
+                    return radiance + throughput * sample.attenuation * Color::new(1., 0., 0.);
                 } else {
-                    return Color::new(0., 0., 0.);
+                    return radiance;
                 }
             }
 
-            let mut final_color = Color::new(1.0, 1.0, 1.0);
-            let mut remain_depth = depth;
-            let mut cur_ray = r;
-            while let Some(rec) = world.hit(cur_ray, 0.001, INFINITY) {
-                if let Some((attenuation, scattered)) = rec.material.clone().scatter(cur_ray, rec) {
-                    final_color = final_color * attenuation;
-                    cur_ray = scattered;
-                    remain_depth -= 1;
-                    // If we've exceeded the ray bounce limit, no more light is gathered.
-                    if remain_depth <= 0 {
-                        if cfg!(debug_assertions) {
-                            // in debug mode, when exceed the bounce limit, return RED for clearer visualization
-                            return final_color * Color::new(1., 0., 0.);
-                        } else {
-                            return Color::default();
-                        }
-                    }
-                } else {
-                    return Color::default();
-                }
-            }
+            last_bounce = if sample.specular {
+                None
+            } else {
+                radiance += throughput * lights.direct(world, cur_ray, &rec);
+                Some((rec.p, sample.pdf))
+            };
+            throughput = throughput * sample.attenuation;
+            cur_ray = sample.ray;
+        } else {
+            return radiance;
+        }
+    }
 
-            let unit_direction = unit(cur_ray.direction());
-            let t = 0.5 * (unit_direction.y() + 1.0);
-            final_color * ((1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0))
-        })
-        .collect()
+    let direction = cur_ray.direction();
+    let weight = match last_bounce {
+        Some((_, material_pdf)) => power_heuristic(material_pdf, lights.background_pdf(direction)),
+        None => 1.0,
+    };
+    radiance + throughput * background.color(direction) * weight
 }
 
+/// Recursive path tracing without any light sampling; slow, but simple
+/// enough to check `ray_color_iterative` against.
 #[allow(dead_code)]
-pub fn ray_color_default(r: Ray, world: &impl Hittable, depth: i32) -> Color {
+pub fn ray_color_default(
+    r: Ray,
+    world: &impl Hittable,
+    background: &dyn Background,
+    depth: i32,
+) -> Color {
     // If we've exceeded the ray bounce limit, no more light is gathered.
     if depth <= 0 {
         if cfg!(debug_assertions) {
@@ -57,16 +217,16 @@ pub fn ray_color_default(r: Ray, world: &impl Hittable, depth: i32) -> Color {
             return Color::new(0., 0., 0.);
         }
     }
-    if let Some(rec) = world.hit(r, 0.001, INFINITY) {
-        if let Some((attenuation, scattered)) = rec.material.clone().scatter(r, rec) {
-            return attenuation * ray_color_default(scattered, world, depth - 1);
+    if let Some(rec) = world.hit(r, 0.001, f32::INFINITY) {
+        let emitted = rec.material.emitted(&rec);
+        if let Some(sample) = rec.material.sample(r, &rec) {
+            return emitted
+                + sample.attenuation * ray_color_default(sample.ray, world, background, depth - 1);
         }
-        return Color::default();
+        return emitted;
     }
 
-    let unit_direction = unit(r.direction());
-    let t = 0.5 * (unit_direction.y() + 1.0);
-    return (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0);
+    background.color(r.direction())
 }
 /*
 #[cfg(test)]
@@ -108,3 +268,86 @@ mod test {
     }
 }
 */
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::background::{LatLongMap, SolidBackground};
+    use crate::image::Image;
+    use crate::material::{DiffuseLight, Lambertian};
+    use crate::rtweekend::seed_rng;
+    use crate::sphere::Sphere;
+    use crate::triangle::Triangle;
+
+    #[test]
+    fn environment_sampling_is_unbiased() {
+        seed_rng(11);
+        // A convex diffuse object under a uniform sky reflects albedo times
+        // the sky, whichever way its bounces are sampled.
+        let mut image = Image::new(8, 4);
+        for y in 0..4 {
+            for x in 0..8 {
+                image.set(x, y, Color::new(1., 1., 1.));
+            }
+        }
+        let sky = LatLongMap::new(image).intensity(2.);
+        let sphere = Sphere::new(
+            Point3::new(0., 0., 0.),
+            1.,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.25, 0.1))),
+        );
+
+        let r = Ray::new(Point3::new(0., 5., 0.), Vec3::new(0., -1., 0.));
+        let n = 20000;
+        let mut sum = Color::default();
+        for _ in 0..n {
+            sum += ray_color_iterative(r, &sphere, &HittableList::new(), &sky, 4);
+        }
+        let mean = sum / n as f32;
+        assert!((mean - Color::new(1., 0.5, 0.2)).length() < 0.03);
+    }
+
+    #[test]
+    fn light_sampling_matches_brute_force() {
+        seed_rng(13);
+        // A lamp hanging over a diffuse floor. Seen from straight below, a
+        // sphere of radius r at distance d gives the floor an irradiance of
+        // pi * L * (r / d)^2, so the floor reflects albedo * L * (r / d)^2.
+        let floor = Arc::new(Triangle::new(
+            Point3::new(-100., 0., -100.),
+            Point3::new(100., 0., -100.),
+            Point3::new(0., 0., 200.),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        ));
+        let lamp = Arc::new(Sphere::new(
+            Point3::new(0., 4., 0.),
+            1.,
+            Arc::new(DiffuseLight::new(Color::new(10., 10., 10.))),
+        ));
+        let mut world = HittableList::new();
+        world.add(floor);
+        world.add(lamp.clone());
+        let mut lights = HittableList::new();
+        lights.add(lamp);
+        let black = SolidBackground::new(Color::default());
+
+        let r = Ray::new(Point3::new(0., 2., 0.), Vec3::new(0., -1., 0.));
+        let expected = 0.5 * 10. / 16.;
+        let n = 20000;
+        let (mut sampled, mut brute_force) = (Color::default(), Color::default());
+        let mut sampled_squares = 0.0;
+        for _ in 0..n {
+            let c = ray_color_iterative(r, &world, &lights, &black, 4);
+            sampled += c / n as f32;
+            sampled_squares += c.x() * c.x() / n as f32;
+            brute_force += ray_color_default(r, &world, &black, 4) / n as f32;
+        }
+        assert!((sampled - Color::new(expected, expected, expected)).length() < 0.01);
+        assert!((brute_force.x() - expected).abs() < 0.04);
+        // Light sampling is what makes the estimate converge this quickly.
+        let variance = sampled_squares - sampled.x() * sampled.x();
+        assert!(variance < 0.001);
+    }
+}
@@ -0,0 +1,299 @@
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+
+use crate::background::{Background, SkyGradient};
+use crate::camera::Camera;
+use crate::film::{Film, Tile};
+use crate::hittable::Hittable;
+use crate::hittable_list::HittableList;
+use crate::image::Image;
+use crate::ray_color::ray_color_iterative;
+use crate::rtweekend::{random, seed_rng};
+use crate::vec3::Color;
+
+/// Which integrator traces the rays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    #[default]
+    Cpu,
+    /// The batched integrator in the `gpu` module.
+    #[cfg(feature = "gpu")]
+    Gpu,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cpu" => Ok(Self::Cpu),
+            #[cfg(feature = "gpu")]
+            "gpu" => Ok(Self::Gpu),
+            #[cfg(not(feature = "gpu"))]
+            "gpu" => Err("the gpu backend needs a build with `--features gpu`".to_string()),
+            _ => Err(format!("unknown backend '{}' (expected cpu or gpu)", s)),
+        }
+    }
+}
+
+/// Image size and sampling parameters for `render`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderSettings {
+    pub aspect_ratio: f32,
+    pub image_width: i32,
+    pub image_height: i32,
+    pub samples_per_pixel: i32,
+    pub max_depth: i32,
+    pub backend: Backend,
+}
+
+impl RenderSettings {
+    /// Settings for an image of `image_width` pixels, its height following
+    /// from `aspect_ratio`.
+    pub fn new(
+        aspect_ratio: f32,
+        image_width: i32,
+        samples_per_pixel: i32,
+        max_depth: i32,
+    ) -> Self {
+        Self {
+            aspect_ratio,
+            image_width,
+            image_height: (image_width as f32 / aspect_ratio) as i32,
+            samples_per_pixel,
+            max_depth,
+            backend: Backend::default(),
+        }
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self::new(3.0 / 2.0, 600, 100, 50)
+    }
+}
+
+/// How far a render has got, as passed to the progress callback.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub tiles_done: usize,
+    pub tiles_total: usize,
+}
+
+/// Tiled renderer spreading the image over the rayon thread pool.
+///
+/// Every tile is traced with the iterative bounce loop and summed into a
+/// `Film`, which is averaged into an `Image` once all tiles are done.
+#[derive(Clone)]
+pub struct Renderer {
+    settings: RenderSettings,
+    tile_size: usize,
+    seed: Option<u64>,
+    lights: HittableList,
+    background: Arc<dyn Background>,
+}
+
+impl Renderer {
+    pub fn new(settings: RenderSettings) -> Self {
+        Self {
+            settings,
+            tile_size: 32,
+            seed: None,
+            lights: HittableList::new(),
+            background: Arc::new(SkyGradient::default()),
+        }
+    }
+
+    /// Edge length of the square tiles handed to each worker.
+    pub fn tile_size(mut self, tile_size: usize) -> Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+
+    /// Make the render reproducible: each tile reseeds its thread's
+    /// generator from `seed` and its index, independent of scheduling.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Emitting objects of the world to sample directly at every diffuse
+    /// bounce. Lights left out are still found, only by chance.
+    pub fn lights(mut self, lights: HittableList) -> Self {
+        self.lights = lights;
+        self
+    }
+
+    /// What rays leaving the scene see; the sky gradient by default.
+    pub fn background(mut self, background: Arc<dyn Background>) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn render(&self, world: &impl Hittable, camera: &Camera) -> Image {
+        self.render_with_progress(world, camera, |_| {})
+    }
+
+    /// Like `render`, calling `progress` from the worker threads after each tile.
+    pub fn render_with_progress(
+        &self,
+        world: &impl Hittable,
+        camera: &Camera,
+        progress: impl Fn(Progress) + Sync,
+    ) -> Image {
+        let width = self.settings.image_width as usize;
+        let height = self.settings.image_height as usize;
+        let mut film = Film::new(width, height);
+        let tiles = film.tiles(self.tile_size);
+        let tiles_total = tiles.len();
+        let tiles_done = AtomicUsize::new(0);
+
+        let rendered: Vec<(Tile, Vec<Color>)> = tiles
+            .into_par_iter()
+            .enumerate()
+            .map(|(index, tile)| {
+                if let Some(seed) = self.seed {
+                    seed_rng(seed.wrapping_add((index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)));
+                }
+                let sums = self.render_tile(world, camera, &tile);
+                progress(Progress {
+                    tiles_done: tiles_done.fetch_add(1, Ordering::Relaxed) + 1,
+                    tiles_total,
+                });
+                (tile, sums)
+            })
+            .collect();
+
+        for (tile, sums) in rendered.iter() {
+            film.add_tile(tile, sums, self.settings.samples_per_pixel as u32);
+        }
+        film.to_image()
+    }
+
+    fn render_tile(&self, world: &impl Hittable, camera: &Camera, tile: &Tile) -> Vec<Color> {
+        let RenderSettings {
+            image_width,
+            image_height,
+            samples_per_pixel,
+            max_depth,
+            backend,
+            ..
+        } = self.settings;
+
+        let mut sums = Vec::with_capacity(tile.width * tile.height);
+        for y in tile.y..tile.y + tile.height {
+            // Image rows run top to bottom, camera v bottom to top.
+            let j = image_height as usize - 1 - y;
+            for i in tile.x..tile.x + tile.width {
+                let rays = (0..samples_per_pixel).map(|_| {
+                    let u = (i as f32 + random::<f32>()) / (image_width - 1) as f32;
+                    let v = (j as f32 + random::<f32>()) / (image_height - 1) as f32;
+                    camera.get_ray(u, v)
+                });
+                let pixel_color = match backend {
+                    Backend::Cpu => rays
+                        .map(|r| {
+                            ray_color_iterative(
+                                r,
+                                world,
+                                &self.lights,
+                                &*self.background,
+                                max_depth,
+                            )
+                        })
+                        .fold(Color::default(), |acc, x| acc + x),
+                    #[cfg(feature = "gpu")]
+                    Backend::Gpu => crate::gpu::ray_color::ray_color(
+                        rays.collect(),
+                        world,
+                        &*self.background,
+                        max_depth,
+                    )
+                    .into_iter()
+                    .fold(Color::default(), |acc, x| acc + x),
+                };
+                sums.push(pixel_color);
+            }
+        }
+        sums
+    }
+}
+
+/// Render `world` as seen by `camera` with a default `Renderer`.
+pub fn render(world: &impl Hittable, camera: &Camera, settings: &RenderSettings) -> Image {
+    Renderer::new(*settings).render(world, camera)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::background::SolidBackground;
+    use crate::camera::CameraSettings;
+    use crate::hittable_list::HittableList;
+    use crate::material::DiffuseLight;
+    use crate::scene::test_scene_metal;
+    use crate::sphere::Sphere;
+    use crate::vec3::{Point3, Vec3};
+
+    #[test]
+    fn seeded_render_is_reproducible() {
+        let world = test_scene_metal();
+        let settings = RenderSettings::new(3.0 / 2.0, 45, 4, 8);
+        let camera = CameraSettings {
+            look_from: Point3::new(13., 2., 3.),
+            look_at: Point3::new(0., 0., 0.),
+            vup: Vec3::new(0., 1., 0.),
+            vfov: 20.,
+            aperture: 0.1,
+            focus_dist: 10.0,
+            shutter_open: 0.,
+            shutter_close: 0.,
+        }
+        .build(settings.aspect_ratio);
+
+        let calls = AtomicUsize::new(0);
+        let renderer = Renderer::new(settings).tile_size(7).seed(42);
+        let a = renderer.render_with_progress(&world, &camera, |progress| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            assert_eq!(progress.tiles_total, 35);
+        });
+        let b = renderer.render(&world, &camera);
+        assert_eq!(calls.into_inner(), 35);
+        assert_eq!((a.width(), a.height()), (45, 30));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn lights_are_seen_against_black() {
+        // The camera sits inside a glowing sphere.
+        let emit = Color::new(0.5, 1., 4.);
+        let mut world = HittableList::new();
+        world.add(Arc::new(Sphere::new(
+            Point3::new(0., 0., 0.),
+            10.,
+            Arc::new(DiffuseLight::new(emit)),
+        )));
+        let settings = RenderSettings::new(1.0, 4, 4, 8);
+        let camera = CameraSettings {
+            look_from: Point3::new(0., 0., 0.),
+            look_at: Point3::new(0., 0., -1.),
+            vup: Vec3::new(0., 1., 0.),
+            vfov: 90.,
+            aperture: 0.,
+            focus_dist: 1.,
+            shutter_open: 0.,
+            shutter_close: 0.,
+        }
+        .build(settings.aspect_ratio);
+
+        let renderer =
+            Renderer::new(settings).background(Arc::new(SolidBackground::new(Color::default())));
+        let image = renderer.render(&world, &camera);
+        assert!(image.pixels().iter().all(|&c| c == emit));
+        let empty = renderer.render(&HittableList::new(), &camera);
+        assert!(empty.pixels().iter().all(|&c| c == Color::default()));
+    }
+}
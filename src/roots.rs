@@ -0,0 +1,166 @@
+//! Real roots of low-degree polynomials, for intersecting analytic shapes.
+
+use std::f64::consts::PI;
+
+/// Real roots of `a t² + b t + c`, smallest first.
+///
+/// Uses the form that avoids cancellation between `b` and the square root
+/// of the discriminant.
+pub fn quadratic(a: f32, b: f32, c: f32) -> Option<(f32, f32)> {
+    if a == 0.0 {
+        if b == 0.0 {
+            return None;
+        }
+        let t = -c / b;
+        return Some((t, t));
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let (t0, t1) = if q == 0.0 {
+        // b and c are both zero: a double root at zero.
+        (0.0, 0.0)
+    } else {
+        (q / a, c / q)
+    };
+    Some(if t0 <= t1 { (t0, t1) } else { (t1, t0) })
+}
+
+/// Real roots of the monic cubic `t³ + a t² + b t + c`, in no particular
+/// order.
+fn cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    let q = (a * a - 3.0 * b) / 9.0;
+    let r = (2.0 * a * a * a - 9.0 * a * b + 27.0 * c) / 54.0;
+    let shift = a / 3.0;
+    if r * r < q * q * q {
+        // Three real roots, from the trigonometric form.
+        let theta = (r / (q * q * q).sqrt()).clamp(-1.0, 1.0).acos();
+        let scale = -2.0 * q.sqrt();
+        [0.0, 2.0 * PI, -2.0 * PI]
+            .iter()
+            .map(|offset| scale * ((theta + offset) / 3.0).cos() - shift)
+            .collect()
+    } else {
+        let big = -r.signum() * (r.abs() + (r * r - q * q * q).sqrt()).cbrt();
+        let small = if big == 0.0 { 0.0 } else { q / big };
+        vec![big + small - shift]
+    }
+}
+
+/// Real roots of `a t⁴ + b t³ + c t² + d t + e`, smallest first, found with
+/// Ferrari's method and polished by Newton's method.
+pub fn quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return Vec::new();
+    }
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+
+    // Depress to y⁴ + p y² + q y + r with t = y - b / 4.
+    let shift = b / 4.0;
+    let b2 = b * b;
+    let p = c - 3.0 * b2 / 8.0;
+    let q = d - b * c / 2.0 + b2 * b / 8.0;
+    let r = e - b * d / 4.0 + b2 * c / 16.0 - 3.0 * b2 * b2 / 256.0;
+
+    let mut roots = Vec::with_capacity(4);
+    let mut push_quadratic = |qa: f64, qb: f64, qc: f64| {
+        let discriminant = qb * qb - 4.0 * qa * qc;
+        if discriminant >= 0.0 {
+            let sqrt = discriminant.sqrt();
+            roots.push((-qb - sqrt) / (2.0 * qa));
+            roots.push((-qb + sqrt) / (2.0 * qa));
+        }
+    };
+    if q.abs() < 1e-12 {
+        // Biquadratic: a quadratic in y².
+        let discriminant = p * p - 4.0 * r;
+        if discriminant >= 0.0 {
+            let sqrt = discriminant.sqrt();
+            for z in [(-p - sqrt) / 2.0, (-p + sqrt) / 2.0] {
+                if z >= 0.0 {
+                    push_quadratic(1.0, 0.0, -z);
+                }
+            }
+        }
+    } else {
+        // Split into two quadratics around the largest root m of the
+        // resolvent 8m³ + 8p m² + (2p² - 8r) m - q², which is positive.
+        let m = cubic(p, p * p / 4.0 - r, -q * q / 8.0)
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if m > 0.0 {
+            let s = (2.0 * m).sqrt();
+            push_quadratic(1.0, s, p / 2.0 + m - q / (2.0 * s));
+            push_quadratic(1.0, -s, p / 2.0 + m + q / (2.0 * s));
+        }
+    }
+
+    let f = |t: f64| (((t + b) * t + c) * t + d) * t + e;
+    let df = |t: f64| ((4.0 * t + 3.0 * b) * t + 2.0 * c) * t + d;
+    let mut roots: Vec<f64> = roots
+        .into_iter()
+        .map(|y| {
+            let mut t = y - shift;
+            for _ in 0..2 {
+                let slope = df(t);
+                if slope != 0.0 {
+                    t -= f(t) / slope;
+                }
+            }
+            t
+        })
+        .collect();
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quadratic_roots() {
+        assert_eq!(quadratic(1., -3., 2.), Some((1., 2.)));
+        assert_eq!(quadratic(-1., 3., -2.), Some((1., 2.)));
+        assert_eq!(quadratic(1., 0., 1.), None);
+        assert_eq!(quadratic(0., 2., -4.), Some((2., 2.)));
+        assert_eq!(quadratic(1., 0., 0.), Some((0., 0.)));
+        // A tiny root next to a large one keeps its precision.
+        let (small, large) = quadratic(1., -1e4, 1.).unwrap();
+        assert!((small - 1e-4).abs() < 1e-9 && (large - 1e4).abs() < 1.);
+    }
+
+    #[test]
+    fn quartic_roots() {
+        let expand = |r: [f64; 4]| {
+            let [a, b, c, d] = r;
+            (
+                1.0,
+                -(a + b + c + d),
+                a * b + a * c + a * d + b * c + b * d + c * d,
+                -(a * b * c + a * b * d + a * c * d + b * c * d),
+                a * b * c * d,
+            )
+        };
+        for expected in [
+            [-3., -1., 2., 5.],
+            [0.5, 1.5, 2.5, 3.5],
+            [-2., -1., 1., 2.],
+            [1., 2., 3., 40.],
+        ] {
+            let (a, b, c, d, e) = expand(expected);
+            let roots = quartic(2. * a, 2. * b, 2. * c, 2. * d, 2. * e);
+            assert_eq!(roots.len(), 4, "{:?}", expected);
+            for (root, want) in roots.iter().zip(expected) {
+                assert!((root - want).abs() < 1e-6, "{:?}: {:?}", expected, roots);
+            }
+        }
+        // (t² + 1)(t - 1)(t - 2) has two real roots, t⁴ + 1 none.
+        let roots = quartic(1., -3., 3., -3., 2.);
+        assert_eq!(roots.len(), 2);
+        assert!((roots[0] - 1.).abs() < 1e-9 && (roots[1] - 2.).abs() < 1e-9);
+        assert!(quartic(1., 0., 0., 0., 1.).is_empty());
+    }
+}
@@ -1,5 +1,24 @@
+use std::cell::RefCell;
+
+use rand::distributions::{Distribution, Standard};
 use rand::prelude::*;
 
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Reseed the calling thread's generator, making its sequence reproducible.
+pub fn seed_rng(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+pub fn random<T>() -> T
+where
+    Standard: Distribution<T>,
+{
+    RNG.with(|rng| rng.borrow_mut().gen())
+}
+
 pub fn random_float(min: f32, max: f32) -> f32 {
-    thread_rng().gen_range(min..max)
+    RNG.with(|rng| rng.borrow_mut().gen_range(min..max))
 }
@@ -0,0 +1,148 @@
+//! Piecewise-constant distributions for importance sampling.
+
+/// A 1D distribution over [0, 1) proportional to a step function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution1D {
+    func: Vec<f32>,
+    cdf: Vec<f32>,
+    integral: f32,
+}
+
+impl Distribution1D {
+    /// `func` gives the (non-negative) height of each of the equally wide
+    /// steps. An all-zero function is sampled uniformly.
+    pub fn new(func: Vec<f32>) -> Self {
+        assert!(!func.is_empty(), "distribution over no values");
+        let n = func.len() as f32;
+        let mut cdf = Vec::with_capacity(func.len() + 1);
+        cdf.push(0.0);
+        for (i, &f) in func.iter().enumerate() {
+            cdf.push(cdf[i] + f.max(0.0) / n);
+        }
+        let integral = cdf[func.len()];
+        if integral > 0.0 {
+            cdf.iter_mut().for_each(|c| *c /= integral);
+        } else {
+            cdf.iter_mut()
+                .enumerate()
+                .for_each(|(i, c)| *c = i as f32 / n);
+        }
+        Self {
+            func,
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.func.is_empty()
+    }
+
+    /// Integral of the step function over [0, 1).
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    /// Map a uniform `u` in [0, 1) to `(x, pdf, step)`.
+    pub fn sample(&self, u: f32) -> (f32, f32, usize) {
+        // The last step whose cdf does not exceed u.
+        let step = self.cdf.partition_point(|&c| c <= u).clamp(1, self.len()) - 1;
+        let width = self.cdf[step + 1] - self.cdf[step];
+        let offset = if width > 0.0 {
+            ((u - self.cdf[step]) / width).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let x = ((step as f32 + offset) / self.len() as f32).min(1.0 - f32::EPSILON);
+        (x, self.pdf(x), step)
+    }
+
+    /// Density at `x` in [0, 1).
+    pub fn pdf(&self, x: f32) -> f32 {
+        let step = ((x * self.len() as f32) as usize).min(self.len() - 1);
+        if self.integral > 0.0 {
+            self.func[step].max(0.0) / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A 2D distribution over [0, 1)², sampled row first: the marginal picks
+/// `v`, that row's conditional distribution `u`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// `func` holds `width` values per row, rows in increasing `v`.
+    pub fn new(func: &[f32], width: usize) -> Self {
+        let conditional: Vec<Distribution1D> = func
+            .chunks(width)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|d| d.integral()).collect());
+        Self {
+            conditional,
+            marginal,
+        }
+    }
+
+    /// Map uniform `(u0, u1)` to a point `(u, v)` and its density.
+    pub fn sample(&self, u0: f32, u1: f32) -> ((f32, f32), f32) {
+        let (v, pdf_v, row) = self.marginal.sample(u1);
+        let (u, pdf_u, _) = self.conditional[row].sample(u0);
+        ((u, v), pdf_u * pdf_v)
+    }
+
+    pub fn pdf(&self, u: f32, v: f32) -> f32 {
+        let row = ((v * self.conditional.len() as f32) as usize).min(self.conditional.len() - 1);
+        self.marginal.pdf(v) * self.conditional[row].pdf(u)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn samples_follow_the_steps() {
+        let d = Distribution1D::new(vec![0., 1., 3.]);
+        assert!((d.integral() - 4. / 3.).abs() < 1e-6);
+        assert_eq!(d.sample(0.0).2, 1);
+        assert_eq!(d.sample(0.3).2, 2);
+        let (x, pdf, _) = d.sample(0.625);
+        assert!((x - 2.5 / 3.).abs() < 1e-6);
+        assert!((pdf - 2.25).abs() < 1e-5);
+        assert_eq!(d.pdf(0.1), 0.);
+
+        let flat = Distribution1D::new(vec![0., 0.]);
+        assert_eq!(flat.sample(0.75), (0.75, 1., 1));
+    }
+
+    #[test]
+    fn pdf_integrates_to_one() {
+        let func: Vec<f32> = (0..12).map(|i| ((i * 7) % 5) as f32).collect();
+        let d = Distribution2D::new(&func, 4);
+        // A grid lining up with the 4 x 3 steps.
+        let n = 48;
+        let mut total = 0.0;
+        for i in 0..n {
+            for j in 0..n {
+                let (u, v) = ((i as f32 + 0.5) / n as f32, (j as f32 + 0.5) / n as f32);
+                total += d.pdf(u, v) / (n * n) as f32;
+            }
+        }
+        assert!((total - 1.0).abs() < 1e-4);
+
+        let ((u, v), pdf) = d.sample(0.5, 0.5);
+        assert_eq!(pdf, d.pdf(u, v));
+        assert!(pdf > 0.0);
+    }
+}
@@ -1,9 +1,9 @@
 use crate::hittable_list::HittableList;
 use crate::material::*;
-use crate::rtweekend::random_float;
+use crate::quad::Quad;
+use crate::rtweekend::{random, random_float};
 use crate::sphere::Sphere;
-use crate::vec3::{Color, Point3};
-use rand::random;
+use crate::vec3::{Color, Point3, Vec3};
 use std::sync::Arc;
 
 pub fn test_scene() -> HittableList {
@@ -132,3 +132,63 @@ pub fn random_scene() -> HittableList {
 
     world
 }
+
+/// The Cornell box with a glass and a diffuse sphere, lit only by the
+/// ceiling light, which also comes back on its own as the box's lights.
+/// Meant to be rendered against a black background, seen from
+/// (278, 278, -800).
+pub fn cornell_box() -> (HittableList, HittableList) {
+    let mut world = HittableList::new();
+    let mut lights = HittableList::new();
+
+    let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+    let light = Arc::new(DiffuseLight::new(Color::new(15., 15., 15.)));
+
+    let x = Vec3::new(555., 0., 0.);
+    let y = Vec3::new(0., 555., 0.);
+    let z = Vec3::new(0., 0., 555.);
+    world.add(Arc::new(Quad::new(Point3::new(555., 0., 0.), y, z, green)));
+    world.add(Arc::new(Quad::new(Point3::new(0., 0., 0.), y, z, red)));
+    lights.add(Arc::new(Quad::new(
+        Point3::new(343., 554., 332.),
+        Vec3::new(-130., 0., 0.),
+        Vec3::new(0., 0., -105.),
+        light,
+    )));
+    for light in lights.objects() {
+        world.add(light.clone());
+    }
+    world.add(Arc::new(Quad::new(
+        Point3::new(0., 0., 0.),
+        x,
+        z,
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(555., 555., 555.),
+        -x,
+        -z,
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(0., 0., 555.),
+        x,
+        y,
+        white.clone(),
+    )));
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(190., 90., 190.),
+        90.,
+        Arc::new(Dielectric::new(1.5)),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(370., 120., 370.),
+        120.,
+        white,
+    )));
+
+    (world, lights)
+}
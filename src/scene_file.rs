@@ -0,0 +1,1836 @@
+//! JSON scene descriptions: image settings, camera, background, a table of
+//! named materials and the objects using them. The format is described in
+//! `docs/scene-format.md`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde_json::{Map, Value};
+
+use crate::background::*;
+use crate::camera::CameraSettings;
+use crate::cone::Cone;
+use crate::constant_medium::ConstantMedium;
+use crate::csg::{Csg, CsgOp};
+use crate::cuboid::Cuboid;
+use crate::cylinder::Cylinder;
+use crate::density_grid::{read_grid, DensityGrid};
+use crate::disk::Disk;
+use crate::heightfield::Heightfield;
+use crate::heterogeneous_medium::HeterogeneousMedium;
+use crate::hittable::Hittable;
+use crate::hittable_list::HittableList;
+use crate::input::{load_image, ImageError};
+use crate::instance::Instance;
+use crate::material::*;
+use crate::moving_sphere::MovingSphere;
+use crate::obj::{load_obj, ObjError};
+use crate::plane::Plane;
+use crate::quad::Quad;
+use crate::render::RenderSettings;
+use crate::sdf::{Mandelbulb, RoundedBox, Sdf, SdfSphere, SmoothUnion};
+use crate::sdf_object::SdfObject;
+use crate::sphere::Sphere;
+use crate::texture::*;
+use crate::torus::Torus;
+use crate::transform::Transform;
+use crate::triangle::Triangle;
+use crate::vec3::*;
+
+pub struct SceneDescription {
+    pub settings: RenderSettings,
+    pub camera: CameraSettings,
+    pub background: Arc<dyn Background>,
+    pub world: HittableList,
+    /// The emitting objects of `world`.
+    pub lights: HittableList,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io {
+        file: String,
+        source: std::io::Error,
+    },
+    Syntax {
+        file: String,
+        source: serde_json::Error,
+    },
+    Invalid {
+        file: String,
+        key: String,
+        message: String,
+    },
+    Obj(ObjError),
+    Image(ImageError),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { file, source } => write!(f, "{}: {}", file, source),
+            Self::Syntax { file, source } => write!(f, "{}: {}", file, source),
+            Self::Invalid { file, key, message } => write!(f, "{}: {}: {}", file, key, message),
+            Self::Obj(err) => err.fmt(f),
+            Self::Image(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Syntax { source, .. } => Some(source),
+            Self::Invalid { .. } => None,
+            Self::Obj(err) => Some(err),
+            Self::Image(err) => Some(err),
+        }
+    }
+}
+
+impl From<ObjError> for SceneError {
+    fn from(err: ObjError) -> Self {
+        Self::Obj(err)
+    }
+}
+
+impl From<ImageError> for SceneError {
+    fn from(err: ImageError) -> Self {
+        Self::Image(err)
+    }
+}
+
+/// Load a scene file; relative paths inside it are resolved against its directory.
+pub fn load_scene(path: &Path) -> Result<SceneDescription, SceneError> {
+    let file = path.display().to_string();
+    let source = std::fs::read_to_string(path).map_err(|source| SceneError::Io {
+        file: file.clone(),
+        source,
+    })?;
+    parse_scene(
+        &source,
+        &file,
+        path.parent().unwrap_or_else(|| Path::new("")),
+    )
+}
+
+/// Parse the text of a scene file; `file` names it in errors and
+/// `base_dir` is where its relative paths start.
+pub fn parse_scene(
+    source: &str,
+    file: &str,
+    base_dir: &Path,
+) -> Result<SceneDescription, SceneError> {
+    let value: Value = serde_json::from_str(source).map_err(|source| SceneError::Syntax {
+        file: file.to_string(),
+        source,
+    })?;
+    let root = Node {
+        value: &value,
+        key: String::new(),
+        file,
+    };
+    root.check_keys(&["image", "camera", "background", "materials", "objects"])?;
+
+    let settings = match root.get("image")? {
+        Some(node) => parse_image(&node)?,
+        None => RenderSettings::default(),
+    };
+    let camera = parse_camera(&root.field("camera")?)?;
+    let background = match root.get("background")? {
+        Some(node) => parse_background(&node, base_dir)?,
+        None => Arc::new(SkyGradient::default()),
+    };
+
+    let mut materials = HashMap::new();
+    let mut light_materials = HashSet::new();
+    if let Some(node) = root.get("materials")? {
+        for (name, material) in node.entries()? {
+            if material.field("type")?.str()? == "diffuse_light" {
+                light_materials.insert(name.clone());
+            }
+            materials.insert(name, parse_material(&material, base_dir)?);
+        }
+    }
+
+    let mut world = HittableList::new();
+    let mut lights = HittableList::new();
+    let mut meshes = HashMap::new();
+    for object in root.field("objects")?.items()? {
+        parse_object(&object, &materials, base_dir, &mut meshes, &mut world)?;
+        if let Some(name) = object.get("material")? {
+            if light_materials.contains(name.str()?) {
+                lights.add(world.objects().last().unwrap().clone());
+            }
+        }
+    }
+
+    Ok(SceneDescription {
+        settings,
+        camera,
+        background,
+        world,
+        lights,
+    })
+}
+
+/// Image size, samples per pixel and bounce depth.
+fn parse_image(node: &Node) -> Result<RenderSettings, SceneError> {
+    node.check_keys(&["width", "aspect_ratio", "samples_per_pixel", "max_depth"])?;
+    let default = RenderSettings::default();
+    let aspect_ratio = node
+        .opt_positive("aspect_ratio")?
+        .unwrap_or(default.aspect_ratio);
+    let width = node.opt_count("width")?.unwrap_or(default.image_width);
+    let samples_per_pixel = node
+        .opt_count("samples_per_pixel")?
+        .unwrap_or(default.samples_per_pixel);
+    let max_depth = node.opt_count("max_depth")?.unwrap_or(default.max_depth);
+    let settings = RenderSettings::new(aspect_ratio, width, samples_per_pixel, max_depth);
+    if settings.image_height < 1 {
+        return Err(node.error("width / aspect_ratio gives an image less than one pixel high"));
+    }
+    Ok(settings)
+}
+
+/// Camera placement, lens and shutter.
+fn parse_camera(node: &Node) -> Result<CameraSettings, SceneError> {
+    node.check_keys(&[
+        "look_from",
+        "look_at",
+        "vup",
+        "vfov",
+        "aperture",
+        "focus_dist",
+        "shutter_open",
+        "shutter_close",
+    ])?;
+    let look_from = node.field("look_from")?.vec3()?;
+    let look_at = node.field("look_at")?.vec3()?;
+    let vup = match node.get("vup")? {
+        Some(vup) => vup.vec3()?,
+        None => Vec3::new(0., 1., 0.),
+    };
+    let vfov = node.field("vfov")?.f32()?;
+    if !(vfov > 0.0 && vfov < 180.0) {
+        return Err(node
+            .field("vfov")?
+            .error("must be between 0 and 180 degrees"));
+    }
+    let aperture = match node.get("aperture")? {
+        Some(aperture) => aperture.non_negative()?,
+        None => 0.0,
+    };
+    let focus_dist = node
+        .opt_positive("focus_dist")?
+        .unwrap_or_else(|| (look_from - look_at).length());
+    let shutter_open = match node.get("shutter_open")? {
+        Some(open) => open.f32()?,
+        None => 0.0,
+    };
+    let shutter_close = match node.get("shutter_close")? {
+        Some(close) => close.f32()?,
+        None => shutter_open,
+    };
+    if shutter_close < shutter_open {
+        return Err(node
+            .field("shutter_close")?
+            .error("must not come before shutter_open"));
+    }
+    if (look_from - look_at).near_zero() {
+        return Err(node.field("look_at")?.error("must differ from look_from"));
+    }
+    if cross(vup, look_from - look_at).near_zero() {
+        return Err(node.error("vup must not be parallel to the view direction"));
+    }
+    Ok(CameraSettings {
+        look_from,
+        look_at,
+        vup,
+        vfov,
+        aperture,
+        focus_dist,
+        shutter_open,
+        shutter_close,
+    })
+}
+
+/// A colour, a `gradient` or an environment `image`.
+fn parse_background(node: &Node, base_dir: &Path) -> Result<Arc<dyn Background>, SceneError> {
+    if node.value.is_array() {
+        return Ok(Arc::new(SolidBackground::new(node.color()?)));
+    }
+    let kind = node.field("type")?;
+    Ok(match kind.str()? {
+        "gradient" => {
+            node.check_keys(&["type", "bottom", "top"])?;
+            Arc::new(SkyGradient::new(
+                node.field("bottom")?.color()?,
+                node.field("top")?.color()?,
+            ))
+        }
+        "image" => {
+            node.check_keys(&["type", "path", "rotation", "intensity"])?;
+            let image = load_image(&base_dir.join(node.field("path")?.str()?))?;
+            let rotation = match node.get("rotation")? {
+                Some(rotation) => rotation.f32()?,
+                None => 0.0,
+            };
+            let intensity = match node.get("intensity")? {
+                Some(intensity) => intensity.non_negative()?,
+                None => 1.0,
+            };
+            Arc::new(
+                LatLongMap::new(image)
+                    .rotation(rotation)
+                    .intensity(intensity),
+            )
+        }
+        other => {
+            return Err(kind.error(format!(
+                "unknown background type '{}' (expected gradient or image)",
+                other
+            )))
+        }
+    })
+}
+
+/// The material `type`s, for error messages.
+const MATERIAL_TYPES: &[&str] = &[
+    "lambertian",
+    "isotropic",
+    "henyey_greenstein",
+    "metal",
+    "dielectric",
+    "diffuse_light",
+];
+
+/// One entry of the material table.
+fn parse_material(node: &Node, base_dir: &Path) -> Result<Arc<dyn Material>, SceneError> {
+    let kind = node.field("type")?;
+    Ok(match kind.str()? {
+        "lambertian" => {
+            node.check_keys(&["type", "albedo"])?;
+            Arc::new(Lambertian::textured(parse_texture(
+                &node.field("albedo")?,
+                base_dir,
+            )?))
+        }
+        "isotropic" => {
+            node.check_keys(&["type", "albedo"])?;
+            Arc::new(Isotropic::textured(parse_texture(
+                &node.field("albedo")?,
+                base_dir,
+            )?))
+        }
+        "henyey_greenstein" => {
+            node.check_keys(&["type", "albedo", "g"])?;
+            let g = node.field("g")?;
+            let albedo = parse_texture(&node.field("albedo")?, base_dir)?;
+            match g.f32()? {
+                g if g > -1.0 && g < 1.0 => Arc::new(HenyeyGreenstein::textured(albedo, g)),
+                _ => return Err(g.error("must lie strictly between -1 and 1")),
+            }
+        }
+        "metal" => {
+            node.check_keys(&["type", "albedo", "fuzz"])?;
+            let fuzz = match node.get("fuzz")? {
+                Some(fuzz) => fuzz.non_negative()?,
+                None => 0.0,
+            };
+            let albedo = parse_texture(&node.field("albedo")?, base_dir)?;
+            Arc::new(Metal::textured(albedo, fuzz))
+        }
+        "dielectric" => {
+            node.check_keys(&["type", "ir"])?;
+            Arc::new(Dielectric::new(node.field("ir")?.positive()?))
+        }
+        "diffuse_light" => {
+            node.check_keys(&["type", "emit"])?;
+            Arc::new(DiffuseLight::new(node.field("emit")?.color()?))
+        }
+        other => {
+            return Err(kind.error(format!(
+                "unknown material type '{}' (expected {})",
+                other,
+                alternatives(MATERIAL_TYPES)
+            )))
+        }
+    })
+}
+
+/// An `albedo`: a colour or a `checker`, `image` or procedural texture.
+fn parse_texture(node: &Node, base_dir: &Path) -> Result<Arc<dyn Texture>, SceneError> {
+    if node.value.is_array() {
+        return Ok(Arc::new(SolidColor::new(node.color()?)));
+    }
+    let kind = node.field("type")?;
+    Ok(match kind.str()? {
+        "checker" => {
+            node.check_keys(&["type", "scale", "even", "odd", "space"])?;
+            let scale = node.field("scale")?.positive()?;
+            let even = parse_texture(&node.field("even")?, base_dir)?;
+            let odd = parse_texture(&node.field("odd")?, base_dir)?;
+            let space = match node.get("space")? {
+                Some(space) => match space.str()? {
+                    "solid" => CheckerSpace::Solid,
+                    "uv" => CheckerSpace::Uv,
+                    other => {
+                        return Err(space.error(format!(
+                            "unknown checker space '{}' (expected solid or uv)",
+                            other
+                        )))
+                    }
+                },
+                None => CheckerSpace::Solid,
+            };
+            match space {
+                CheckerSpace::Solid => Arc::new(Checker::new(scale, even, odd)),
+                CheckerSpace::Uv => Arc::new(Checker::uv(scale, even, odd)),
+            }
+        }
+        "image" => {
+            node.check_keys(&["type", "path", "wrap"])?;
+            let wrap = match node.get("wrap")? {
+                Some(wrap) => match wrap.str()? {
+                    "repeat" => WrapMode::Repeat,
+                    "mirror" => WrapMode::Mirror,
+                    "clamp" => WrapMode::Clamp,
+                    other => {
+                        return Err(wrap.error(format!(
+                            "unknown wrap mode '{}' (expected repeat, mirror or clamp)",
+                            other
+                        )))
+                    }
+                },
+                None => WrapMode::default(),
+            };
+            let image = load_image(&base_dir.join(node.field("path")?.str()?))?;
+            Arc::new(ImageTexture::new(image).wrap(wrap))
+        }
+        name @ ("marble" | "wood" | "clouds") => {
+            node.check_keys(&["type", "seed", "scale", "colors"])?;
+            let pattern = match name {
+                "marble" => NoisePattern::Marble,
+                "wood" => NoisePattern::Wood,
+                _ => NoisePattern::Clouds,
+            };
+            let seed = match node.get("seed")? {
+                Some(seed) => seed.u64()?,
+                None => 0,
+            };
+            let mut texture = NoiseTexture::new(pattern, seed);
+            if let Some(scale) = node.opt_positive("scale")? {
+                texture = texture.scale(scale);
+            }
+            if let Some(colors) = node.get("colors")? {
+                match &colors.items()?[..] {
+                    [from, to] => texture = texture.colors(from.color()?, to.color()?),
+                    items => {
+                        return Err(colors.error(format!("expected 2 colors, got {}", items.len())))
+                    }
+                }
+            }
+            Arc::new(texture)
+        }
+        other => {
+            return Err(kind.error(format!(
+                "unknown texture type '{}' (expected checker, image, marble, wood or clouds)",
+                other
+            )))
+        }
+    })
+}
+
+/// The object `type`s, for error messages.
+const OBJECT_TYPES: &[&str] = &[
+    "sphere",
+    "moving_sphere",
+    "quad",
+    "box",
+    "disk",
+    "plane",
+    "cylinder",
+    "cone",
+    "torus",
+    "csg",
+    "constant_medium",
+    "heterogeneous_medium",
+    "sdf",
+    "heightfield",
+    "triangle",
+    "obj",
+];
+
+/// One entry of the object list, added to `world`; `meshes` holds the OBJ
+/// files loaded so far.
+fn parse_object(
+    node: &Node,
+    materials: &HashMap<String, Arc<dyn Material>>,
+    base_dir: &Path,
+    meshes: &mut HashMap<PathBuf, HittableList>,
+    world: &mut HittableList,
+) -> Result<(), SceneError> {
+    let material = |node: &Node| -> Result<Arc<dyn Material>, SceneError> {
+        let name = node.field("material")?;
+        materials
+            .get(name.str()?)
+            .cloned()
+            .ok_or_else(|| name.error(format!("unknown material '{}'", name.str().unwrap())))
+    };
+    let direction = |node: &Node| -> Result<Vec3, SceneError> {
+        let v = node.vec3()?;
+        if v.near_zero() {
+            return Err(node.error("must not be zero"));
+        }
+        Ok(v)
+    };
+    let corners = |node: &Node| -> Result<(Point3, Point3), SceneError> {
+        let corners = node.field("corners")?;
+        let c = corners
+            .items()?
+            .iter()
+            .map(|c| c.vec3())
+            .collect::<Result<Vec<_>, _>>()?;
+        match c[..] {
+            [a, b] => Ok((a, b)),
+            _ => Err(corners.error(format!("expected 2 corners, got {}", c.len()))),
+        }
+    };
+    let capped = |node: &Node| -> Result<bool, SceneError> {
+        match node.get("capped")? {
+            Some(capped) => capped.bool(),
+            None => Ok(true),
+        }
+    };
+
+    let mut parsed = HittableList::new();
+    let kind = node.field("type")?;
+    match kind.str()? {
+        "sphere" => {
+            node.check_keys(&["type", "center", "radius", "material", "transform"])?;
+            parsed.add(Arc::new(Sphere::new(
+                node.field("center")?.vec3()?,
+                node.field("radius")?.positive()?,
+                material(node)?,
+            )));
+        }
+        "moving_sphere" => {
+            node.check_keys(&[
+                "type",
+                "center0",
+                "center1",
+                "time0",
+                "time1",
+                "radius",
+                "material",
+                "transform",
+            ])?;
+            let time0 = match node.get("time0")? {
+                Some(time) => time.f32()?,
+                None => 0.0,
+            };
+            let time1 = match node.get("time1")? {
+                Some(time) => time.f32()?,
+                None => 1.0,
+            };
+            if time1 <= time0 {
+                return Err(node.field("time1")?.error("must come after time0"));
+            }
+            parsed.add(Arc::new(MovingSphere::new(
+                node.field("center0")?.vec3()?,
+                node.field("center1")?.vec3()?,
+                time0,
+                time1,
+                node.field("radius")?.positive()?,
+                material(node)?,
+            )));
+        }
+        "quad" => {
+            node.check_keys(&["type", "q", "u", "v", "material", "transform"])?;
+            let (u, v) = (node.field("u")?.vec3()?, node.field("v")?.vec3()?);
+            if cross(u, v).near_zero() {
+                return Err(node.field("v")?.error("must not be parallel to u"));
+            }
+            parsed.add(Arc::new(Quad::new(
+                node.field("q")?.vec3()?,
+                u,
+                v,
+                material(node)?,
+            )));
+        }
+        "box" => {
+            node.check_keys(&["type", "corners", "material", "transform"])?;
+            let (a, b) = corners(node)?;
+            parsed.add(Arc::new(Cuboid::new(a, b, material(node)?)));
+        }
+        "disk" => {
+            node.check_keys(&[
+                "type",
+                "center",
+                "normal",
+                "radius",
+                "material",
+                "transform",
+            ])?;
+            parsed.add(Arc::new(Disk::new(
+                node.field("center")?.vec3()?,
+                direction(&node.field("normal")?)?,
+                node.field("radius")?.positive()?,
+                material(node)?,
+            )));
+        }
+        "plane" => {
+            node.check_keys(&["type", "point", "normal", "material", "transform"])?;
+            parsed.add(Arc::new(Plane::new(
+                node.field("point")?.vec3()?,
+                direction(&node.field("normal")?)?,
+                material(node)?,
+            )));
+        }
+        "cylinder" => {
+            node.check_keys(&[
+                "type",
+                "base",
+                "top",
+                "radius",
+                "capped",
+                "material",
+                "transform",
+            ])?;
+            let (base, top) = (node.field("base")?.vec3()?, node.field("top")?.vec3()?);
+            if (top - base).near_zero() {
+                return Err(node.field("top")?.error("must differ from base"));
+            }
+            let cylinder = Cylinder::new(
+                base,
+                top,
+                node.field("radius")?.positive()?,
+                material(node)?,
+            );
+            parsed.add(Arc::new(cylinder.capped(capped(node)?)));
+        }
+        "cone" => {
+            node.check_keys(&[
+                "type",
+                "base",
+                "apex",
+                "radius",
+                "capped",
+                "material",
+                "transform",
+            ])?;
+            let (base, apex) = (node.field("base")?.vec3()?, node.field("apex")?.vec3()?);
+            if (apex - base).near_zero() {
+                return Err(node.field("apex")?.error("must differ from base"));
+            }
+            let cone = Cone::new(
+                base,
+                apex,
+                node.field("radius")?.positive()?,
+                material(node)?,
+            );
+            parsed.add(Arc::new(cone.capped(capped(node)?)));
+        }
+        "torus" => {
+            node.check_keys(&[
+                "type",
+                "center",
+                "axis",
+                "major_radius",
+                "minor_radius",
+                "material",
+                "transform",
+            ])?;
+            let axis = match node.get("axis")? {
+                Some(axis) => direction(&axis)?,
+                None => Vec3::new(0., 1., 0.),
+            };
+            parsed.add(Arc::new(Torus::new(
+                node.field("center")?.vec3()?,
+                axis,
+                node.field("major_radius")?.positive()?,
+                node.field("minor_radius")?.positive()?,
+                material(node)?,
+            )));
+        }
+        "csg" => {
+            node.check_keys(&["type", "operation", "a", "b", "transform"])?;
+            let operation = node.field("operation")?;
+            let op = match operation.str()? {
+                "union" => CsgOp::Union,
+                "intersection" => CsgOp::Intersection,
+                "difference" => CsgOp::Difference,
+                other => {
+                    return Err(operation.error(format!(
+                        "unknown operation '{}' (expected union, intersection or difference)",
+                        other
+                    )))
+                }
+            };
+            let mut operands = Vec::new();
+            for key in ["a", "b"] {
+                let operand = node.field(key)?;
+                let mut list = HittableList::new();
+                parse_object(&operand, materials, base_dir, meshes, &mut list)?;
+                match list.objects() {
+                    [object] if object.is_closed() => operands.push(object.clone()),
+                    _ => return Err(operand.error("must be a closed shape")),
+                }
+            }
+            parsed.add(Arc::new(Csg::new(
+                op,
+                operands[0].clone(),
+                operands[1].clone(),
+            )));
+        }
+        "constant_medium" => {
+            node.check_keys(&["type", "boundary", "density", "material", "transform"])?;
+            let boundary = node.field("boundary")?;
+            let mut list = HittableList::new();
+            parse_object(&boundary, materials, base_dir, meshes, &mut list)?;
+            let boundary = match list.objects() {
+                [object] if object.is_closed() => object.clone(),
+                _ => return Err(boundary.error("must be a closed shape")),
+            };
+            parsed.add(Arc::new(ConstantMedium::new(
+                boundary,
+                node.field("density")?.positive()?,
+                material(node)?,
+            )));
+        }
+        "heterogeneous_medium" => {
+            node.check_keys(&[
+                "type",
+                "corners",
+                "grid",
+                "density",
+                "material",
+                "transform",
+            ])?;
+            let (a, b) = corners(node)?;
+            let min = Point3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+            let max = Point3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+            if (0..3).any(|axis| min[axis] == max[axis]) {
+                return Err(node.field("corners")?.error("must differ along every axis"));
+            }
+            let density = match node.get("density")? {
+                Some(density) => density.positive()?,
+                None => 1.0,
+            };
+            parsed.add(Arc::new(HeterogeneousMedium::new(
+                parse_grid(&node.field("grid")?, base_dir)?,
+                min,
+                max,
+                density,
+                material(node)?,
+            )));
+        }
+        "sdf" => {
+            node.check_keys(&[
+                "type",
+                "shape",
+                "epsilon",
+                "max_steps",
+                "material",
+                "transform",
+            ])?;
+            let mut object = SdfObject::new(parse_sdf(&node.field("shape")?)?, material(node)?);
+            if let Some(epsilon) = node.opt_positive("epsilon")? {
+                object = object.epsilon(epsilon);
+            }
+            if let Some(max_steps) = node.opt_count("max_steps")? {
+                object = object.max_steps(max_steps as u32);
+            }
+            parsed.add(Arc::new(object));
+        }
+        "heightfield" => {
+            node.check_keys(&["type", "path", "corner", "size", "material", "transform"])?;
+            let path = node.field("path")?;
+            let extension = Path::new(path.str()?)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ext.to_ascii_lowercase());
+            let too_small = |nx: usize, nz: usize| {
+                path.error(format!(
+                    "expected at least 2 by 2 heights, got {} by {}",
+                    nx, nz
+                ))
+            };
+            let corner = node.field("corner")?.vec3()?;
+            let size_node = node.field("size")?;
+            let size = size_node.vec3()?;
+            if size.x() <= 0.0 || size.z() <= 0.0 {
+                return Err(size_node.error("must be positive along x and z"));
+            }
+            let field = match extension.as_deref() {
+                Some("grid") => {
+                    let grid = load_grid(&path, base_dir)?;
+                    // Rows of the grid run along z.
+                    let [nx, nz, depth] = grid.dims();
+                    if depth != 1 {
+                        return Err(path.error(format!("expected a grid 1 deep, got {}", depth)));
+                    }
+                    if nx < 2 || nz < 2 {
+                        return Err(too_small(nx, nz));
+                    }
+                    Heightfield::new(nx, nz, grid.data(), corner, size, material(node)?)
+                }
+                _ => {
+                    let image = load_image(&base_dir.join(path.str()?))?;
+                    if image.width() < 2 || image.height() < 2 {
+                        return Err(too_small(image.width(), image.height()));
+                    }
+                    let srgb = !matches!(extension.as_deref(), Some("hdr" | "pfm"));
+                    Heightfield::from_image(&image, srgb, corner, size, material(node)?)
+                }
+            };
+            parsed.add(Arc::new(field));
+        }
+        "triangle" => {
+            node.check_keys(&["type", "vertices", "material", "transform"])?;
+            let vertices = node.field("vertices")?;
+            let v = vertices
+                .items()?
+                .iter()
+                .map(|v| v.vec3())
+                .collect::<Result<Vec<_>, _>>()?;
+            if v.len() != 3 {
+                return Err(vertices.error(format!("expected 3 vertices, got {}", v.len())));
+            }
+            parsed.add(Arc::new(Triangle::new(v[0], v[1], v[2], material(node)?)));
+        }
+        "obj" => {
+            node.check_keys(&["type", "path", "transform"])?;
+            // Loaded once however often it is placed.
+            let path = base_dir.join(node.field("path")?.str()?);
+            let mesh = match meshes.get(&path) {
+                Some(mesh) => mesh.clone(),
+                None => {
+                    let mesh = load_obj(&path)?;
+                    meshes.insert(path, mesh.clone());
+                    mesh
+                }
+            };
+            for object in mesh.objects() {
+                parsed.add(object.clone());
+            }
+        }
+        other => {
+            return Err(kind.error(format!(
+                "unknown object type '{}' (expected {})",
+                other,
+                alternatives(OBJECT_TYPES)
+            )))
+        }
+    }
+
+    match node.get("transform")? {
+        Some(transform) => {
+            let transform = parse_transform(&transform)?;
+            let object: Arc<dyn Hittable> = match parsed.objects() {
+                [object] => object.clone(),
+                _ => Arc::new(parsed),
+            };
+            world.add(Arc::new(Instance::new(object, transform)));
+        }
+        None => {
+            for object in parsed.objects() {
+                world.add(object.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The grid file named by `path`; decoding errors point at the key.
+fn load_grid(path: &Node, base_dir: &Path) -> Result<DensityGrid, SceneError> {
+    let file = base_dir.join(path.str()?);
+    let data = std::fs::read(&file).map_err(|source| SceneError::Io {
+        file: file.display().to_string(),
+        source,
+    })?;
+    read_grid(&data).map_err(|message| path.error(format!("{}: {}", file.display(), message)))
+}
+
+/// The signed distance `shape` `type`s, for error messages.
+const SHAPE_TYPES: &[&str] = &["sphere", "rounded_box", "smooth_union", "mandelbulb"];
+
+/// A signed distance field: a primitive or a smooth union of two.
+fn parse_sdf(node: &Node) -> Result<Arc<dyn Sdf>, SceneError> {
+    let kind = node.field("type")?;
+    Ok(match kind.str()? {
+        "sphere" => {
+            node.check_keys(&["type", "center", "radius"])?;
+            Arc::new(SdfSphere::new(
+                node.field("center")?.vec3()?,
+                node.field("radius")?.positive()?,
+            ))
+        }
+        "rounded_box" => {
+            node.check_keys(&["type", "center", "half_extents", "radius"])?;
+            let half_extents_node = node.field("half_extents")?;
+            let half_extents = half_extents_node.vec3()?;
+            if (0..3).any(|a| half_extents[a] <= 0.0) {
+                return Err(half_extents_node.error("must be positive"));
+            }
+            let radius = match node.get("radius")? {
+                Some(radius) => radius.non_negative()?,
+                None => 0.0,
+            };
+            if (0..3).any(|a| radius > half_extents[a]) {
+                return Err(node
+                    .field("radius")?
+                    .error("must not exceed the smallest half extent"));
+            }
+            Arc::new(RoundedBox::new(
+                node.field("center")?.vec3()?,
+                half_extents,
+                radius,
+            ))
+        }
+        "smooth_union" => {
+            node.check_keys(&["type", "a", "b", "k"])?;
+            Arc::new(SmoothUnion::new(
+                parse_sdf(&node.field("a")?)?,
+                parse_sdf(&node.field("b")?)?,
+                node.field("k")?.positive()?,
+            ))
+        }
+        "mandelbulb" => {
+            node.check_keys(&["type", "power", "iterations"])?;
+            let power = match node.get("power")? {
+                Some(power) => match power.f32()? {
+                    p if p >= 2.0 => p,
+                    _ => return Err(power.error("must be at least 2")),
+                },
+                None => 8.0,
+            };
+            let iterations = node.opt_count("iterations")?.unwrap_or(12);
+            Arc::new(Mandelbulb::new(power, iterations as u32))
+        }
+        other => {
+            return Err(kind.error(format!(
+                "unknown shape type '{}' (expected {})",
+                other,
+                alternatives(SHAPE_TYPES)
+            )))
+        }
+    })
+}
+
+/// A density grid read from a `file` or generated as a noisy `cloud`.
+fn parse_grid(node: &Node, base_dir: &Path) -> Result<Arc<DensityGrid>, SceneError> {
+    let kind = node.field("type")?;
+    Ok(Arc::new(match kind.str()? {
+        "file" => {
+            node.check_keys(&["type", "path"])?;
+            load_grid(&node.field("path")?, base_dir)?
+        }
+        "cloud" => {
+            node.check_keys(&["type", "resolution", "seed", "scale"])?;
+            let resolution = node.opt_count("resolution")?.unwrap_or(64);
+            let seed = match node.get("seed")? {
+                Some(seed) => seed.u64()?,
+                None => 0,
+            };
+            let scale = node.opt_positive("scale")?.unwrap_or(4.0);
+            DensityGrid::cloud(resolution as usize, seed, scale)
+        }
+        other => {
+            return Err(kind.error(format!(
+                "unknown grid type '{}' (expected file or cloud)",
+                other
+            )))
+        }
+    }))
+}
+
+/// A list of steps applied in order, each one of `translate`, `rotate`,
+/// `scale` or a 4x4 `matrix`.
+fn parse_transform(node: &Node) -> Result<Transform, SceneError> {
+    let mut transform = Transform::identity();
+    for step in node.items()? {
+        let keys: Vec<&String> = step.object()?.keys().collect();
+        let [kind] = keys[..] else {
+            return Err(step.error("expected one of translate, rotate, scale or matrix"));
+        };
+        let value = step.field(kind)?;
+        let next = match kind.as_str() {
+            "translate" => Transform::translate(value.vec3()?),
+            "rotate" => {
+                value.check_keys(&["axis", "degrees"])?;
+                let axis = value.field("axis")?.vec3()?;
+                if axis.near_zero() {
+                    return Err(value.field("axis")?.error("must not be zero"));
+                }
+                Transform::rotate(axis, value.field("degrees")?.f32()?)
+            }
+            "scale" => {
+                let factors = if value.value.is_number() {
+                    let factor = value.f32()?;
+                    Vec3::new(factor, factor, factor)
+                } else {
+                    value.vec3()?
+                };
+                if factors.x() == 0.0 || factors.y() == 0.0 || factors.z() == 0.0 {
+                    return Err(value.error("must not be zero"));
+                }
+                Transform::scale(factors)
+            }
+            "matrix" => {
+                let rows = value.items()?;
+                if rows.len() != 4 {
+                    return Err(value.error(format!("expected 4 rows, got {}", rows.len())));
+                }
+                let mut m = [[0.0; 4]; 4];
+                for (row, node) in m.iter_mut().zip(rows) {
+                    let items = node.items()?;
+                    if items.len() != 4 {
+                        return Err(node.error(format!("expected 4 numbers, got {}", items.len())));
+                    }
+                    for (value, item) in row.iter_mut().zip(items) {
+                        *value = item.f32()?;
+                    }
+                }
+                Transform::from_matrix(m).ok_or_else(|| {
+                    value.error("must be affine (last row 0, 0, 0, 1) and invertible")
+                })?
+            }
+            other => {
+                return Err(value.error(format!(
+                    "unknown transform '{}' (expected translate, rotate, scale or matrix)",
+                    other
+                )))
+            }
+        };
+        transform = transform.then(&next);
+    }
+    Ok(transform)
+}
+
+/// `names` listed as alternatives, like "a, b or c".
+fn alternatives(names: &[&str]) -> String {
+    match names {
+        [rest @ .., last] if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        _ => names.join(""),
+    }
+}
+
+/// A JSON value together with the key path leading to it, for error messages.
+struct Node<'a> {
+    value: &'a Value,
+    key: String,
+    file: &'a str,
+}
+
+impl<'a> Node<'a> {
+    fn error(&self, message: impl Into<String>) -> SceneError {
+        SceneError::Invalid {
+            file: self.file.to_string(),
+            key: if self.key.is_empty() {
+                String::from("<root>")
+            } else {
+                self.key.clone()
+            },
+            message: message.into(),
+        }
+    }
+
+    fn child(&self, key: String, value: &'a Value) -> Node<'a> {
+        Node {
+            value,
+            key,
+            file: self.file,
+        }
+    }
+
+    fn object(&self) -> Result<&'a Map<String, Value>, SceneError> {
+        self.value
+            .as_object()
+            .ok_or_else(|| self.error("expected an object"))
+    }
+
+    /// The key of this node's member `name`.
+    fn member_key(&self, name: &str) -> String {
+        if self.key.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", self.key, name)
+        }
+    }
+
+    fn get(&self, name: &str) -> Result<Option<Node<'a>>, SceneError> {
+        Ok(self
+            .object()?
+            .get(name)
+            .map(|value| self.child(self.member_key(name), value)))
+    }
+
+    fn field(&self, name: &str) -> Result<Node<'a>, SceneError> {
+        self.get(name)?
+            .ok_or_else(|| self.error(format!("missing key '{}'", name)))
+    }
+
+    fn check_keys(&self, allowed: &[&str]) -> Result<(), SceneError> {
+        for key in self.object()?.keys() {
+            if !allowed.contains(&key.as_str()) {
+                return Err(self.child(self.member_key(key), self.value).error(format!(
+                    "unknown key (expected one of: {})",
+                    allowed.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<(String, Node<'a>)>, SceneError> {
+        Ok(self
+            .object()?
+            .iter()
+            .map(|(name, value)| (name.clone(), self.child(self.member_key(name), value)))
+            .collect())
+    }
+
+    fn items(&self) -> Result<Vec<Node<'a>>, SceneError> {
+        let items = self
+            .value
+            .as_array()
+            .ok_or_else(|| self.error("expected an array"))?;
+        Ok(items
+            .iter()
+            .enumerate()
+            .map(|(i, value)| self.child(format!("{}[{}]", self.key, i), value))
+            .collect())
+    }
+
+    fn str(&self) -> Result<&'a str, SceneError> {
+        self.value
+            .as_str()
+            .ok_or_else(|| self.error("expected a string"))
+    }
+
+    fn f32(&self) -> Result<f32, SceneError> {
+        self.value
+            .as_f64()
+            .map(|v| v as f32)
+            .ok_or_else(|| self.error("expected a number"))
+    }
+
+    fn positive(&self) -> Result<f32, SceneError> {
+        let v = self.f32()?;
+        if v > 0.0 {
+            Ok(v)
+        } else {
+            Err(self.error(format!("must be positive, got {}", v)))
+        }
+    }
+
+    fn non_negative(&self) -> Result<f32, SceneError> {
+        let v = self.f32()?;
+        if v >= 0.0 {
+            Ok(v)
+        } else {
+            Err(self.error(format!("must not be negative, got {}", v)))
+        }
+    }
+
+    fn opt_positive(&self, name: &str) -> Result<Option<f32>, SceneError> {
+        self.get(name)?.map(|node| node.positive()).transpose()
+    }
+
+    fn opt_count(&self, name: &str) -> Result<Option<i32>, SceneError> {
+        self.get(name)?
+            .map(|node| match node.value.as_u64() {
+                Some(v) if v >= 1 && v <= i32::MAX as u64 => Ok(v as i32),
+                _ => Err(node.error("expected a positive integer")),
+            })
+            .transpose()
+    }
+
+    fn bool(&self) -> Result<bool, SceneError> {
+        self.value
+            .as_bool()
+            .ok_or_else(|| self.error("expected true or false"))
+    }
+
+    fn u64(&self) -> Result<u64, SceneError> {
+        self.value
+            .as_u64()
+            .ok_or_else(|| self.error("expected a non-negative integer"))
+    }
+
+    fn vec3(&self) -> Result<Vec3, SceneError> {
+        let items = self.items()?;
+        if items.len() != 3 {
+            return Err(self.error(format!("expected 3 numbers, got {}", items.len())));
+        }
+        Ok(Vec3::new(items[0].f32()?, items[1].f32()?, items[2].f32()?))
+    }
+
+    fn color(&self) -> Result<Color, SceneError> {
+        let c = self.vec3()?;
+        if c.x() < 0.0 || c.y() < 0.0 || c.z() < 0.0 {
+            return Err(self.error("color components must not be negative"));
+        }
+        Ok(c)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::image::Image;
+    use crate::input::srgb_to_linear;
+    use crate::output::write_png;
+    use crate::ray::Ray;
+
+    fn parse(source: &str) -> Result<SceneDescription, SceneError> {
+        parse_scene(source, "test.json", Path::new(""))
+    }
+
+    fn parse_error(source: &str) -> String {
+        parse(source).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn parse_full_scene() {
+        let scene = parse(
+            r#"{
+                "image": { "width": 300, "aspect_ratio": 2.0, "samples_per_pixel": 8, "max_depth": 5 },
+                "camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 },
+                "background": [0, 0, 0.1],
+                "materials": {
+                    "red": { "type": "lambertian", "albedo": [0.8, 0.1, 0.1] },
+                    "glass": { "type": "dielectric", "ir": 1.5 },
+                    "lamp": { "type": "diffuse_light", "emit": [4, 4, 4] }
+                },
+                "objects": [
+                    { "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "red" },
+                    { "type": "triangle", "vertices": [[0, 0, -2], [1, 0, -2], [0, 1, -2]], "material": "glass" },
+                    { "type": "sphere", "center": [0, 10, 0], "radius": 1, "material": "lamp" }
+                ]
+            }"#,
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(scene.settings, RenderSettings::new(2.0, 300, 8, 5));
+        assert_eq!(scene.settings.image_height, 150);
+        assert_eq!(
+            scene.background.color(Vec3::new(0., 1., 0.)),
+            Color::new(0., 0., 0.1)
+        );
+        assert_eq!(scene.world.objects().len(), 3);
+        assert_eq!(scene.lights.objects().len(), 1);
+        assert!(Arc::ptr_eq(
+            &scene.lights.objects()[0],
+            &scene.world.objects()[2]
+        ));
+
+        let r = scene
+            .camera
+            .build(scene.settings.aspect_ratio)
+            .get_ray(0.5, 0.5);
+        let rec = scene.world.hit(r, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.p - Point3::new(0., 0., 1.)).length() < 1e-4);
+    }
+
+    #[test]
+    fn motion_blur() {
+        let scene = parse(
+            r#"{
+                "camera": {
+                    "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40,
+                    "shutter_open": 0.5, "shutter_close": 1
+                },
+                "materials": { "red": { "type": "lambertian", "albedo": [0.8, 0.1, 0.1] } },
+                "objects": [
+                    { "type": "moving_sphere", "center0": [0, -2, 0], "center1": [0, 2, 0], "radius": 1, "material": "red" }
+                ]
+            }"#,
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(
+            (scene.camera.shutter_open, scene.camera.shutter_close),
+            (0.5, 1.)
+        );
+        // The sphere rises from y = 0 to y = 2 while the shutter is open,
+        // so the centre of the image sees it for the first half only.
+        let camera = scene.camera.build(scene.settings.aspect_ratio);
+        for _ in 0..100 {
+            let r = camera.get_ray(0.5, 0.5);
+            assert!((0.5..=1.).contains(&r.time()));
+            let hit = scene.world.hit(r, 0.001, f32::INFINITY).is_some();
+            assert_eq!(hit, r.time() <= 0.75);
+        }
+
+        let camera = r#""camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40, "shutter_open": 1, "shutter_close": 0 }"#;
+        assert_eq!(
+            parse_error(&format!(r#"{{ {}, "objects": [] }}"#, camera)),
+            "test.json: camera.shutter_close: must not come before shutter_open"
+        );
+    }
+
+    #[test]
+    fn planar_shapes() {
+        let scene = parse(
+            r#"{
+                "camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 },
+                "materials": {
+                    "white": { "type": "lambertian", "albedo": [0.7, 0.7, 0.7] },
+                    "lamp": { "type": "diffuse_light", "emit": [4, 4, 4] }
+                },
+                "objects": [
+                    { "type": "quad", "q": [-1, -1, 0], "u": [2, 0, 0], "v": [0, 2, 0], "material": "white" },
+                    { "type": "box", "corners": [[2, 0, 0], [3, 1, -1]], "material": "white" },
+                    { "type": "disk", "center": [0, 4, 0], "normal": [0, -1, 0], "radius": 1, "material": "lamp" },
+                    { "type": "plane", "point": [0, -2, 0], "normal": [0, 1, 0], "material": "white" }
+                ]
+            }"#,
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(scene.world.objects().len(), 4);
+        assert_eq!(scene.lights.objects().len(), 1);
+        let world = crate::bvh::accelerate(&scene.world);
+        assert_eq!(world.objects().len(), 2);
+
+        let hit = |origin: Point3, direction: Vec3| {
+            world
+                .hit(Ray::new(origin, direction), 0.001, f32::INFINITY)
+                .unwrap()
+                .p
+        };
+        let toward = Vec3::new(0., 0., -1.);
+        assert!((hit(Point3::new(0.5, 0.5, 5.), toward).z()).abs() < 1e-4);
+        assert!((hit(Point3::new(2.5, 0.5, 5.), toward).z()).abs() < 1e-4);
+        let up = Vec3::new(0., 1., 0.);
+        assert!((hit(Point3::new(0.2, 2., 0.2), up).y() - 4.).abs() < 1e-4);
+        assert!((hit(Point3::new(50., 0., 50.), -up).y() + 2.).abs() < 1e-4);
+
+        let camera = r#""camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 }"#;
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "materials": {{ "m": {{ "type": "lambertian", "albedo": [1, 1, 1] }} }},
+                    "objects": [{{ "type": "quad", "q": [0, 0, 0], "u": [1, 0, 0], "v": [2, 0, 0], "material": "m" }}] }}"#,
+                camera
+            )),
+            "test.json: objects[0].v: must not be parallel to u"
+        );
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "materials": {{ "m": {{ "type": "lambertian", "albedo": [1, 1, 1] }} }},
+                    "objects": [{{ "type": "plane", "point": [0, 0, 0], "normal": [0, 0, 0], "material": "m" }}] }}"#,
+                camera
+            )),
+            "test.json: objects[0].normal: must not be zero"
+        );
+    }
+
+    #[test]
+    fn revolved_shapes() {
+        let scene = parse(
+            r#"{
+                "camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 },
+                "materials": { "steel": { "type": "metal", "albedo": [0.8, 0.8, 0.8], "fuzz": 0 } },
+                "objects": [
+                    { "type": "cylinder", "base": [0, 0, 0], "top": [0, 2, 0], "radius": 0.5, "capped": false, "material": "steel" },
+                    { "type": "cone", "base": [3, 0, 0], "apex": [3, 2, 0], "radius": 1, "material": "steel" },
+                    { "type": "torus", "center": [6, 1, 0], "axis": [0, 0, 1], "major_radius": 1, "minor_radius": 0.25, "material": "steel" }
+                ]
+            }"#,
+        )
+        .ok()
+        .unwrap();
+        let toward = |x, y| Ray::new(Point3::new(x, y, 5.), Vec3::new(0., 0., -1.));
+        let depth = |x, y| {
+            scene
+                .world
+                .hit(toward(x, y), 0.001, f32::INFINITY)
+                .map(|rec| rec.p.z())
+        };
+        assert!((depth(0., 1.).unwrap() - 0.5).abs() < 1e-4);
+        assert!((depth(3., 1.).unwrap() - 0.5).abs() < 1e-4);
+        assert!((depth(6., 2.).unwrap() - 0.25).abs() < 1e-3);
+        assert_eq!(depth(6., 1.), None);
+
+        let camera = r#""camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 }"#;
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "materials": {{ "m": {{ "type": "lambertian", "albedo": [1, 1, 1] }} }},
+                    "objects": [{{ "type": "cylinder", "base": [0, 0, 0], "top": [0, 1, 0], "radius": 1, "capped": 1, "material": "m" }}] }}"#,
+                camera
+            )),
+            "test.json: objects[0].capped: expected true or false"
+        );
+    }
+
+    #[test]
+    fn solid_geometry() {
+        let scene = parse(
+            r#"{
+                "camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 },
+                "materials": { "steel": { "type": "metal", "albedo": [0.8, 0.8, 0.8], "fuzz": 0 } },
+                "objects": [{
+                    "type": "csg", "operation": "difference",
+                    "a": { "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "steel" },
+                    "b": {
+                        "type": "cylinder", "base": [0, -2, 0], "top": [0, 2, 0], "radius": 0.25, "material": "steel",
+                        "transform": [{ "rotate": { "axis": [1, 0, 0], "degrees": 90 } }]
+                    },
+                    "transform": [{ "translate": [1, 0, 0] }]
+                }]
+            }"#,
+        )
+        .ok()
+        .unwrap();
+        let toward = |x, y| Ray::new(Point3::new(x, y, 5.), Vec3::new(0., 0., -1.));
+        // The bore now runs along z, through the moved sphere's center.
+        assert!(scene
+            .world
+            .hit(toward(1., 0.), 0.001, f32::INFINITY)
+            .is_none());
+        let rec = scene
+            .world
+            .hit(toward(1.5, 0.), 0.001, f32::INFINITY)
+            .unwrap();
+        assert!((rec.p.z() - 0.75f32.sqrt()).abs() < 1e-4);
+
+        let camera = r#""camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 }"#;
+        let csg = |operation: &str, b: &str| {
+            parse_error(&format!(
+                r#"{{ {}, "materials": {{ "m": {{ "type": "lambertian", "albedo": [1, 1, 1] }} }},
+                    "objects": [{{ "type": "csg", "operation": "{}",
+                        "a": {{ "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "m" }},
+                        "b": {} }}] }}"#,
+                camera, operation, b
+            ))
+        };
+        let disk = r#"{ "type": "disk", "center": [0, 0, 0], "normal": [0, 1, 0], "radius": 1, "material": "m" }"#;
+        assert_eq!(
+            csg("union", disk),
+            "test.json: objects[0].b: must be a closed shape"
+        );
+        let sphere = r#"{ "type": "sphere", "center": [0, 0, 0], "radius": 2, "material": "m" }"#;
+        assert_eq!(
+            csg("xor", sphere),
+            "test.json: objects[0].operation: unknown operation 'xor' (expected union, intersection or difference)"
+        );
+    }
+
+    #[test]
+    fn participating_media() {
+        crate::rtweekend::seed_rng(20);
+        let scene = parse(
+            r#"{
+                "camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 },
+                "materials": {
+                    "ground": { "type": "lambertian", "albedo": [0.5, 0.5, 0.5] },
+                    "smoke": { "type": "isotropic", "albedo": [0.9, 0.9, 0.9] }
+                },
+                "objects": [{
+                    "type": "constant_medium", "density": 100, "material": "smoke",
+                    "boundary": { "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "ground" }
+                }]
+            }"#,
+        )
+        .ok()
+        .unwrap();
+        // Dense enough to scatter right at the surface.
+        let toward = |x| Ray::new(Point3::new(x, 0., 5.), Vec3::new(0., 0., -1.));
+        let rec = scene.world.hit(toward(0.), 0.001, f32::INFINITY).unwrap();
+        assert!(rec.p.z() < 1. && rec.p.z() > 0.8);
+        assert!(scene.world.hit(toward(1.5), 0.001, f32::INFINITY).is_none());
+        assert!(scene.lights.objects().is_empty());
+
+        let camera = r#""camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 }"#;
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "materials": {{ "m": {{ "type": "isotropic", "albedo": [1, 1, 1] }} }},
+                    "objects": [{{ "type": "constant_medium", "density": 1, "material": "m",
+                        "boundary": {{ "type": "quad", "q": [0, 0, 0], "u": [1, 0, 0], "v": [0, 1, 0], "material": "m" }} }}] }}"#,
+                camera
+            )),
+            "test.json: objects[0].boundary: must be a closed shape"
+        );
+    }
+
+    #[test]
+    fn heterogeneous_media() {
+        let dir = std::env::temp_dir().join(format!("scene_file_grids_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut grid = b"GRID 1 1 2\n".to_vec();
+        for d in [0f32, 1.] {
+            grid.extend_from_slice(&d.to_le_bytes());
+        }
+        std::fs::write(dir.join("half.grid"), &grid).unwrap();
+        std::fs::write(dir.join("bad.grid"), b"GRID 1 1\n").unwrap();
+        let scene = |grid: &str| {
+            parse_scene(
+                &format!(
+                    r#"{{
+                        "camera": {{ "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 }},
+                        "materials": {{ "vapour": {{ "type": "henyey_greenstein", "albedo": [1, 1, 1], "g": 0.8 }} }},
+                        "objects": [{{
+                            "type": "heterogeneous_medium", "corners": [[1, 1, 1], [-1, -1, -1]],
+                            "density": 1000, "grid": {}, "material": "vapour"
+                        }}]
+                    }}"#,
+                    grid
+                ),
+                "test.json",
+                &dir,
+            )
+        };
+
+        // Dense in the half nearer the camera, empty behind.
+        let half = scene(r#"{ "type": "file", "path": "half.grid" }"#)
+            .ok()
+            .unwrap();
+        let toward = Ray::new(Point3::new(0., 0., 5.), Vec3::new(0., 0., -1.));
+        let rec = half.world.hit(toward, 0.001, f32::INFINITY).unwrap();
+        assert!(rec.p.z() <= 1. && rec.p.z() > 0.9);
+        assert_eq!(half.world.transmittance(toward, 0.001, f32::INFINITY), 0.);
+        let away = Ray::new(Point3::new(0., 0., -0.6), Vec3::new(0., 0., -1.));
+        assert_eq!(half.world.transmittance(away, 0.001, f32::INFINITY), 1.);
+
+        let cloud = scene(r#"{ "type": "cloud", "resolution": 8, "seed": 2 }"#)
+            .ok()
+            .unwrap();
+        assert!(cloud.world.hit(toward, 0.001, f32::INFINITY).is_some());
+
+        assert_eq!(
+            scene(r#"{ "type": "file", "path": "bad.grid" }"#)
+                .err()
+                .unwrap()
+                .to_string(),
+            format!(
+                "test.json: objects[0].grid.path: {}: truncated grid header",
+                dir.join("bad.grid").display()
+            )
+        );
+        assert_eq!(
+            scene(r#"{ "type": "fractal" }"#).err().unwrap().to_string(),
+            "test.json: objects[0].grid.type: unknown grid type 'fractal' (expected file or cloud)"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let camera = r#""camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 }"#;
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "materials": {{ "m": {{ "type": "henyey_greenstein", "albedo": [1, 1, 1], "g": 1 }} }}, "objects": [] }}"#,
+                camera
+            )),
+            "test.json: materials.m.g: must lie strictly between -1 and 1"
+        );
+    }
+
+    #[test]
+    fn implicit_shapes() {
+        let scene = parse(
+            r#"{
+                "camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 },
+                "materials": { "steel": { "type": "metal", "albedo": [0.8, 0.8, 0.8], "fuzz": 0 } },
+                "objects": [
+                    {
+                        "type": "sdf", "material": "steel", "epsilon": 1e-5,
+                        "shape": {
+                            "type": "smooth_union", "k": 0.5,
+                            "a": { "type": "sphere", "center": [-1, 0, 0], "radius": 1 },
+                            "b": { "type": "rounded_box", "center": [1, 0, 0], "half_extents": [1, 1, 1], "radius": 0.25 }
+                        }
+                    },
+                    { "type": "sdf", "material": "steel", "shape": { "type": "mandelbulb" }, "transform": [{ "translate": [0, 10, 0] }] }
+                ]
+            }"#,
+        )
+        .ok()
+        .unwrap();
+        let toward = |x, y| Ray::new(Point3::new(x, y, 5.), Vec3::new(0., 0., -1.));
+        let depth = |x, y| {
+            scene
+                .world
+                .hit(toward(x, y), 0.001, f32::INFINITY)
+                .map(|rec| rec.p.z())
+        };
+        assert!((depth(-1., 0.).unwrap() - 1.).abs() < 1e-3);
+        assert!((depth(1.5, 0.5).unwrap() - 1.).abs() < 1e-3);
+        // Filled in where the sphere meets the box's rounded edge.
+        assert!(depth(0., 0.8).is_some());
+        assert!(depth(0., 10.).is_some());
+        assert_eq!(depth(3., 3.), None);
+
+        let camera = r#""camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 }"#;
+        let sdf = |shape: &str| {
+            parse_error(&format!(
+                r#"{{ {}, "materials": {{ "m": {{ "type": "lambertian", "albedo": [1, 1, 1] }} }},
+                    "objects": [{{ "type": "sdf", "material": "m", "shape": {} }}] }}"#,
+                camera, shape
+            ))
+        };
+        assert_eq!(
+            sdf(
+                r#"{ "type": "rounded_box", "center": [0, 0, 0], "half_extents": [1, 0.2, 1], "radius": 0.5 }"#
+            ),
+            "test.json: objects[0].shape.radius: must not exceed the smallest half extent"
+        );
+        assert_eq!(
+            sdf(r#"{ "type": "smooth_union", "k": 1, "a": { "type": "sphere", "center": [0, 0, 0], "radius": 1 }, "b": { "type": "cube" } }"#),
+            "test.json: objects[0].shape.b.type: unknown shape type 'cube' (expected sphere, rounded_box, smooth_union or mandelbulb)"
+        );
+    }
+
+    #[test]
+    fn heightfields() {
+        let dir = std::env::temp_dir().join(format!("scene_file_terrain_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // A ramp down x, stored as values rather than light.
+        std::fs::write(dir.join("ramp.pgm"), "P2 3 2 255\n0 51 255\n0 51 255\n").unwrap();
+        let mut grid = b"GRID 2 2 1\n".to_vec();
+        for h in [0f32, 4., 0., 4.] {
+            grid.extend_from_slice(&h.to_le_bytes());
+        }
+        std::fs::write(dir.join("ramp.grid"), &grid).unwrap();
+        std::fs::write(dir.join("deep.grid"), b"GRID 1 1 2\n\0\0\0\0\0\0\0\0").unwrap();
+        // The same ramp as colours, stored as the bytes 0, 51, 102, 153 and 255.
+        let stored = |b: f32| srgb_to_linear(b / 255.);
+        let mut colours = Image::new(3, 2);
+        for z in 0..2 {
+            colours.set(1, z, Color::new(stored(51.), stored(102.), stored(153.)));
+            colours.set(2, z, Color::new(1., 1., 1.));
+        }
+        let mut png = Vec::new();
+        write_png(&colours, &mut png).unwrap();
+        std::fs::write(dir.join("ramp.png"), png).unwrap();
+        let scene = |path: &str| {
+            parse_scene(
+                &format!(
+                    r#"{{
+                        "camera": {{ "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 }},
+                        "materials": {{ "ground": {{ "type": "lambertian", "albedo": [0.5, 0.5, 0.5] }} }},
+                        "objects": [{{
+                            "type": "heightfield", "path": "{}", "corner": [0, 1, 0], "size": [2, 10, 1],
+                            "material": "ground"
+                        }}]
+                    }}"#,
+                    path
+                ),
+                "test.json",
+                &dir,
+            )
+        };
+        let height = |scene: &SceneDescription, x| {
+            let down = Ray::new(Point3::new(x, 100., 0.5), Vec3::new(0., -1., 0.));
+            scene.world.hit(down, 0.001, f32::INFINITY).unwrap().p.y()
+        };
+        let image = scene("ramp.pgm").ok().unwrap();
+        assert!((height(&image, 1.) - 3.).abs() < 1e-4);
+        // Scene files and `from_image` agree on what an image's heights are.
+        let png = scene("ramp.png").ok().unwrap();
+        let direct = Heightfield::from_image(
+            &load_image(&dir.join("ramp.png")).unwrap(),
+            true,
+            Point3::new(0., 1., 0.),
+            Vec3::new(2., 10., 1.),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        for x in [0.25, 1., 1.6] {
+            let down = Ray::new(Point3::new(x, 100., 0.5), Vec3::new(0., -1., 0.));
+            let expected = direct.hit(down, 0.001, f32::INFINITY).unwrap().p.y();
+            assert_eq!(height(&png, x), expected);
+        }
+        assert!((height(&png, 1.) - 5.).abs() < 1e-4);
+        let floats = scene("ramp.grid").ok().unwrap();
+        assert!((height(&floats, 0.5) - 11.).abs() < 1e-4);
+        assert_eq!(
+            scene("deep.grid").err().unwrap().to_string(),
+            "test.json: objects[0].path: expected a grid 1 deep, got 2"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn transformed_instances() {
+        let dir = std::env::temp_dir().join(format!("scene_file_instances_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tri.obj"), "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        let scene = parse_scene(
+            r#"{
+                "camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 },
+                "materials": { "lamp": { "type": "diffuse_light", "emit": [4, 4, 4] } },
+                "objects": [
+                    { "type": "obj", "path": "tri.obj", "transform": [{ "translate": [10, 0, 0] }] },
+                    {
+                        "type": "obj", "path": "tri.obj",
+                        "transform": [{ "scale": [2, 1, 1] }, { "rotate": { "axis": [0, 0, 1], "degrees": 90 } }]
+                    },
+                    {
+                        "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "lamp",
+                        "transform": [{ "matrix": [[2, 0, 0, 0], [0, 2, 0, 20], [0, 0, 2, 0], [0, 0, 0, 1]] }]
+                    }
+                ]
+            }"#,
+            "test.json",
+            &dir,
+        )
+        .ok()
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(scene.world.objects().len(), 3);
+        assert_eq!(scene.lights.objects().len(), 1);
+        let down = |x, y| Ray::new(Point3::new(x, y, 1.), Vec3::new(0., 0., -1.));
+        assert!(scene
+            .world
+            .hit(down(10.2, 0.2), 0.001, f32::INFINITY)
+            .is_some());
+        assert!(scene
+            .world
+            .hit(down(0.2, 0.2), 0.001, f32::INFINITY)
+            .is_none());
+        // Stretched along x, then turned to lie along y.
+        assert!(scene
+            .world
+            .hit(down(-0.2, 1.5), 0.001, f32::INFINITY)
+            .is_some());
+        let rec = scene
+            .world
+            .hit(
+                Ray::new(Point3::new(0., 20., 5.), Vec3::new(0., 0., -1.)),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((rec.p - Point3::new(0., 20., 2.)).length() < 1e-4);
+
+        let camera = r#""camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 }"#;
+        let error = |transform: &str| {
+            parse_error(&format!(
+                r#"{{ {}, "materials": {{ "red": {{ "type": "lambertian", "albedo": [1, 0, 0] }} }},
+                    "objects": [{{ "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "red", "transform": {} }}] }}"#,
+                camera, transform
+            ))
+        };
+        assert_eq!(
+            error(r#"[{ "scale": 0 }]"#),
+            "test.json: objects[0].transform[0].scale: must not be zero"
+        );
+        assert_eq!(
+            error(r#"[{ "translate": [1, 0, 0], "scale": 2 }]"#),
+            "test.json: objects[0].transform[0]: expected one of translate, rotate, scale or matrix"
+        );
+        assert_eq!(
+            error(r#"[{ "matrix": [[1, 0, 0, 0], [0, 1, 0, 0], [0, 0, 0, 0], [0, 0, 0, 1]] }]"#),
+            "test.json: objects[0].transform[0].matrix: must be affine (last row 0, 0, 0, 1) and invertible"
+        );
+    }
+
+    #[test]
+    fn textured_materials() {
+        let scene = parse(
+            r#"{
+                "camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 },
+                "materials": {
+                    "board": {
+                        "type": "lambertian",
+                        "albedo": { "type": "checker", "space": "uv", "scale": 0.25, "even": [1, 1, 1], "odd": [0, 0, 1] }
+                    }
+                },
+                "objects": [{ "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "board" }]
+            }"#,
+        )
+        .ok()
+        .unwrap();
+        // The ray hits (0, 0, 1), at u = 0.25 and v = 0.5 on the sphere.
+        let r = Ray::new(Point3::new(0., 0., 5.), Vec3::new(0., 0., -1.));
+        let rec = scene.world.hit(r, 0.001, f32::INFINITY).unwrap();
+        let sample = rec.material.sample(r, &rec).unwrap();
+        assert_eq!(sample.attenuation, Color::new(0., 0., 1.));
+
+        let camera = r#""camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 }"#;
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "materials": {{ "m": {{ "type": "lambertian", "albedo": {{ "type": "image", "path": "a.png", "wrap": "tile" }} }} }}, "objects": [] }}"#,
+                camera
+            )),
+            "test.json: materials.m.albedo.wrap: unknown wrap mode 'tile' (expected repeat, mirror or clamp)"
+        );
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "materials": {{ "m": {{ "type": "metal", "albedo": {{ "type": "wood", "seed": -1 }} }} }}, "objects": [] }}"#,
+                camera
+            )),
+            "test.json: materials.m.albedo.seed: expected a non-negative integer"
+        );
+        let marble =
+            r#"{ "type": "marble", "seed": 3, "scale": 2, "colors": [[0, 0, 0], [1, 1, 1]] }"#;
+        let scene = parse(&format!(
+            r#"{{ {}, "materials": {{ "m": {{ "type": "lambertian", "albedo": {} }} }}, "objects": [] }}"#,
+            camera, marble
+        ));
+        assert!(scene.is_ok());
+    }
+
+    #[test]
+    fn errors_name_the_key() {
+        let camera = r#""camera": { "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vfov": 40 }"#;
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "objects": [
+                    {{ "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "red" }},
+                    {{ "type": "sphere", "center": [0, 0], "radius": 1, "material": "red" }}
+                ] }}"#,
+                camera
+            )),
+            "test.json: objects[0].material: unknown material 'red'"
+        );
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "materials": {{ "m": {{ "type": "metal", "albedo": [1, 1, 1], "fuz": 0.1 }} }}, "objects": [] }}"#,
+                camera
+            )),
+            "test.json: materials.m.fuz: unknown key (expected one of: type, albedo, fuzz)"
+        );
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "materials": {{ "m": {{ "type": "plastic" }} }}, "objects": [] }}"#,
+                camera
+            )),
+            "test.json: materials.m.type: unknown material type 'plastic' (expected lambertian, \
+             isotropic, henyey_greenstein, metal, dielectric or diffuse_light)"
+        );
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "objects": [{{ "type": "blob" }}] }}"#,
+                camera
+            )),
+            "test.json: objects[0].type: unknown object type 'blob' (expected sphere, \
+             moving_sphere, quad, box, disk, plane, cylinder, cone, torus, csg, constant_medium, \
+             heterogeneous_medium, sdf, heightfield, triangle or obj)"
+        );
+        assert!(parse_error(&format!(r#"{{ {}, "objcts": [] }}"#, camera))
+            .starts_with("test.json: objcts: unknown key"));
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "image": {{ "samples_per_pixel": 0 }}, "objects": [] }}"#,
+                camera
+            )),
+            "test.json: image.samples_per_pixel: expected a positive integer"
+        );
+        assert_eq!(
+            parse_error(&format!(
+                r#"{{ {}, "background": {{ "type": "stars" }}, "objects": [] }}"#,
+                camera
+            )),
+            "test.json: background.type: unknown background type 'stars' (expected gradient or image)"
+        );
+        assert_eq!(
+            parse_error(r#"{ "objects": [] }"#),
+            "test.json: <root>: missing key 'camera'"
+        );
+    }
+}
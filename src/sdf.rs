@@ -0,0 +1,202 @@
+//! Signed distance fields, for implicit shapes rendered by `SdfObject`.
+
+use std::sync::Arc;
+
+use crate::aabb::{surrounding_box, Aabb};
+use crate::vec3::*;
+
+/// A shape given by the signed distance to its surface: negative inside,
+/// positive outside. Sphere tracing only needs a lower bound on the true
+/// distance, so estimates that never overshoot will do.
+pub trait Sdf: Send + Sync {
+    fn distance(&self, p: Point3) -> f32;
+
+    /// Axis-aligned box enclosing the surface, or `None` if it is unbounded.
+    fn bounding_box(&self) -> Option<Aabb>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfSphere {
+    center: Point3,
+    radius: f32,
+}
+
+impl SdfSphere {
+    pub fn new(center: Point3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, p: Point3) -> f32 {
+        (p - self.center).length() - self.radius
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        Some(Aabb::new(self.center - r, self.center + r))
+    }
+}
+
+/// An axis-aligned box of the given half extents with its edges and
+/// corners rounded off by `radius`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundedBox {
+    center: Point3,
+    half_extents: Vec3,
+    radius: f32,
+}
+
+impl RoundedBox {
+    /// `radius` may not exceed the smallest half extent.
+    pub fn new(center: Point3, half_extents: Vec3, radius: f32) -> Self {
+        assert!(
+            radius >= 0.0 && (0..3).all(|a| radius <= half_extents[a]),
+            "the rounding radius must fit inside the box"
+        );
+        Self {
+            center,
+            half_extents,
+            radius,
+        }
+    }
+}
+
+impl Sdf for RoundedBox {
+    fn distance(&self, p: Point3) -> f32 {
+        let r = self.radius;
+        let d = p - self.center;
+        // How far outside the box shrunk by the radius, per axis.
+        let q = [0, 1, 2].map(|a| d[a].abs() - (self.half_extents[a] - r));
+        let outside = Vec3::new(q[0].max(0.0), q[1].max(0.0), q[2].max(0.0)).length();
+        let inside = q[0].max(q[1]).max(q[2]).min(0.0);
+        outside + inside - r
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(
+            self.center - self.half_extents,
+            self.center + self.half_extents,
+        ))
+    }
+}
+
+/// Two shapes merged with a fillet of about `k` where they meet, rather
+/// than the crease of a plain union.
+#[derive(Clone)]
+pub struct SmoothUnion {
+    a: Arc<dyn Sdf>,
+    b: Arc<dyn Sdf>,
+    k: f32,
+}
+
+impl SmoothUnion {
+    pub fn new(a: Arc<dyn Sdf>, b: Arc<dyn Sdf>, k: f32) -> Self {
+        assert!(k > 0.0, "the blend distance must be positive");
+        Self { a, b, k }
+    }
+}
+
+impl Sdf for SmoothUnion {
+    /// The polynomial smooth minimum, which lies at most `k / 4` below the
+    /// plain one.
+    fn distance(&self, p: Point3) -> f32 {
+        let (a, b) = (self.a.distance(p), self.b.distance(p));
+        let h = (0.5 + 0.5 * (b - a) / self.k).clamp(0.0, 1.0);
+        b + (a - b) * h - self.k * h * (1.0 - h)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let bbox = surrounding_box(self.a.bounding_box()?, self.b.bounding_box()?);
+        let pad = Vec3::new(self.k, self.k, self.k) / 4.0;
+        Some(Aabb::new(bbox.min() - pad, bbox.max() + pad))
+    }
+}
+
+/// The Mandelbulb fractal of the given power (8 for the classic one),
+/// about unit size around the origin, estimated by iterating at most
+/// `iterations` times.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mandelbulb {
+    power: f32,
+    iterations: u32,
+}
+
+impl Mandelbulb {
+    pub fn new(power: f32, iterations: u32) -> Self {
+        assert!(power >= 2.0, "the power must be at least 2");
+        Self { power, iterations }
+    }
+}
+
+impl Sdf for Mandelbulb {
+    /// The usual distance estimate from the running derivative of the orbit.
+    fn distance(&self, p: Point3) -> f32 {
+        let n = self.power;
+        let mut z = p;
+        let mut dr = 1.0;
+        let mut r = z.length();
+        for _ in 0..self.iterations {
+            if r > 2.0 || r == 0.0 {
+                break;
+            }
+            let theta = (z.z() / r).acos() * n;
+            let phi = z.y().atan2(z.x()) * n;
+            dr = n * r.powf(n - 1.0) * dr + 1.0;
+            z = r.powf(n)
+                * Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                )
+                + p;
+            r = z.length();
+        }
+        if r == 0.0 {
+            return 0.0;
+        }
+        0.5 * r.ln() * r / dr
+    }
+
+    /// Orbits starting more than 2 from the origin always escape.
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(
+            Point3::new(-2., -2., -2.),
+            Point3::new(2., 2., 2.),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let rounded = RoundedBox::new(Point3::new(1., 0., 0.), Vec3::new(1., 2., 3.), 0.5);
+        assert_eq!(rounded.distance(Point3::new(3., 0., 0.)), 1.);
+        assert_eq!(rounded.distance(Point3::new(1., 0., 0.)), -1.);
+        // Off a corner, measured from the rounded surface.
+        let corner = Point3::new(2.5, 2.5, 3.5);
+        let expected = (corner - Point3::new(1.5, 1.5, 2.5)).length() - 0.5;
+        assert!((rounded.distance(corner) - expected).abs() < 1e-6);
+
+        let a = Arc::new(SdfSphere::new(Point3::new(-1., 0., 0.), 1.));
+        let b = Arc::new(SdfSphere::new(Point3::new(1., 0., 0.), 1.));
+        let blend = SmoothUnion::new(a.clone(), b, 0.5);
+        // Far from the seam it is either sphere; at it, filled in.
+        assert_eq!(
+            blend.distance(Point3::new(-3., 0., 0.)),
+            a.distance(Point3::new(-3., 0., 0.))
+        );
+        let seam = Point3::new(0., 1., 0.);
+        assert!(blend.distance(seam) < a.distance(seam) - 0.1);
+        let bbox = blend.bounding_box().unwrap();
+        assert_eq!(bbox.min(), Point3::new(-2.125, -1.125, -1.125));
+
+        let bulb = Mandelbulb::new(8., 12);
+        assert!(bulb.distance(Point3::new(0., 0., 0.5)) < 0.);
+        let far = bulb.distance(Point3::new(0., 0., 3.));
+        assert!(far > 1. && far < 3.);
+    }
+}
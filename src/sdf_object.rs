@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::*;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sdf::Sdf;
+use crate::vec3::*;
+
+/// An implicit `Sdf` shape, found by sphere tracing: stepping along the
+/// ray by the distance to the nearest surface until within `epsilon` of
+/// it, or giving up after `max_steps`. Normals are estimated from the
+/// field's gradient. Hits carry no texture coordinates.
+#[derive(Clone)]
+pub struct SdfObject {
+    sdf: Arc<dyn Sdf>,
+    material: Arc<dyn Material>,
+    epsilon: f32,
+    max_steps: u32,
+}
+
+impl SdfObject {
+    pub fn new(sdf: Arc<dyn Sdf>, material: Arc<dyn Material>) -> Self {
+        Self {
+            sdf,
+            material,
+            epsilon: 1e-4,
+            max_steps: 256,
+        }
+    }
+
+    /// How close to the surface counts as a hit.
+    pub fn epsilon(mut self, epsilon: f32) -> Self {
+        assert!(epsilon > 0.0, "epsilon must be positive");
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// The most steps taken along one ray before calling it a miss.
+    pub fn max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// The gradient of the field, from four samples at the corners of a
+    /// tetrahedron. Steps much below 1e-4 would mostly measure rounding.
+    fn normal(&self, p: Point3) -> Vec3 {
+        let h = self.epsilon.max(1e-4);
+        let n = [
+            Vec3::new(1., -1., -1.),
+            Vec3::new(-1., -1., 1.),
+            Vec3::new(-1., 1., -1.),
+            Vec3::new(1., 1., 1.),
+        ]
+        .into_iter()
+        .fold(Vec3::default(), |sum, k| {
+            sum + k * self.sdf.distance(p + h * k)
+        });
+        unit(n)
+    }
+}
+
+impl Hittable for SdfObject {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let (start, end) = match self.sdf.bounding_box() {
+            Some(bbox) => bbox.clip(r, t_min, t_max)?,
+            None => (t_min, t_max),
+        };
+        let speed = r.direction().length();
+        let mut t = start;
+        for step in 0..self.max_steps {
+            let distance = self.sdf.distance(r.at(t)).abs();
+            // A ray starting on the surface is leaving it, so always take
+            // the first step, unless that begins where the ray enters the
+            // bounding box.
+            if distance < self.epsilon && (step > 0 || start > t_min) {
+                let mut rec = HitRecord {
+                    p: r.at(t),
+                    t,
+                    material: self.material.clone(),
+                    normal: Vec3::default(),
+                    u: 0.,
+                    v: 0.,
+                    front_face: false,
+                };
+                rec.set_face_normal(r, self.normal(rec.p));
+                return Some(rec);
+            }
+            t += distance.max(self.epsilon) / speed;
+            if t > end {
+                return None;
+            }
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.sdf.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::sdf::{Mandelbulb, SdfSphere};
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn traces_like_the_analytic_sphere() {
+        let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let center = Point3::new(0., 0., -3.);
+        let traced = SdfObject::new(Arc::new(SdfSphere::new(center, 1.)), material.clone());
+        let sphere = Sphere::new(center, 1., material.clone());
+        for (x, y) in [(0., 0.), (0.5, 0.3), (-0.2, 0.9), (0.95, 0.)] {
+            let r = Ray::new(Point3::default(), Vec3::new(x, y, -3.));
+            let expected = sphere.hit(r, 0.001, f32::INFINITY).unwrap();
+            let rec = traced.hit(r, 0.001, f32::INFINITY).unwrap();
+            assert!((rec.p - expected.p).length() < 1e-3, "{:?}", (x, y));
+            assert!((rec.normal - expected.normal).length() < 1e-3);
+            assert!(rec.front_face);
+        }
+        assert!(traced
+            .hit(
+                Ray::new(Point3::default(), Vec3::new(0., 2., -3.)),
+                0.001,
+                f32::INFINITY
+            )
+            .is_none());
+
+        // From inside, towards the far wall.
+        let out = Ray::new(center, Vec3::new(1., 0., 0.));
+        let rec = traced.hit(out, 0., f32::INFINITY).unwrap();
+        assert!((rec.t - 1.).abs() < 1e-3);
+        assert!(!rec.front_face);
+        assert!((rec.normal - Vec3::new(-1., 0., 0.)).length() < 1e-3);
+    }
+
+    #[test]
+    fn step_limit() {
+        let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let bulb = Arc::new(Mandelbulb::new(8., 12));
+        let r = Ray::new(Point3::new(0.1, 0.2, 3.), Vec3::new(0., 0., -1.));
+        let rec = SdfObject::new(bulb.clone(), material.clone())
+            .hit(r, 0.001, f32::INFINITY)
+            .unwrap();
+        assert!(rec.p.z() > 0.3 && rec.p.z() < 1.3);
+        assert!((rec.normal.length() - 1.).abs() < 1e-4);
+        assert!(dot(rec.normal, r.direction()) < 0.);
+        let hasty = SdfObject::new(bulb, material).max_steps(2);
+        assert!(hasty.hit(r, 0.001, f32::INFINITY).is_none());
+    }
+}
@@ -1,8 +1,11 @@
+use std::f32::consts::PI;
 use std::sync::Arc;
 
+use crate::aabb::Aabb;
 use crate::hittable::*;
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::rtweekend::random;
 use crate::vec3::*;
 
 #[derive(Clone)]
@@ -20,40 +23,196 @@ impl Sphere {
             material,
         }
     }
+
+    /// One minus the cosine of the half-angle of the cone the sphere fills
+    /// as seen from `origin`, or `None` from inside it.
+    fn cone_gap(&self, origin: Point3) -> Option<f32> {
+        let ratio = self.radius * self.radius / (self.center - origin).length_squared();
+        if ratio >= 1.0 {
+            return None;
+        }
+        // 1 - sqrt(1 - x), without cancellation for small spheres far away.
+        Some(ratio / (1.0 + (1.0 - ratio).sqrt()))
+    }
 }
 
-impl Hittable for Sphere {
-    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
-        let oc = r.origin() - self.center;
-        let a = r.direction().length_squared();
-        let half_b = dot(oc, r.direction());
-        let c = oc.length_squared() - self.radius * self.radius;
+/// Texture coordinates of a point on the unit sphere: `u` goes round from
+/// -x through +z, +x and -z, `v` from the bottom pole up to the top.
+pub fn sphere_uv(p: Point3) -> (f32, f32) {
+    let theta = (-p.y()).clamp(-1.0, 1.0).acos();
+    let phi = (-p.z()).atan2(p.x()) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
+
+/// Where the line through `r` crosses the sphere of `radius` around
+/// `center`, nearest first.
+fn sphere_roots(center: Point3, radius: f32, r: Ray) -> Option<(f32, f32)> {
+    let oc = r.origin() - center;
+    let a = r.direction().length_squared();
+    let half_b = dot(oc, r.direction());
+    let c = oc.length_squared() - radius * radius;
+
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrtd = discriminant.sqrt();
+    Some(((-half_b - sqrtd) / a, (-half_b + sqrtd) / a))
+}
 
-        let discriminant = half_b * half_b - a * c;
-        if discriminant < 0.0 {
+fn sphere_record(
+    center: Point3,
+    radius: f32,
+    material: &Arc<dyn Material>,
+    r: Ray,
+    t: f32,
+) -> HitRecord {
+    let mut rec = HitRecord {
+        p: r.at(t),
+        t,
+        material: material.clone(),
+        normal: Vec3::default(),
+        u: 0.0,
+        v: 0.0,
+        front_face: false,
+    };
+    let outward_normal = (rec.p - center) / radius;
+    rec.set_face_normal(r, outward_normal);
+    (rec.u, rec.v) = sphere_uv(outward_normal);
+    rec
+}
+
+/// Intersect `r` with the sphere of `radius` around `center`.
+pub(crate) fn hit_sphere(
+    center: Point3,
+    radius: f32,
+    material: &Arc<dyn Material>,
+    r: Ray,
+    t_min: f32,
+    t_max: f32,
+) -> Option<HitRecord> {
+    let (near, far) = sphere_roots(center, radius, r)?;
+
+    // Find the nearest root that lies in the acceptable range.
+    let mut root = near;
+    if root < t_min || t_max < root {
+        root = far;
+        if root < t_min || t_max < root {
             return None;
         }
-        let sqrtd = discriminant.sqrt();
+    }
+    Some(sphere_record(center, radius, material, r, root))
+}
 
-        // Find the nearest root that lies in the acceptable range.
-        let mut root = (-half_b - sqrtd) / a;
-        if root < t_min || t_max < root {
-            root = (-half_b + sqrtd) / a;
-            if root < t_min || t_max < root {
-                return None;
+/// The stretch of the line through `r` inside the sphere, if any.
+pub(crate) fn sphere_intervals(
+    center: Point3,
+    radius: f32,
+    material: &Arc<dyn Material>,
+    r: Ray,
+) -> Vec<Interval> {
+    match sphere_roots(center, radius, r) {
+        Some((near, far)) => vec![Interval {
+            enter: sphere_record(center, radius, material, r, near),
+            exit: sphere_record(center, radius, material, r, far),
+        }],
+        None => Vec::new(),
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        hit_sphere(self.center, self.radius, &self.material, r, t_min, t_max)
+    }
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        Some(Aabb::new(self.center - r, self.center + r))
+    }
+
+    fn is_closed(&self) -> bool {
+        true
+    }
+
+    fn intervals(&self, r: Ray) -> Vec<Interval> {
+        sphere_intervals(self.center, self.radius, &self.material, r)
+    }
+
+    fn sample_towards(&self, origin: Point3) -> Option<(HitRecord, f32)> {
+        // Uniform over the cone of directions that hit the sphere.
+        let gap = self.cone_gap(origin)?;
+        let cos_theta = 1.0 - random::<f32>() * gap;
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random::<f32>();
+        let direction = Onb::new(unit(self.center - origin)).local(Vec3::new(
+            phi.cos() * sin_theta,
+            phi.sin() * sin_theta,
+            cos_theta,
+        ));
+        let rec = self.hit(Ray::new(origin, direction), 0.0, f32::INFINITY)?;
+        Some((rec, 1.0 / (2.0 * PI * gap)))
+    }
+
+    fn pdf_towards(&self, origin: Point3, direction: Vec3) -> f32 {
+        match self.cone_gap(origin) {
+            Some(gap)
+                if self
+                    .hit(Ray::new(origin, direction), 0.0, f32::INFINITY)
+                    .is_some() =>
+            {
+                1.0 / (2.0 * PI * gap)
             }
+            _ => 0.0,
         }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::DiffuseLight;
+
+    #[test]
+    fn sampling_covers_the_visible_cap() {
+        crate::rtweekend::seed_rng(3);
+        let sphere = Sphere::new(
+            Point3::new(0., 0., -4.),
+            2.,
+            Arc::new(DiffuseLight::new(Color::new(1., 1., 1.))),
+        );
+        let origin = Point3::new(0., 0., 0.);
+        // The cap seen from 4 units away spans a cone of 30 degrees.
+        let solid_angle = 2. * PI * (1. - (PI / 6.).cos());
+        for _ in 0..1000 {
+            let (rec, pdf) = sphere.sample_towards(origin).unwrap();
+            assert!(((rec.p - sphere.center).length() - 2.).abs() < 1e-4);
+            assert!(rec.front_face && rec.p.z() > -4.);
+            assert!((pdf * solid_angle - 1.).abs() < 1e-3);
+            assert_eq!(sphere.pdf_towards(origin, rec.p - origin), pdf);
+        }
+        assert_eq!(sphere.pdf_towards(origin, Vec3::new(0., 1., 0.)), 0.);
+        assert!(sphere.sample_towards(Point3::new(0., 1., -4.)).is_none());
+    }
+
+    #[test]
+    fn texture_coordinates() {
+        let uv = |x, y, z| sphere_uv(Point3::new(x, y, z));
+        assert_eq!(uv(-1., 0., 0.), (0.0, 0.5));
+        assert_eq!(uv(0., 1., 0.).1, 1.0);
+        assert_eq!(uv(0., -1., 0.).1, 0.0);
+        let (u, _) = uv(0., 0., 1.);
+        assert!((u - 0.25).abs() < 1e-6);
+        let (u, _) = uv(1., 0., 0.);
+        assert!((u - 0.5).abs() < 1e-6);
+        let (u, _) = uv(0., 0., -1.);
+        assert!((u - 0.75).abs() < 1e-6);
 
-        let mut rec = HitRecord {
-            p: r.at(root),
-            t: root,
-            material: self.material.clone(),
-            normal: Vec3::default(),
-            front_face: false,
-        };
-        let outward_normal = (rec.p - self.center) / self.radius;
-        rec.set_face_normal(r, outward_normal);
-
-        Some(rec)
+        let sphere = Sphere::new(
+            Point3::new(0., 0., -4.),
+            2.,
+            Arc::new(DiffuseLight::new(Color::new(1., 1., 1.))),
+        );
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., -1.));
+        let rec = sphere.hit(r, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.u - 0.25).abs() < 1e-6 && (rec.v - 0.5).abs() < 1e-6);
     }
 }
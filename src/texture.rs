@@ -0,0 +1,348 @@
+//! Colours that vary over a surface.
+
+use std::sync::Arc;
+
+use crate::image::Image;
+use crate::perlin::Perlin;
+use crate::vec3::*;
+
+/// A colour looked up by surface coordinates `(u, v)` or by the hit point
+/// `p` itself.
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f32, v: f32, p: Point3) -> Color;
+}
+
+/// The same colour everywhere.
+pub struct SolidColor {
+    color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f32, _v: f32, _p: Point3) -> Color {
+        self.color
+    }
+}
+
+/// Where a `Checker` lays out its squares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckerSpace {
+    /// Cubes in world space, cutting through every object alike.
+    Solid,
+    /// Squares in texture space, following the surface.
+    Uv,
+}
+
+/// Alternates between two textures in cells `scale` wide.
+pub struct Checker {
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+    scale: f32,
+    space: CheckerSpace,
+}
+
+impl Checker {
+    /// A 3D checker of cubes with edges `scale` long.
+    pub fn new(scale: f32, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            even,
+            odd,
+            scale,
+            space: CheckerSpace::Solid,
+        }
+    }
+
+    /// A checker of squares `scale` wide in `(u, v)`.
+    pub fn uv(scale: f32, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            space: CheckerSpace::Uv,
+            ..Self::new(scale, even, odd)
+        }
+    }
+
+    /// Both colours as solid textures.
+    pub fn from_colors(scale: f32, even: Color, odd: Color) -> Self {
+        Self::new(
+            scale,
+            Arc::new(SolidColor::new(even)),
+            Arc::new(SolidColor::new(odd)),
+        )
+    }
+
+    pub fn space(&self) -> CheckerSpace {
+        self.space
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f32, v: f32, p: Point3) -> Color {
+        let cell = |x: f32| (x / self.scale).floor() as i64;
+        let sum = match self.space {
+            CheckerSpace::Solid => cell(p.x()) + cell(p.y()) + cell(p.z()),
+            CheckerSpace::Uv => cell(u) + cell(v),
+        };
+        if sum.rem_euclid(2) == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+/// What an `ImageTexture` shows outside [0, 1]².
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    /// Tile the image.
+    #[default]
+    Repeat,
+    /// Tile the image, flipping every other copy.
+    Mirror,
+    /// Stretch the edge pixels outwards.
+    Clamp,
+}
+
+impl WrapMode {
+    /// Map a pixel index, possibly outside the image, into `0..size`.
+    fn apply(self, i: i64, size: usize) -> usize {
+        let n = size as i64;
+        let i = match self {
+            Self::Repeat => i.rem_euclid(n),
+            Self::Mirror => {
+                let i = i.rem_euclid(2 * n);
+                if i < n {
+                    i
+                } else {
+                    2 * n - 1 - i
+                }
+            }
+            Self::Clamp => i.clamp(0, n - 1),
+        };
+        i as usize
+    }
+}
+
+/// An image stretched over [0, 1]², `v` running from its bottom row to its
+/// top, bilinearly filtered.
+pub struct ImageTexture {
+    image: Image,
+    wrap: WrapMode,
+}
+
+impl ImageTexture {
+    pub fn new(image: Image) -> Self {
+        assert!(
+            image.width() > 0 && image.height() > 0,
+            "texture without pixels"
+        );
+        Self {
+            image,
+            wrap: WrapMode::default(),
+        }
+    }
+
+    pub fn wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f32, v: f32, _p: Point3) -> Color {
+        let (width, height) = (self.image.width(), self.image.height());
+        // Pixel centres sit at half-integer coordinates.
+        let x = u * width as f32 - 0.5;
+        let y = (1.0 - v) * height as f32 - 0.5;
+        if !(x.is_finite() && y.is_finite()) {
+            return Color::default();
+        }
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let texel = |x: i64, y: i64| {
+            self.image
+                .get(self.wrap.apply(x, width), self.wrap.apply(y, height))
+        };
+        let row = |y| (1.0 - fx) * texel(x0, y) + fx * texel(x0 + 1, y);
+        (1.0 - fy) * row(y0) + fy * row(y0 + 1)
+    }
+}
+
+/// The look of a `NoiseTexture`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoisePattern {
+    /// Veins from a sine wave along z, bent by turbulence.
+    Marble,
+    /// Rings around the y axis, warped by fBm.
+    Wood,
+    /// Soft fBm billows.
+    Clouds,
+}
+
+/// A procedural texture blending between two colours by Perlin noise.
+///
+/// The pattern depends only on the point and the seed, so renders stay
+/// reproducible however the work is scheduled.
+pub struct NoiseTexture {
+    noise: Perlin,
+    pattern: NoisePattern,
+    scale: f32,
+    colors: [Color; 2],
+}
+
+impl NoiseTexture {
+    pub fn new(pattern: NoisePattern, seed: u64) -> Self {
+        let (scale, colors) = match pattern {
+            NoisePattern::Marble => (
+                4.0,
+                [Color::new(0.05, 0.05, 0.06), Color::new(0.9, 0.9, 0.88)],
+            ),
+            NoisePattern::Wood => (
+                2.0,
+                [Color::new(0.8, 0.6, 0.35), Color::new(0.45, 0.25, 0.1)],
+            ),
+            NoisePattern::Clouds => (1.0, [Color::new(0.3, 0.5, 0.9), Color::new(1.0, 1.0, 1.0)]),
+        };
+        Self {
+            noise: Perlin::new(seed),
+            pattern,
+            scale,
+            colors,
+        }
+    }
+
+    /// Spatial frequency of the pattern; larger is finer.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// The colours at either end of the pattern: veins and stone, early and
+    /// late wood, or sky and cloud.
+    pub fn colors(mut self, from: Color, to: Color) -> Self {
+        self.colors = [from, to];
+        self
+    }
+
+    /// Where `p` falls between the two colours, in [0, 1].
+    fn blend(&self, p: Point3) -> f32 {
+        let q = self.scale * p;
+        match self.pattern {
+            NoisePattern::Marble => {
+                0.5 * (1.0 + (q.z() + 10.0 * self.noise.turbulence(p, 7)).sin())
+            }
+            NoisePattern::Wood => {
+                let radius = (q.x() * q.x() + q.z() * q.z()).sqrt();
+                let r = radius + 0.5 * self.noise.fbm(q, 4);
+                r - r.floor()
+            }
+            NoisePattern::Clouds => (0.5 + 0.6 * self.noise.fbm(q, 6)).clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f32, _v: f32, p: Point3) -> Color {
+        let t = self.blend(p);
+        (1.0 - t) * self.colors[0] + t * self.colors[1]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checkers() {
+        let white = Color::new(1., 1., 1.);
+        let black = Color::default();
+        let solid = Checker::from_colors(0.5, white, black);
+        let p = |x, y, z| Point3::new(x, y, z);
+        assert_eq!(solid.value(0., 0., p(0.1, 0.1, 0.1)), white);
+        assert_eq!(solid.value(0., 0., p(0.6, 0.1, 0.1)), black);
+        assert_eq!(solid.value(0., 0., p(-0.1, 0.1, 0.1)), black);
+        assert_eq!(solid.value(0., 0., p(-0.1, -0.1, 0.1)), white);
+
+        let uv = Checker::uv(
+            0.25,
+            Arc::new(SolidColor::new(white)),
+            Arc::new(SolidColor::new(black)),
+        );
+        assert_eq!(uv.space(), CheckerSpace::Uv);
+        assert_eq!(uv.value(0.1, 0.1, p(0.6, 0., 0.)), white);
+        assert_eq!(uv.value(0.3, 0.1, p(0., 0., 0.)), black);
+        assert_eq!(uv.value(0.3, 0.3, p(0., 0., 0.)), white);
+    }
+
+    #[test]
+    fn image_lookup_and_wrapping() {
+        // Left column dark, right column bright; the top row is red.
+        let mut image = Image::new(2, 2);
+        image.set(0, 0, Color::new(1., 0., 0.));
+        image.set(1, 0, Color::new(1., 1., 0.));
+        image.set(0, 1, Color::new(0., 0., 0.));
+        image.set(1, 1, Color::new(0., 1., 0.));
+        let texture = ImageTexture::new(image);
+        let at = |u, v| texture.value(u, v, Point3::default());
+
+        // Pixel centres come back exactly; v = 1 is the top row.
+        assert_eq!(at(0.25, 0.75), Color::new(1., 0., 0.));
+        assert_eq!(at(0.75, 0.25), Color::new(0., 1., 0.));
+        assert!((at(0.5, 0.25) - Color::new(0., 0.5, 0.)).length() < 1e-6);
+        // Repeating blends across the edge with the far side.
+        assert!((at(0., 0.25) - Color::new(0., 0.5, 0.)).length() < 1e-6);
+        assert_eq!(at(1.25, 0.75), at(0.25, 0.75));
+
+        let clamped = ImageTexture::new(texture.image().clone()).wrap(WrapMode::Clamp);
+        assert_eq!(
+            clamped.value(-3., 0.25, Point3::default()),
+            Color::default()
+        );
+        let mirrored = ImageTexture::new(texture.image().clone()).wrap(WrapMode::Mirror);
+        assert_eq!(
+            mirrored.value(1.25, 0.25, Point3::default()),
+            Color::new(0., 1., 0.)
+        );
+        assert_eq!(WrapMode::Mirror.apply(-1, 3), 0);
+        assert_eq!(WrapMode::Mirror.apply(4, 3), 1);
+    }
+
+    #[test]
+    fn noise_textures_are_reproducible() {
+        let points: Vec<Point3> = (0..200)
+            .map(|i| Point3::new(i as f32 * 0.37, (i % 7) as f32 * 0.21, i as f32 * -0.13))
+            .collect();
+        for pattern in [
+            NoisePattern::Marble,
+            NoisePattern::Wood,
+            NoisePattern::Clouds,
+        ] {
+            let a = NoiseTexture::new(pattern, 9);
+            let b = NoiseTexture::new(pattern, 9).scale(a.scale);
+            let c = NoiseTexture::new(pattern, 10);
+            let mut differs = false;
+            for &p in points.iter() {
+                let t = a.blend(p);
+                assert!((0.0..=1.0).contains(&t), "{:?} at {:?}: {}", pattern, p, t);
+                assert_eq!(a.value(0., 0., p), b.value(0.5, 0.5, p));
+                differs |= a.value(0., 0., p) != c.value(0., 0., p);
+            }
+            assert!(differs, "{:?} ignores its seed", pattern);
+        }
+
+        let black_white = NoiseTexture::new(NoisePattern::Clouds, 1)
+            .colors(Color::default(), Color::new(1., 1., 1.));
+        let p = Point3::new(0.3, 0.6, 0.9);
+        let t = black_white.blend(p);
+        assert_eq!(black_white.value(0., 0., p), Color::new(t, t, t));
+    }
+}
@@ -0,0 +1,197 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::disk::rim_extent;
+use crate::hittable::*;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::roots::{quadratic, quartic};
+use crate::vec3::*;
+
+/// A ring of `minor_radius` thickness swept round `axis` through `center`,
+/// `major_radius` out from it.
+///
+/// `u` goes round the axis and `v` round the tube, starting from its outer
+/// equator.
+#[derive(Clone)]
+pub struct Torus {
+    center: Point3,
+    major_radius: f32,
+    minor_radius: f32,
+    /// `w` along the axis.
+    frame: Onb,
+    material: Arc<dyn Material>,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point3,
+        axis: Vec3,
+        major_radius: f32,
+        minor_radius: f32,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+            frame: Onb::new(unit(axis)),
+            material,
+        }
+    }
+}
+
+impl Torus {
+    /// Where the ray crosses the surface between `t_min` and `t_max`, in
+    /// order.
+    fn crossings(&self, r: Ray, t_min: f32, t_max: f32) -> Vec<f32> {
+        let (big, small) = (self.major_radius, self.minor_radius);
+        let o = self.frame.coords(r.origin() - self.center);
+        let d = self.frame.coords(r.direction());
+
+        // The quartic loses precision with the origin far away, so start
+        // it where the ray enters the bounding sphere, with a unit direction.
+        let bound = big + small;
+        let Some((enter, exit)) = quadratic(
+            d.length_squared(),
+            2.0 * dot(o, d),
+            o.length_squared() - bound * bound,
+        ) else {
+            return Vec::new();
+        };
+        if exit < t_min || enter > t_max {
+            return Vec::new();
+        }
+        let start = enter.max(t_min);
+        let scale = d.length();
+        let [ox, oy, oz] = {
+            let p = o + start * d;
+            [p.x() as f64, p.y() as f64, p.z() as f64]
+        };
+        let [dx, dy, dz] = {
+            let d = d / scale;
+            [d.x() as f64, d.y() as f64, d.z() as f64]
+        };
+
+        // (|p|² + R² - r²)² = 4R² (x² + y²) along p = o + s d.
+        let (big, small) = (big as f64, small as f64);
+        let od = ox * dx + oy * dy + oz * dz;
+        let k = ox * ox + oy * oy + oz * oz + big * big - small * small;
+        let four_r2 = 4.0 * big * big;
+        quartic(
+            1.0,
+            4.0 * od,
+            4.0 * od * od + 2.0 * k - four_r2 * (dx * dx + dy * dy),
+            4.0 * od * k - 2.0 * four_r2 * (ox * dx + oy * dy),
+            k * k - four_r2 * (ox * ox + oy * oy),
+        )
+        .into_iter()
+        .map(|s| start + s as f32 / scale)
+        .filter(|t| (t_min..=t_max).contains(t))
+        .collect()
+    }
+
+    fn record(&self, r: Ray, t: f32) -> HitRecord {
+        let p = self.frame.coords(r.at(t) - self.center);
+        // The normal points away from the nearest point on the core circle.
+        let ring = Vec3::new(p.x(), p.y(), 0.0);
+        let core = self.major_radius * unit(ring);
+        let normal = unit(p - core);
+        let mut rec = HitRecord {
+            p: r.at(t),
+            t,
+            material: self.material.clone(),
+            normal: Vec3::default(),
+            u: (p.y().atan2(p.x()) / (2.0 * PI)).rem_euclid(1.0),
+            v: (p.z().atan2(ring.length() - self.major_radius) / (2.0 * PI)).rem_euclid(1.0),
+            front_face: false,
+        };
+        rec.set_face_normal(r, self.frame.local(normal));
+        rec
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let t = *self.crossings(r, t_min, t_max).first()?;
+        Some(self.record(r, t))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = self.minor_radius + 1e-4;
+        let half = rim_extent(self.frame.w(), self.major_radius) + Vec3::new(r, r, r);
+        Some(Aabb::new(self.center - half, self.center + half))
+    }
+
+    fn is_closed(&self) -> bool {
+        true
+    }
+
+    fn intervals(&self, r: Ray) -> Vec<Interval> {
+        let crossings = self.crossings(r, f32::NEG_INFINITY, f32::INFINITY);
+        pair_crossings(crossings.into_iter().map(|t| self.record(r, t)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn ring() -> Torus {
+        // Lying flat, a tube of radius 0.5 round a circle of radius 2.
+        Torus::new(
+            Point3::new(0., 0., -10.),
+            Vec3::new(0., 1., 0.),
+            2.,
+            0.5,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn hits_the_tube_not_the_hole() {
+        let torus = ring();
+        // Across the middle: outer wall, inner wall, inner wall, outer wall.
+        let across = Ray::new(Point3::new(-100., 0., -10.), Vec3::new(1., 0., 0.));
+        let rec = torus.hit(across, 0., f32::INFINITY).unwrap();
+        assert!((rec.p - Point3::new(-2.5, 0., -10.)).length() < 1e-3);
+        assert!((rec.normal - Vec3::new(-1., 0., 0.)).length() < 1e-4);
+        assert!(rec.front_face);
+        let rec = torus.hit(across, 97.6, f32::INFINITY).unwrap();
+        assert!((rec.p.x() + 1.5).abs() < 1e-3);
+        assert!(!rec.front_face);
+
+        // Down through the hole and down onto the tube.
+        let down = |x| Ray::new(Point3::new(x, 5., -10.), Vec3::new(0., -2., 0.));
+        assert!(torus.hit(down(0.), 0., f32::INFINITY).is_none());
+        let rec = torus.hit(down(2.), 0., f32::INFINITY).unwrap();
+        assert!((rec.t - 2.25).abs() < 1e-3);
+        assert!((rec.normal - Vec3::new(0., 1., 0.)).length() < 1e-3);
+        assert!((rec.v - 0.25).abs() < 1e-3);
+        assert!(torus.hit(down(2.6), 0., f32::INFINITY).is_none());
+    }
+
+    #[test]
+    fn grazing_rays_land_on_the_surface() {
+        crate::rtweekend::seed_rng(8);
+        let torus = ring();
+        let bbox = torus.bounding_box().unwrap();
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let target = Point3::new(0., 0., -10.) + 2.6 * Vec3::random_in_range(-1., 1.);
+            let origin = Point3::new(0., 0., -10.) + 30. * unit(Vec3::random_in_range(-1., 1.));
+            let r = Ray::new(origin, target - origin);
+            if let Some(rec) = torus.hit(r, 0.001, f32::INFINITY) {
+                hits += 1;
+                let p = torus.frame.coords(rec.p - torus.center);
+                let rho = (p.x() * p.x() + p.y() * p.y()).sqrt();
+                let distance = ((rho - 2.).powi(2) + p.z() * p.z()).sqrt();
+                assert!((distance - 0.5).abs() < 1e-3, "{:?}", rec.p);
+                assert!(bbox.hit(r, 0.001, f32::INFINITY));
+            }
+        }
+        assert!(hits > 200);
+    }
+}
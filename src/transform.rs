@@ -0,0 +1,274 @@
+//! Affine transforms for placing objects.
+
+use crate::aabb::Aabb;
+use crate::vec3::*;
+
+/// A row-major 4x4 matrix acting on column vectors.
+pub type Matrix4 = [[f32; 4]; 4];
+
+const IDENTITY: Matrix4 = [
+    [1., 0., 0., 0.],
+    [0., 1., 0., 0.],
+    [0., 0., 1., 0.],
+    [0., 0., 0., 1.],
+];
+
+/// An invertible affine transform, kept together with its inverse so
+/// neither ever has to be recomputed while rendering.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    m: Matrix4,
+    inv: Matrix4,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self {
+            m: IDENTITY,
+            inv: IDENTITY,
+        }
+    }
+
+    /// Any affine matrix (the bottom row must be `[0, 0, 0, 1]`), or `None`
+    /// if it cannot be inverted.
+    pub fn from_matrix(m: Matrix4) -> Option<Self> {
+        if m[3] != [0., 0., 0., 1.] {
+            return None;
+        }
+        Some(Self { m, inv: invert(m)? })
+    }
+
+    pub fn translate(offset: Vec3) -> Self {
+        let mut m = IDENTITY;
+        let mut inv = IDENTITY;
+        for i in 0..3 {
+            m[i][3] = offset[i];
+            inv[i][3] = -offset[i];
+        }
+        Self { m, inv }
+    }
+
+    /// Stretch by `factors` along the axes; none of them may be zero.
+    pub fn scale(factors: Vec3) -> Self {
+        assert!(
+            factors.x() != 0.0 && factors.y() != 0.0 && factors.z() != 0.0,
+            "scaling by zero cannot be undone"
+        );
+        let mut m = IDENTITY;
+        let mut inv = IDENTITY;
+        for i in 0..3 {
+            m[i][i] = factors[i];
+            inv[i][i] = 1.0 / factors[i];
+        }
+        Self { m, inv }
+    }
+
+    /// Turn `degrees` anticlockwise about `axis`, looking down it towards
+    /// the origin.
+    pub fn rotate(axis: Vec3, degrees: f32) -> Self {
+        let a = unit(axis);
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let k = 1.0 - cos;
+        let mut m = IDENTITY;
+        m[0][..3].copy_from_slice(&[
+            cos + a.x() * a.x() * k,
+            a.x() * a.y() * k - a.z() * sin,
+            a.x() * a.z() * k + a.y() * sin,
+        ]);
+        m[1][..3].copy_from_slice(&[
+            a.y() * a.x() * k + a.z() * sin,
+            cos + a.y() * a.y() * k,
+            a.y() * a.z() * k - a.x() * sin,
+        ]);
+        m[2][..3].copy_from_slice(&[
+            a.z() * a.x() * k - a.y() * sin,
+            a.z() * a.y() * k + a.x() * sin,
+            cos + a.z() * a.z() * k,
+        ]);
+        // Rotations are orthogonal: the inverse is the transpose.
+        let mut inv = IDENTITY;
+        for (i, row) in inv.iter_mut().enumerate().take(3) {
+            for (j, value) in row.iter_mut().enumerate().take(3) {
+                *value = m[j][i];
+            }
+        }
+        Self { m, inv }
+    }
+
+    /// This transform followed by `next`.
+    pub fn then(&self, next: &Transform) -> Self {
+        Self {
+            m: multiply(&next.m, &self.m),
+            inv: multiply(&self.inv, &next.inv),
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            m: self.inv,
+            inv: self.m,
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.m
+    }
+
+    pub fn point(&self, p: Point3) -> Point3 {
+        self.vector(p) + Vec3::new(self.m[0][3], self.m[1][3], self.m[2][3])
+    }
+
+    /// A direction: translation does not apply.
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        let row = |r: &[f32; 4]| r[0] * v.x() + r[1] * v.y() + r[2] * v.z();
+        Vec3::new(row(&self.m[0]), row(&self.m[1]), row(&self.m[2]))
+    }
+
+    /// A surface normal, which goes through the inverse transpose to stay
+    /// perpendicular to the transformed surface. Not normalised.
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        let column =
+            |c: usize| self.inv[0][c] * n.x() + self.inv[1][c] * n.y() + self.inv[2][c] * n.z();
+        Vec3::new(column(0), column(1), column(2))
+    }
+
+    /// The axis-aligned box around the transformed corners of `bbox`.
+    pub fn bounding_box(&self, bbox: Aabb) -> Aabb {
+        let (lo, hi) = (bbox.min(), bbox.max());
+        let mut min = Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+        let mut max = -min;
+        for corner in 0..8 {
+            let p = self.point(Point3::new(
+                if corner & 1 == 0 { lo.x() } else { hi.x() },
+                if corner & 2 == 0 { lo.y() } else { hi.y() },
+                if corner & 4 == 0 { lo.z() } else { hi.z() },
+            ));
+            min = Point3::new(min.x().min(p.x()), min.y().min(p.y()), min.z().min(p.z()));
+            max = Point3::new(max.x().max(p.x()), max.y().max(p.y()), max.z().max(p.z()));
+        }
+        Aabb::new(min, max)
+    }
+
+    /// Whether the transform keeps angles, i.e. is a rotation, reflection
+    /// and uniform scale plus a translation. Solid angles then survive it.
+    pub fn is_similarity(&self) -> bool {
+        let columns = [0, 1, 2].map(|c| Vec3::new(self.m[0][c], self.m[1][c], self.m[2][c]));
+        let scale = columns[0].length_squared();
+        let close = |a: f32, b: f32| (a - b).abs() <= 1e-4 * scale;
+        columns.iter().all(|c| close(c.length_squared(), scale))
+            && close(dot(columns[0], columns[1]), 0.0)
+            && close(dot(columns[1], columns[2]), 0.0)
+            && close(dot(columns[2], columns[0]), 0.0)
+    }
+}
+
+fn multiply(a: &Matrix4, b: &Matrix4) -> Matrix4 {
+    let mut m = [[0.0; 4]; 4];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    m
+}
+
+/// Gauss-Jordan elimination with partial pivoting, in double precision.
+fn invert(m: Matrix4) -> Option<Matrix4> {
+    let mut a = m.map(|row| row.map(f64::from));
+    let mut inv = IDENTITY.map(|row| row.map(f64::from));
+    for col in 0..4 {
+        let pivot = (col..4)
+            .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+            .unwrap();
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        inv.swap(col, pivot);
+        let scale = 1.0 / a[col][col];
+        for j in 0..4 {
+            a[col][j] *= scale;
+            inv[col][j] *= scale;
+        }
+        for row in 0..4 {
+            if row != col {
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+    }
+    Some(inv.map(|row| row.map(|v| v as f32)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        (a - b).length() < 1e-5
+    }
+
+    #[test]
+    fn compose_and_invert() {
+        let quarter = Transform::rotate(Vec3::new(0., 1., 0.), 90.);
+        assert!(close(
+            quarter.point(Point3::new(1., 0., 0.)),
+            Point3::new(0., 0., -1.)
+        ));
+
+        let t = Transform::scale(Vec3::new(2., 1., 1.))
+            .then(&quarter)
+            .then(&Transform::translate(Vec3::new(0., 3., 0.)));
+        let p = Point3::new(1., 2., 3.);
+        assert!(close(t.point(p), Point3::new(3., 5., -2.)));
+        assert!(close(t.inverse().point(t.point(p)), p));
+        assert!(close(
+            t.vector(Vec3::new(1., 0., 0.)),
+            Vec3::new(0., 0., -2.)
+        ));
+
+        // Inverting the matrix from scratch agrees with tracking it.
+        let general = Transform::from_matrix(*t.matrix()).unwrap();
+        for (a, b) in general.inverse().matrix().iter().zip(t.inverse().matrix()) {
+            for (x, y) in a.iter().zip(b) {
+                assert!((x - y).abs() < 1e-5);
+            }
+        }
+        let mut flat = IDENTITY;
+        flat[2][2] = 0.;
+        assert!(Transform::from_matrix(flat).is_none());
+
+        assert!(quarter
+            .then(&Transform::scale(Vec3::new(3., 3., 3.)))
+            .is_similarity());
+        assert!(!t.is_similarity());
+    }
+
+    #[test]
+    fn normals_stay_perpendicular() {
+        let t = Transform::scale(Vec3::new(1., 4., 1.))
+            .then(&Transform::rotate(Vec3::new(1., 1., 0.), 30.));
+        // The plane x + y = 0 contains both tangents.
+        let n = t.normal(Vec3::new(1., 1., 0.));
+        for tangent in [Vec3::new(1., -1., 0.), Vec3::new(0., 0., 1.)] {
+            assert!(dot(n, t.vector(tangent)).abs() < 1e-5);
+        }
+
+        let bbox = Transform::rotate(Vec3::new(0., 0., 1.), 45.).bounding_box(Aabb::new(
+            Point3::new(-1., -1., 0.),
+            Point3::new(1., 1., 0.),
+        ));
+        let half = 2f32.sqrt();
+        assert!(close(bbox.min(), Point3::new(-half, -half, 0.)));
+        assert!(close(bbox.max(), Point3::new(half, half, 0.)));
+    }
+}
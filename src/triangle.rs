@@ -0,0 +1,242 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::*;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::rtweekend::random;
+use crate::vec3::*;
+
+#[derive(Clone)]
+pub struct Triangle {
+    vertices: [Point3; 3],
+    material: Arc<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(v0: Point3, v1: Point3, v2: Point3, material: Arc<dyn Material>) -> Self {
+        Self {
+            vertices: [v0, v1, v2],
+            material,
+        }
+    }
+
+    /// Solid angle density of uniform area sampling picking `r.at(t)`, seen
+    /// from the ray's origin.
+    fn pdf_at(&self, r: Ray, t: f32) -> f32 {
+        let [v0, v1, v2] = self.vertices;
+        let normal = cross(v1 - v0, v2 - v0);
+        let area = 0.5 * normal.length();
+        let to_point = t * r.direction();
+        let cosine = dot(unit(normal), unit(to_point)).abs();
+        if area == 0.0 || cosine == 0.0 {
+            return 0.0;
+        }
+        to_point.length_squared() / (cosine * area)
+    }
+}
+
+/// Möller–Trumbore ray/triangle intersection.
+///
+/// Returns the ray parameter together with the barycentric coordinates
+/// `(b1, b2)` of the hit point, weighting `v1` and `v2` respectively.
+pub fn intersect_triangle(
+    r: Ray,
+    vertices: [Point3; 3],
+    t_min: f32,
+    t_max: f32,
+) -> Option<(f32, f32, f32)> {
+    let [v0, v1, v2] = vertices;
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let pvec = cross(r.direction(), edge2);
+    let det = dot(edge1, pvec);
+    // Ray is parallel to the triangle plane.
+    if det == 0.0 || !det.is_finite() {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = r.origin() - v0;
+    let b1 = dot(tvec, pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let qvec = cross(tvec, edge1);
+    let b2 = dot(r.direction(), qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = dot(edge2, qvec) * inv_det;
+    if t < t_min || t_max < t {
+        return None;
+    }
+    Some((t, b1, b2))
+}
+
+pub fn triangle_bounding_box(vertices: [Point3; 3]) -> Aabb {
+    let [v0, v1, v2] = vertices;
+    // Pad flat boxes so axis-aligned triangles still have a volume.
+    let pad = Vec3::new(1e-4, 1e-4, 1e-4);
+    let small = Point3::new(
+        v0.x().min(v1.x()).min(v2.x()),
+        v0.y().min(v1.y()).min(v2.y()),
+        v0.z().min(v1.z()).min(v2.z()),
+    );
+    let big = Point3::new(
+        v0.x().max(v1.x()).max(v2.x()),
+        v0.y().max(v1.y()).max(v2.y()),
+        v0.z().max(v1.z()).max(v2.z()),
+    );
+    Aabb::new(small - pad, big + pad)
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let (t, b1, b2) = intersect_triangle(r, self.vertices, t_min, t_max)?;
+
+        let [v0, v1, v2] = self.vertices;
+        // Without texture coordinates of its own, the triangle is textured
+        // by its barycentric coordinates.
+        let mut rec = HitRecord {
+            p: r.at(t),
+            t,
+            material: self.material.clone(),
+            normal: Vec3::default(),
+            u: b1,
+            v: b2,
+            front_face: false,
+        };
+        let outward_normal = unit(cross(v1 - v0, v2 - v0));
+        rec.set_face_normal(r, outward_normal);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(triangle_bounding_box(self.vertices))
+    }
+
+    fn sample_towards(&self, origin: Point3) -> Option<(HitRecord, f32)> {
+        // Uniform over the area, folding the far half of the square back.
+        let (mut b1, mut b2) = (random::<f32>(), random::<f32>());
+        if b1 + b2 > 1.0 {
+            (b1, b2) = (1.0 - b1, 1.0 - b2);
+        }
+        let [v0, v1, v2] = self.vertices;
+        let p = v0 + b1 * (v1 - v0) + b2 * (v2 - v0);
+
+        let r = Ray::new(origin, p - origin);
+        let pdf = self.pdf_at(r, 1.0);
+        if pdf == 0.0 || !pdf.is_finite() {
+            return None;
+        }
+        let mut rec = HitRecord {
+            p,
+            t: 1.0,
+            material: self.material.clone(),
+            normal: Vec3::default(),
+            u: b1,
+            v: b2,
+            front_face: false,
+        };
+        rec.set_face_normal(r, unit(cross(v1 - v0, v2 - v0)));
+        Some((rec, pdf))
+    }
+
+    fn pdf_towards(&self, origin: Point3, direction: Vec3) -> f32 {
+        let r = Ray::new(origin, direction);
+        match intersect_triangle(r, self.vertices, 0.0, f32::INFINITY) {
+            Some((t, _, _)) => self.pdf_at(r, t),
+            None => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn unit_triangle() -> Triangle {
+        Triangle::new(
+            Point3::new(0., 0., 0.),
+            Point3::new(1., 0., 0.),
+            Point3::new(0., 1., 0.),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn hit_inside() {
+        let r = Ray::new(Point3::new(0.25, 0.25, 1.), Vec3::new(0., 0., -1.));
+        let rec = unit_triangle().hit(r, 0.001, f32::INFINITY).unwrap();
+        assert_eq!(rec.t, 1.0);
+        assert_eq!(rec.normal, Vec3::new(0., 0., 1.));
+        assert!(rec.front_face);
+
+        let r = Ray::new(Point3::new(0.25, 0.25, -1.), Vec3::new(0., 0., 1.));
+        let rec = unit_triangle().hit(r, 0.001, f32::INFINITY).unwrap();
+        assert_eq!(rec.normal, Vec3::new(0., 0., -1.));
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn miss_outside() {
+        let r = Ray::new(Point3::new(0.75, 0.75, 1.), Vec3::new(0., 0., -1.));
+        assert!(unit_triangle().hit(r, 0.001, f32::INFINITY).is_none());
+        let r = Ray::new(Point3::new(0.25, 0.25, 1.), Vec3::new(1., 0., 0.));
+        assert!(unit_triangle().hit(r, 0.001, f32::INFINITY).is_none());
+    }
+
+    #[test]
+    fn area_sampling() {
+        crate::rtweekend::seed_rng(5);
+        let triangle = unit_triangle();
+        let origin = Point3::new(0., 0., 2.);
+        let mut solid_angle = 0.0;
+        let n = 20000;
+        for _ in 0..n {
+            let (rec, pdf) = triangle.sample_towards(origin).unwrap();
+            assert!(rec.p.z() == 0. && rec.p.x() + rec.p.y() <= 1. + 1e-6);
+            assert!(rec.front_face);
+            let expected = triangle.pdf_towards(origin, rec.p - origin);
+            assert!((pdf - expected).abs() <= 1e-4 * pdf);
+            solid_angle += 1. / pdf / n as f32;
+        }
+        // Van Oosterom and Strackee's formula for the solid angle.
+        let [a, b, c] = triangle.vertices.map(|v| v - origin);
+        let numerator = dot(a, cross(b, c)).abs();
+        let denominator = a.length() * b.length() * c.length()
+            + dot(a, b) * c.length()
+            + dot(a, c) * b.length()
+            + dot(b, c) * a.length();
+        let expected = 2. * numerator.atan2(denominator);
+        assert!((solid_angle - expected).abs() < 0.01 * expected);
+    }
+
+    #[test]
+    fn shared_edge_is_watertight() {
+        // Two triangles forming a quad; rays along the diagonal must hit one of them.
+        let a = [
+            Point3::new(0., 0., 0.),
+            Point3::new(1., 0., 0.),
+            Point3::new(1., 1., 0.),
+        ];
+        let b = [
+            Point3::new(0., 0., 0.),
+            Point3::new(1., 1., 0.),
+            Point3::new(0., 1., 0.),
+        ];
+        for i in 1..100 {
+            let s = i as f32 / 100.0;
+            let r = Ray::new(Point3::new(s, s, 1.), Vec3::new(0., 0., -1.));
+            assert!(
+                intersect_triangle(r, a, 0.0, f32::INFINITY).is_some()
+                    || intersect_triangle(r, b, 0.0, f32::INFINITY).is_some()
+            );
+        }
+    }
+}
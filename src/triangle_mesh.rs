@@ -0,0 +1,254 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::bvh::BvhNode;
+use crate::hittable::*;
+use crate::hittable_list::HittableList;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::triangle::{intersect_triangle, triangle_bounding_box};
+use crate::vec3::*;
+
+/// One face of a mesh, as indices into the shared vertex buffers.
+///
+/// Positions, normals and texture coordinates are indexed separately, the
+/// way OBJ files store them.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Face {
+    pub positions: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+}
+
+struct MeshData {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f32, f32)>,
+    faces: Vec<Face>,
+    material: Arc<dyn Material>,
+}
+
+impl MeshData {
+    fn vertices(&self, face: &Face) -> [Point3; 3] {
+        face.positions.map(|i| self.positions[i])
+    }
+}
+
+/// Indexed triangle mesh sharing its vertex, normal and UV buffers across
+/// all faces.
+///
+/// Faces that carry vertex normals are smooth shaded by interpolating them
+/// with the barycentric coordinates of the hit. A mesh without faces is
+/// never hit and has no bounding box.
+pub struct TriangleMesh {
+    data: Arc<MeshData>,
+    /// `None` for a mesh without faces.
+    bvh: Option<BvhNode>,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<(f32, f32)>,
+        faces: Vec<Face>,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        for face in faces.iter() {
+            assert!(
+                face.positions.iter().all(|&i| i < positions.len()),
+                "face position index out of range"
+            );
+            assert!(
+                face.normals.iter().flatten().all(|&i| i < normals.len()),
+                "face normal index out of range"
+            );
+            assert!(
+                face.uvs.iter().flatten().all(|&i| i < uvs.len()),
+                "face uv index out of range"
+            );
+        }
+
+        let data = Arc::new(MeshData {
+            positions,
+            normals,
+            uvs,
+            faces,
+            material,
+        });
+        let mut triangles = HittableList::new();
+        for index in 0..data.faces.len() {
+            triangles.add(Arc::new(MeshTriangle {
+                mesh: data.clone(),
+                index,
+            }));
+        }
+        let bvh = (!triangles.is_empty()).then(|| BvhNode::new(&triangles));
+        Self { data, bvh }
+    }
+
+    pub fn positions(&self) -> &[Point3] {
+        &self.data.positions
+    }
+
+    pub fn normals(&self) -> &[Vec3] {
+        &self.data.normals
+    }
+
+    pub fn uvs(&self) -> &[(f32, f32)] {
+        &self.data.uvs
+    }
+
+    pub fn faces(&self) -> &[Face] {
+        &self.data.faces
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.faces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.faces.is_empty()
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        self.bvh.as_ref()?.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bvh.as_ref()?.bounding_box()
+    }
+
+    fn occluded(&self, r: Ray, t_min: f32, t_max: f32) -> bool {
+        self.bvh
+            .as_ref()
+            .is_some_and(|bvh| bvh.occluded(r, t_min, t_max))
+    }
+}
+
+/// A single face of a `TriangleMesh`, as stored in the mesh's BVH.
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    index: usize,
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let face = &self.mesh.faces[self.index];
+        let vertices = self.mesh.vertices(face);
+        let (t, b1, b2) = intersect_triangle(r, vertices, t_min, t_max)?;
+
+        let [v0, v1, v2] = vertices;
+        let b0 = 1.0 - b1 - b2;
+        let (u, v) = match face.uvs {
+            Some(indices) => {
+                let [(u0, v0), (u1, v1), (u2, v2)] = indices.map(|i| self.mesh.uvs[i]);
+                (b0 * u0 + b1 * u1 + b2 * u2, b0 * v0 + b1 * v1 + b2 * v2)
+            }
+            None => (b1, b2),
+        };
+        let mut rec = HitRecord {
+            p: r.at(t),
+            t,
+            material: self.mesh.material.clone(),
+            normal: Vec3::default(),
+            u,
+            v,
+            front_face: false,
+        };
+        let geometric_normal = unit(cross(v1 - v0, v2 - v0));
+        rec.set_face_normal(r, geometric_normal);
+
+        if let Some([i0, i1, i2]) = face.normals {
+            let normals = &self.mesh.normals;
+            let mut shading_normal = unit(b0 * normals[i0] + b1 * normals[i1] + b2 * normals[i2]);
+            // Keep the shading normal on the same side as the winding order.
+            if dot(shading_normal, geometric_normal) < 0.0 {
+                shading_normal = -shading_normal;
+            }
+            if !shading_normal.x().is_nan() {
+                rec.normal = if rec.front_face {
+                    shading_normal
+                } else {
+                    -shading_normal
+                };
+            }
+        }
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let face = &self.mesh.faces[self.index];
+        Some(triangle_bounding_box(self.mesh.vertices(face)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn interpolates_vertex_normals() {
+        let positions = vec![
+            Point3::new(0., 0., 0.),
+            Point3::new(1., 0., 0.),
+            Point3::new(0., 1., 0.),
+            Point3::new(1., 1., 0.),
+        ];
+        let normals = vec![unit(Vec3::new(-1., 0., 1.)), unit(Vec3::new(1., 0., 1.))];
+        let faces = vec![
+            Face {
+                positions: [0, 1, 2],
+                normals: Some([0, 1, 0]),
+                uvs: None,
+            },
+            Face {
+                positions: [1, 3, 2],
+                normals: None,
+                uvs: None,
+            },
+        ];
+        let mesh = TriangleMesh::new(
+            positions,
+            normals,
+            Vec::new(),
+            faces,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        assert_eq!(mesh.len(), 2);
+
+        // Halfway between the vertices with opposite normals the shading normal is +z.
+        let r = Ray::new(Point3::new(0.5, 0.25, 1.), Vec3::new(0., 0., -1.));
+        let rec = mesh.hit(r, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.normal - Vec3::new(0., 0., 1.)).length() < 1e-6);
+
+        let r = Ray::new(Point3::new(0.1, 0.1, 1.), Vec3::new(0., 0., -1.));
+        let rec = mesh.hit(r, 0.001, f32::INFINITY).unwrap();
+        assert!(rec.normal.x() < 0.0);
+
+        // Flat shaded face falls back to the geometric normal.
+        let r = Ray::new(Point3::new(0.9, 0.9, 1.), Vec3::new(0., 0., -1.));
+        let rec = mesh.hit(r, 0.001, f32::INFINITY).unwrap();
+        assert_eq!(rec.normal, Vec3::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn empty_mesh() {
+        let mesh = TriangleMesh::new(
+            vec![Point3::new(0., 0., 0.)],
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        assert!(mesh.is_empty());
+        assert!(mesh.bounding_box().is_none());
+        let r = Ray::new(Point3::new(0., 0., 1.), Vec3::new(0., 0., -1.));
+        assert!(mesh.hit(r, 0.001, f32::INFINITY).is_none());
+        assert!(!mesh.occluded(r, 0.001, f32::INFINITY));
+    }
+}
@@ -1,4 +1,7 @@
+mod onb;
 mod utils;
+#[allow(clippy::module_inception)]
 mod vec3;
+pub use onb::*;
 pub use utils::*;
 pub use vec3::*;
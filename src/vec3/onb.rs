@@ -0,0 +1,69 @@
+use super::vec3::*;
+
+/// An orthonormal basis around a unit vector `w`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Onb {
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+}
+
+impl Onb {
+    /// `w` must be of unit length.
+    pub fn new(w: Vec3) -> Self {
+        // Duff et al., "Building an Orthonormal Basis, Revisited".
+        let sign = 1.0f32.copysign(w.z());
+        let a = -1.0 / (sign + w.z());
+        let b = w.x() * w.y() * a;
+        let u = Vec3::new(1.0 + sign * w.x() * w.x() * a, sign * b, -sign * w.x());
+        let v = Vec3::new(b, sign + w.y() * w.y() * a, -w.y());
+        Self { u, v, w }
+    }
+
+    pub fn u(&self) -> Vec3 {
+        self.u
+    }
+
+    pub fn v(&self) -> Vec3 {
+        self.v
+    }
+
+    pub fn w(&self) -> Vec3 {
+        self.w
+    }
+
+    /// The vector with coordinates `a` in this basis.
+    pub fn local(&self, a: Vec3) -> Vec3 {
+        a.x() * self.u + a.y() * self.v + a.z() * self.w
+    }
+
+    /// The coordinates of `a` in this basis; the inverse of `local`.
+    pub fn coords(&self, a: Vec3) -> Vec3 {
+        Vec3::new(dot(a, self.u), dot(a, self.v), dot(a, self.w))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn basis_is_orthonormal() {
+        for w in [
+            Vec3::new(0., 0., 1.),
+            Vec3::new(0., 0., -1.),
+            unit(Vec3::new(1., -2., 0.5)),
+            unit(Vec3::new(-0.3, 0.1, -5.)),
+        ] {
+            let onb = Onb::new(w);
+            for (a, b) in [(onb.u(), onb.v()), (onb.v(), onb.w()), (onb.w(), onb.u())] {
+                assert!(dot(a, b).abs() < 1e-6);
+                assert!((a.length() - 1.).abs() < 1e-6);
+            }
+            assert!((cross(onb.u(), onb.v()) - w).length() < 1e-6);
+            assert!((onb.local(Vec3::new(0., 0., 2.)) - 2. * w).length() < 1e-6);
+            let a = Vec3::new(0.3, -1., 2.);
+            assert!((onb.local(onb.coords(a)) - a).length() < 1e-5);
+        }
+    }
+}
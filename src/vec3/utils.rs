@@ -15,6 +15,15 @@ pub fn random_unit_vector() -> Vec3 {
     unit(random_in_unit_sphere())
 }
 
+/// A unit vector about +z with density cos(theta) / pi, for use with `Onb`.
+pub fn random_cosine_direction() -> Vec3 {
+    let r1: f32 = random();
+    let r2: f32 = random();
+    let phi = 2.0 * std::f32::consts::PI * r1;
+    let r = r2.sqrt();
+    Vec3::new(phi.cos() * r, phi.sin() * r, (1.0 - r2).max(0.0).sqrt())
+}
+
 pub fn random_in_hemisphere(normal: &Vec3) -> Vec3 {
     let in_unit_sphere = random_in_unit_sphere();
     if dot(in_unit_sphere, *normal) > 0.0
@@ -1,12 +1,11 @@
 use std::ops::*;
 
-use rand::random;
-
-use crate::rtweekend::random_float;
+use crate::rtweekend::{random, random_float};
 
 pub type Point3 = Vec3;
 pub type Color = Vec3;
 
+/// Three-component vector, also used for points and RGB colors.
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct Vec3 {
     x: f32,
@@ -87,6 +86,19 @@ impl Vec3 {
     }
 }
 
+impl Index<usize> for Vec3 {
+    type Output = f32;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        match i {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 index out of range: {}", i),
+        }
+    }
+}
+
 impl Neg for Vec3 {
     type Output = Vec3;
 